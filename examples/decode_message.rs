@@ -14,6 +14,10 @@ struct Args {
 
     #[clap(long)]
     compact: bool,
+
+    /// Prints only the data in the message body selected by the query (e.g., `1.2[*].5`).
+    #[clap(long)]
+    query: Option<String>,
 }
 
 fn main() {
@@ -24,8 +28,14 @@ fn main() {
     } else {
         track_try_unwrap!(Message::binary_decode(&mut input))
     };
-    println!(
-        "{}",
-        track_try_unwrap!(serdeconv::to_json_string_pretty(&message))
-    );
+    if let Some(query) = args.query {
+        for data in track_try_unwrap!(thrift_codec::data::query(message.body(), &query)) {
+            println!("{}", track_try_unwrap!(serdeconv::to_json_string(&data)));
+        }
+    } else {
+        println!(
+            "{}",
+            track_try_unwrap!(serdeconv::to_json_string_pretty(&message))
+        );
+    }
 }
@@ -0,0 +1,317 @@
+//! Canonical encoding.
+//!
+//! Logically equal values can be encoded to different binaries
+//! (e.g., the order of the fields of a struct depends on how the struct is constructed).
+//! The canonical form defined in this module removes such ambiguities,
+//! so that the encoded binaries of canonical values are byte-identical if the values are equal.
+//! Conversely, different values are encoded to different binaries, with one exception:
+//! the compact protocol encodes every empty map as the single byte `0x00`
+//! regardless of its key and value kinds.
+//!
+//! The canonical form of a value is obtained as follows:
+//!
+//! - Every `NaN` is replaced with the quiet `NaN` which bits is `0x7FF8_0000_0000_0000`.
+//! - The fields of structs are sorted by their identifiers.
+//! - The elements of sets are sorted and deduplicated.
+//! - The entries of maps are sorted by their keys.
+//!
+//! Values which have duplicate field identifiers or duplicate map keys are rejected
+//! because there is no canonical way to merge them.
+//! Note that `-0.0` and `0.0` are different values and are not normalized.
+//!
+//! # Examples
+//!
+//! ```
+//! use thrift_codec::BinaryEncode;
+//! use thrift_codec::canonical::canonical_hash;
+//! use thrift_codec::data::{Field, Set, Struct};
+//!
+//! let a = Struct::new(vec![Field::new(1, Set::from(vec![1, 2])), Field::new(2, "foo")]);
+//! let b = Struct::new(vec![Field::new(2, "foo"), Field::new(1, Set::from(vec![2, 1, 2]))]);
+//!
+//! let mut buf_a = Vec::new();
+//! let mut buf_b = Vec::new();
+//! a.binary_encode_canonical(&mut buf_a).unwrap();
+//! b.binary_encode_canonical(&mut buf_b).unwrap();
+//! assert_eq!(buf_a, buf_b);
+//! assert_eq!(canonical_hash(&a).unwrap(), canonical_hash(&b).unwrap());
+//!
+//! let c = Struct::new(vec![Field::new(1, 1), Field::new(1, 2)]);
+//! assert!(c.binary_encode_canonical(&mut Vec::new()).is_err());
+//! ```
+use crate::data::{Data, DataMut, DataRef, Elements, Map, Struct};
+use crate::message::Message;
+use crate::{BinaryEncode, CompactEncode, ErrorKind, Result};
+use std::cmp::Ordering;
+use std::io::Write;
+
+const CANONICAL_NAN_BITS: u64 = 0x7FF8_0000_0000_0000;
+
+/// This trait allows to convert values into the canonical form.
+pub trait CanonicalForm: Sized {
+    /// Converts this value into the canonical form.
+    ///
+    /// # Errors
+    ///
+    /// If this value contains duplicate field identifiers or duplicate map keys,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    fn into_canonical_form(self) -> Result<Self>;
+}
+impl CanonicalForm for Data {
+    fn into_canonical_form(mut self) -> Result<Self> {
+        track!(normalize(self.as_mut()))?;
+        self.canonicalize();
+        track!(check_map_keys(self.as_ref()))?;
+        Ok(self)
+    }
+}
+impl CanonicalForm for Struct {
+    fn into_canonical_form(mut self) -> Result<Self> {
+        track!(normalize(DataMut::Struct(&mut self)))?;
+        self.canonicalize();
+        track!(check_map_keys(DataRef::Struct(&self)))?;
+        Ok(self)
+    }
+}
+impl CanonicalForm for Message {
+    fn into_canonical_form(self) -> Result<Self> {
+        let body = track!(self.body().clone().into_canonical_form())?;
+        Ok(Message::new(
+            self.method_name(),
+            self.kind(),
+            self.sequence_id(),
+            body,
+        ))
+    }
+}
+
+/// A value in the canonical form.
+///
+/// The encoded binaries of this value are canonical.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Canonical<T> {
+    value: T,
+}
+impl<T: CanonicalForm> Canonical<T> {
+    /// Makes a new `Canonical` instance by converting `value` into the canonical form.
+    ///
+    /// # Errors
+    ///
+    /// If `value` contains duplicate field identifiers or duplicate map keys,
+    /// this function will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn new(value: T) -> Result<Self> {
+        let value = track!(value.into_canonical_form())?;
+        Ok(Canonical { value })
+    }
+
+    /// Returns the reference to the canonical value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Takes ownership of the canonical value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+impl<T: BinaryEncode> Canonical<T> {
+    /// Returns the 64-bit [FNV-1a] hash value of the binary encoded bytes of this value.
+    ///
+    /// The hash value is stable across platforms and versions of this crate,
+    /// but it is not cryptographically secure (use the canonical binaries themselves for signatures).
+    ///
+    /// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+    ///
+    /// # Errors
+    ///
+    /// If this value can not be encoded (e.g., a binary is too large),
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn canonical_hash(&self) -> Result<u64> {
+        let mut hasher = Fnv1a64::new();
+        track!(self.value.binary_encode(&mut hasher))?;
+        Ok(hasher.finish())
+    }
+}
+impl<T: BinaryEncode> BinaryEncode for Canonical<T> {
+    fn binary_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
+        track!(self.value.binary_encode(writer))
+    }
+}
+impl<T: CompactEncode> CompactEncode for Canonical<T> {
+    fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
+        track!(self.value.compact_encode(writer))
+    }
+}
+
+/// Returns the canonical hash value of `value`.
+///
+/// This is equivalent to `Canonical::new(value.clone())?.canonical_hash()`.
+pub fn canonical_hash<T>(value: &T) -> Result<u64>
+where
+    T: CanonicalForm + BinaryEncode + Clone,
+{
+    let canonical = track!(Canonical::new(value.clone()))?;
+    track!(canonical.canonical_hash())
+}
+
+fn normalize(data: DataMut<'_>) -> Result<()> {
+    match data {
+        DataMut::Double(v) if v.is_nan() => *v = f64::from_bits(CANONICAL_NAN_BITS),
+        DataMut::Struct(v) => {
+            let duplicates = v.duplicate_ids();
+            track_assert!(
+                duplicates.is_empty(),
+                ErrorKind::InvalidInput,
+                "Duplicate field identifiers: {:?}",
+                duplicates
+            );
+            for field in v.iter_mut() {
+                track!(normalize(field.data_mut().as_mut()))?;
+            }
+        }
+        DataMut::Map(v) => {
+            let (keys, values) = v.keys_and_values_mut();
+            track!(normalize_elements(keys))?;
+            track!(normalize_elements(values))?;
+        }
+        DataMut::Set(v) => track!(normalize_elements(v))?,
+        DataMut::List(v) => track!(normalize_elements(v))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn normalize_elements(elements: &mut Elements) -> Result<()> {
+    for e in elements.iter_mut() {
+        track!(normalize(e))?;
+    }
+    Ok(())
+}
+
+fn check_map_keys(data: DataRef<'_>) -> Result<()> {
+    match data {
+        DataRef::Struct(v) => {
+            for field in v.fields() {
+                track!(check_map_keys(field.data().as_ref()))?;
+            }
+        }
+        DataRef::Map(v) => {
+            track!(check_sorted_map_keys(v))?;
+            for (k, v) in v.iter() {
+                track!(check_map_keys(k))?;
+                track!(check_map_keys(v))?;
+            }
+        }
+        DataRef::Set(v) => track!(check_map_keys_of_elements(v))?,
+        DataRef::List(v) => track!(check_map_keys_of_elements(v))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn check_map_keys_of_elements(elements: &Elements) -> Result<()> {
+    for e in elements.iter() {
+        track!(check_map_keys(e))?;
+    }
+    Ok(())
+}
+
+fn check_sorted_map_keys(map: &Map) -> Result<()> {
+    let mut keys = map.iter().map(|(k, _)| k);
+    if let Some(mut prev) = keys.next() {
+        for k in keys {
+            track_assert_ne!(
+                prev.total_cmp(&k),
+                Ordering::Equal,
+                ErrorKind::InvalidInput,
+                "Duplicate map key: {:?}",
+                k
+            );
+            prev = k;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub(crate) struct Fnv1a64(u64);
+impl Fnv1a64 {
+    pub(crate) fn new() -> Self {
+        Fnv1a64(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+impl Write for Fnv1a64 {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &b in buf {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataKind, Field, List, Set};
+
+    #[test]
+    fn canonical_encoding_works() {
+        let nan = f64::from_bits(0xFFF0_0000_0000_0001);
+        let a = Struct::new(vec![
+            Field::new(3, List::from(vec![nan, 1.0])),
+            Field::new(
+                1,
+                Map::new(vec![(Set::from(vec![2, 1]), 1), (Set::from(vec![3]), 2)].into_iter()),
+            ),
+        ]);
+        let b = Struct::new(vec![
+            Field::new(
+                1,
+                Map::new(vec![(Set::from(vec![3]), 2), (Set::from(vec![1, 2, 2]), 1)].into_iter()),
+            ),
+            Field::new(3, List::from(vec![f64::NAN, 1.0])),
+        ]);
+        assert_ne!(a, b);
+
+        let a = track_try_unwrap!(Canonical::new(a));
+        let b = track_try_unwrap!(Canonical::new(b));
+        assert_eq!(a, b);
+        assert_eq!(a.get().fields()[0].id(), 1);
+        assert_eq!(
+            track_try_unwrap!(a.canonical_hash()),
+            track_try_unwrap!(b.canonical_hash())
+        );
+
+        let (mut buf_a, mut buf_b) = (Vec::new(), Vec::new());
+        track_try_unwrap!(a.compact_encode(&mut buf_a));
+        track_try_unwrap!(b.compact_encode(&mut buf_b));
+        assert_eq!(buf_a, buf_b);
+    }
+
+    #[test]
+    fn duplicates_are_rejected() {
+        let s = Struct::new(vec![Field::new(1, 1), Field::new(1, 1)]);
+        assert_eq!(
+            *Canonical::new(s).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        let mut map = Map::empty_of(DataKind::Double, DataKind::I32);
+        track_try_unwrap!(map.insert(f64::NAN, 1));
+        track_try_unwrap!(map.insert(-f64::NAN, 2));
+        let data = Data::from(List::from(vec![map]));
+        assert_eq!(
+            *Canonical::new(data).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
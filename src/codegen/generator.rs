@@ -0,0 +1,1464 @@
+use crate::codegen::Module;
+use crate::idl::{self, ConstValue, Definition, FieldType, Requiredness};
+use crate::{ErrorKind, Result};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+macro_rules! w {
+    ($w:expr) => {
+        $w.line("")
+    };
+    ($w:expr, $($arg:tt)*) => {
+        $w.line(&format!($($arg)*))
+    };
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+pub fn generate(modules: &[Module]) -> Result<String> {
+    let mut generator = Generator {
+        modules,
+        paths: modules.iter().map(module_path).collect(),
+        boxed_fields: HashSet::new(),
+    };
+    generator.boxed_fields = track!(generator.recursive_fields())?;
+    let mut tree = ModuleTree::default();
+    for (i, path) in generator.paths.iter().enumerate() {
+        let node = path.iter().fold(&mut tree, |node, name| {
+            node.children.entry(name.clone()).or_default()
+        });
+        track_assert!(
+            node.module.is_none(),
+            ErrorKind::InvalidInput,
+            "Module path conflict: {}",
+            path.join("::")
+        );
+        node.module = Some(i);
+    }
+
+    let mut w = Writer::default();
+    w!(w, "// This file is generated by thrift_codec. DO NOT EDIT.");
+    track!(generator.emit_tree(&mut w, &tree))?;
+    Ok(w.out)
+}
+
+#[derive(Debug, Default)]
+struct ModuleTree {
+    children: BTreeMap<String, ModuleTree>,
+    module: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Writer {
+    out: String,
+    indent: usize,
+}
+impl Writer {
+    fn line(&mut self, s: &str) {
+        if s.starts_with('}') || s.starts_with(')') {
+            self.indent -= 1;
+        }
+        if !s.is_empty() {
+            for _ in 0..self.indent {
+                self.out.push_str("    ");
+            }
+        }
+        self.out.push_str(s);
+        self.out.push('\n');
+        if s.ends_with('{') || s.ends_with('(') {
+            self.indent += 1;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct StructSpec {
+    name: String,
+    fields: Vec<FieldSpec>,
+    orderable: bool,
+}
+
+#[derive(Debug)]
+struct FieldSpec {
+    id: i16,
+    ident: String,
+    rust_type: String,
+    optional: bool,
+    default: Option<String>,
+}
+impl FieldSpec {
+    fn local(&self) -> String {
+        if self.ident == "data" || self.ident == "field" {
+            format!("{}_", self.ident)
+        } else {
+            self.ident.clone()
+        }
+    }
+
+    fn full_type(&self) -> String {
+        if self.optional {
+            format!("Option<{}>", self.rust_type)
+        } else {
+            self.rust_type.clone()
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Generator<'a> {
+    modules: &'a [Module],
+    paths: Vec<Vec<String>>,
+
+    // The fields to be boxed, identified by `(module, struct name, field id)`.
+    boxed_fields: HashSet<(usize, String, i16)>,
+}
+impl<'a> Generator<'a> {
+    /// Returns the fields which close cycles in the graph of the struct types
+    /// (i.e., the structs, unions and exceptions) and the types directly embedded in them.
+    ///
+    /// Boxing these fields makes every generated type have a finite size.
+    fn recursive_fields(&self) -> Result<HashSet<(usize, String, i16)>> {
+        let mut visited = HashMap::new();
+        let mut boxed = HashSet::new();
+        for module in 0..self.modules.len() {
+            for definition in &self.modules[module].document.definitions {
+                if let Some(s) = struct_type(definition) {
+                    track!(self.visit_struct_type(module, s, &mut visited, &mut boxed))?;
+                }
+            }
+        }
+        Ok(boxed)
+    }
+
+    // `visited` maps each struct type to `true` while it is on the DFS stack and `false` after it is finished.
+    fn visit_struct_type(
+        &self,
+        module: usize,
+        s: &'a idl::Struct,
+        visited: &mut HashMap<(usize, &'a str), bool>,
+        boxed: &mut HashSet<(usize, String, i16)>,
+    ) -> Result<()> {
+        if visited.contains_key(&(module, s.name.as_str())) {
+            return Ok(());
+        }
+        visited.insert((module, s.name.as_str()), true);
+        for f in &s.fields {
+            let (j, ty) = track!(self.resolve_type(module, &f.field_type))?;
+            let FieldType::Named(ref name) = ty else {
+                continue;
+            };
+            let (k, definition) = track!(self.lookup(j, name))?;
+            let Some(t) = struct_type(definition) else {
+                continue;
+            };
+            match visited.get(&(k, t.name.as_str())) {
+                Some(true) => {
+                    boxed.insert((module, s.name.clone(), f.id));
+                }
+                Some(false) => {}
+                None => track!(self.visit_struct_type(k, t, visited, boxed))?,
+            }
+        }
+        visited.insert((module, s.name.as_str()), false);
+        Ok(())
+    }
+
+    fn emit_tree(&self, w: &mut Writer, tree: &ModuleTree) -> Result<()> {
+        for (name, child) in &tree.children {
+            w!(w);
+            w!(
+                w,
+                "#[allow(dead_code, missing_docs, non_upper_case_globals, unused_imports, clippy::all)]"
+            );
+            w!(w, "pub mod {} {{", name);
+            if let Some(i) = child.module {
+                track!(self.emit_module(w, i))?;
+            }
+            track!(self.emit_tree(w, child))?;
+            w!(w, "}}");
+        }
+        Ok(())
+    }
+
+    fn emit_module(&self, w: &mut Writer, module: usize) -> Result<()> {
+        w!(w, "use ::thrift_codec::data::ThriftValue;");
+        for definition in &self.modules[module].document.definitions {
+            w!(w);
+            match *definition {
+                Definition::Const(ref d) => track!(self.emit_const(w, module, d))?,
+                Definition::Typedef(ref d) => {
+                    let ty = track!(self.rust_type(module, &d.field_type))?;
+                    w!(w, "pub type {} = {};", ident(&d.name), ty);
+                }
+                Definition::Enum(ref d) => track!(self.emit_enum(w, d))?,
+                Definition::Struct(ref d) => {
+                    let spec = track!(self.struct_spec(module, d))?;
+                    self.emit_struct(w, &spec);
+                    self.emit_codec(w, &spec.name);
+                }
+                Definition::Union(ref d) => {
+                    let spec = track!(self.struct_spec(module, d))?;
+                    track!(self.emit_union(w, &spec))?;
+                    self.emit_codec(w, &spec.name);
+                }
+                Definition::Exception(ref d) => {
+                    let spec = track!(self.struct_spec(module, d))?;
+                    self.emit_struct(w, &spec);
+                    self.emit_codec(w, &spec.name);
+                    self.emit_error_impls(w, &spec.name);
+                }
+                Definition::Service(ref d) => track!(self.emit_service(w, module, d))?,
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_const(&self, w: &mut Writer, module: usize, c: &idl::Const) -> Result<()> {
+        let (_, resolved) = track!(self.resolve_type(module, &c.field_type))?;
+        let ty = track!(self.rust_type(module, &c.field_type))?;
+        match (&resolved, &c.value) {
+            (FieldType::String, ConstValue::String(s)) => {
+                w!(w, "pub const {}: &str = {:?};", ident(&c.name), s);
+            }
+            (FieldType::String, ConstValue::Identifier(_)) => {
+                let value = track!(self.const_expr(module, &c.field_type, &c.value))?;
+                let value = value.trim_end_matches(".to_owned()");
+                w!(w, "pub const {}: &str = {};", ident(&c.name), value);
+            }
+            (
+                FieldType::Bool
+                | FieldType::I8
+                | FieldType::I16
+                | FieldType::I32
+                | FieldType::I64
+                | FieldType::Double,
+                _,
+            ) => {
+                let value = track!(self.const_expr(module, &c.field_type, &c.value))?;
+                w!(w, "pub const {}: {} = {};", ident(&c.name), ty, value);
+            }
+            _ => {
+                let value = track!(self.const_expr(module, &c.field_type, &c.value))?;
+                w!(w, "pub fn {}() -> {} {{", ident(&snake_case(&c.name)), ty);
+                w!(w, "{}", value);
+                w!(w, "}}");
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_enum(&self, w: &mut Writer, e: &idl::Enum) -> Result<()> {
+        track_assert!(
+            !e.values.is_empty(),
+            ErrorKind::InvalidInput,
+            "Empty enum is not supported: {}",
+            e.name
+        );
+        let name = ident(&e.name);
+        w!(
+            w,
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]"
+        );
+        w!(w, "pub enum {} {{", name);
+        for (i, v) in e.values.iter().enumerate() {
+            if i == 0 {
+                w!(w, "#[default]");
+            }
+            w!(w, "{},", ident(&pascal_case(&v.name)));
+        }
+        w!(w, "}}");
+        w!(w, "impl {} {{", name);
+        w!(
+            w,
+            "/// Converts an integer to the corresponding value of this enum."
+        );
+        w!(w, "pub fn from_i32(value: i32) -> Option<Self> {{");
+        w!(w, "match value {{");
+        let mut seen = HashSet::new();
+        for v in &e.values {
+            if seen.insert(v.value) {
+                let variant = ident(&pascal_case(&v.name));
+                w!(w, "{} => Some({}::{}),", v.value, name, variant);
+            }
+        }
+        w!(w, "_ => None,");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w);
+        w!(w, "/// Returns the integer representation of this value.");
+        w!(w, "pub fn to_i32(self) -> i32 {{");
+        w!(w, "match self {{");
+        for v in &e.values {
+            let variant = ident(&pascal_case(&v.name));
+            w!(w, "{}::{} => {},", name, variant, v.value);
+        }
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "impl ThriftValue for {} {{", name);
+        w!(w, "fn kind() -> ::thrift_codec::data::DataKind {{");
+        w!(w, "::thrift_codec::data::DataKind::I32");
+        w!(w, "}}");
+        w!(w);
+        w!(w, "fn to_data(&self) -> ::thrift_codec::data::Data {{");
+        w!(w, "::thrift_codec::data::Data::I32(self.to_i32())");
+        w!(w, "}}");
+        w!(w);
+        w!(
+            w,
+            "fn from_data(data: ::thrift_codec::data::Data) -> ::thrift_codec::Result<Self> {{"
+        );
+        w!(w, "let value = i32::from_data(data)?;");
+        w!(w, "Self::from_i32(value).ok_or_else(|| {{");
+        w!(
+            w,
+            "::thrift_codec::codegen::runtime::unknown_enum_value({:?}, value)",
+            e.name
+        );
+        w!(w, "}})");
+        w!(w, "}}");
+        w!(w, "}}");
+        Ok(())
+    }
+
+    fn emit_struct(&self, w: &mut Writer, spec: &StructSpec) {
+        let has_default = spec.fields.iter().any(|f| f.default.is_some());
+        let mut derives = vec!["Debug", "Clone", "PartialEq"];
+        if spec.orderable {
+            derives.extend(["Eq", "Hash", "PartialOrd", "Ord"]);
+        }
+        if !has_default {
+            derives.push("Default");
+        }
+        w!(w, "#[derive({})]", derives.join(", "));
+        if spec.fields.is_empty() {
+            w!(w, "pub struct {} {{}}", spec.name);
+        } else {
+            w!(w, "pub struct {} {{", spec.name);
+            for f in &spec.fields {
+                w!(w, "pub {}: {},", f.ident, f.full_type());
+            }
+            w!(w, "}}");
+        }
+        if has_default {
+            w!(w, "impl Default for {} {{", spec.name);
+            w!(w, "fn default() -> Self {{");
+            w!(w, "{} {{", spec.name);
+            for f in &spec.fields {
+                let value = match (&f.default, f.optional) {
+                    (Some(v), true) => format!("Some({})", v),
+                    (Some(v), false) => v.clone(),
+                    (None, _) => "Default::default()".to_owned(),
+                };
+                w!(w, "{}: {},", f.ident, value);
+            }
+            w!(w, "}}");
+            w!(w, "}}");
+            w!(w, "}}");
+        }
+
+        w!(w, "impl ThriftValue for {} {{", spec.name);
+        w!(w, "fn kind() -> ::thrift_codec::data::DataKind {{");
+        w!(w, "::thrift_codec::data::DataKind::Struct");
+        w!(w, "}}");
+        w!(w);
+        w!(w, "fn to_data(&self) -> ::thrift_codec::data::Data {{");
+        if spec.fields.is_empty() {
+            w!(w, "let fields = Vec::new();");
+        } else {
+            w!(w, "let mut fields = Vec::new();");
+        }
+        for f in &spec.fields {
+            if f.optional {
+                w!(w, "if let Some(v) = &self.{} {{", f.ident);
+                w!(
+                    w,
+                    "fields.push(::thrift_codec::data::Field::new({}, v.to_data()));",
+                    f.id
+                );
+                w!(w, "}}");
+            } else {
+                w!(
+                    w,
+                    "fields.push(::thrift_codec::data::Field::new({}, self.{}.to_data()));",
+                    f.id,
+                    f.ident
+                );
+            }
+        }
+        w!(
+            w,
+            "::thrift_codec::data::Data::Struct(::thrift_codec::data::Struct::new(fields))"
+        );
+        w!(w, "}}");
+        w!(w);
+        w!(
+            w,
+            "fn from_data(data: ::thrift_codec::data::Data) -> ::thrift_codec::Result<Self> {{"
+        );
+        w!(
+            w,
+            "let fields = ::thrift_codec::data::Struct::from_data(data)?.into_fields();"
+        );
+        for f in &spec.fields {
+            w!(w, "let mut {} = None;", f.local());
+        }
+        w!(w, "for field in fields {{");
+        w!(w, "match field.id() {{");
+        for f in &spec.fields {
+            w!(
+                w,
+                "{} => {} = Some(ThriftValue::from_data(field.into_data())?),",
+                f.id,
+                f.local()
+            );
+        }
+        w!(w, "_ => {{}}");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "Ok({} {{", spec.name);
+        for f in &spec.fields {
+            if f.optional && f.ident == f.local() {
+                w!(w, "{},", f.ident);
+            } else if f.optional {
+                w!(w, "{}: {},", f.ident, f.local());
+            } else {
+                w!(
+                    w,
+                    "{}: ::thrift_codec::codegen::runtime::required({}, {:?}, {:?})?,",
+                    f.ident,
+                    f.local(),
+                    spec.name,
+                    f.ident
+                );
+            }
+        }
+        w!(w, "}})");
+        w!(w, "}}");
+        w!(w, "}}");
+    }
+
+    fn emit_union(&self, w: &mut Writer, spec: &StructSpec) -> Result<()> {
+        track_assert!(
+            !spec.fields.is_empty(),
+            ErrorKind::InvalidInput,
+            "Empty union is not supported: {}",
+            spec.name
+        );
+        if spec.orderable {
+            w!(
+                w,
+                "#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]"
+            );
+        } else {
+            w!(w, "#[derive(Debug, Clone, PartialEq)]");
+        }
+        w!(w, "pub enum {} {{", spec.name);
+        for f in &spec.fields {
+            w!(w, "{}({}),", variant_ident(&f.ident), f.rust_type);
+        }
+        w!(w, "}}");
+        let first = &spec.fields[0];
+        w!(w, "impl Default for {} {{", spec.name);
+        w!(w, "fn default() -> Self {{");
+        let value = first
+            .default
+            .clone()
+            .unwrap_or_else(|| "Default::default()".to_owned());
+        w!(
+            w,
+            "{}::{}({})",
+            spec.name,
+            variant_ident(&first.ident),
+            value
+        );
+        w!(w, "}}");
+        w!(w, "}}");
+
+        w!(w, "impl ThriftValue for {} {{", spec.name);
+        w!(w, "fn kind() -> ::thrift_codec::data::DataKind {{");
+        w!(w, "::thrift_codec::data::DataKind::Struct");
+        w!(w, "}}");
+        w!(w);
+        w!(w, "fn to_data(&self) -> ::thrift_codec::data::Data {{");
+        w!(w, "let field = match self {{");
+        for f in &spec.fields {
+            w!(
+                w,
+                "{}::{}(v) => ::thrift_codec::data::Field::new({}, v.to_data()),",
+                spec.name,
+                variant_ident(&f.ident),
+                f.id
+            );
+        }
+        w!(w, "}};");
+        w!(
+            w,
+            "::thrift_codec::data::Data::Struct(::thrift_codec::data::Struct::new(vec![field]))"
+        );
+        w!(w, "}}");
+        w!(w);
+        w!(
+            w,
+            "fn from_data(data: ::thrift_codec::data::Data) -> ::thrift_codec::Result<Self> {{"
+        );
+        w!(w, "let mut values = Vec::new();");
+        w!(
+            w,
+            "for field in ::thrift_codec::data::Struct::from_data(data)?.into_fields() {{"
+        );
+        w!(w, "match field.id() {{");
+        for f in &spec.fields {
+            w!(
+                w,
+                "{} => values.push({}::{}(ThriftValue::from_data(field.into_data())?)),",
+                f.id,
+                spec.name,
+                variant_ident(&f.ident)
+            );
+        }
+        w!(w, "_ => {{}}");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(
+            w,
+            "::thrift_codec::codegen::runtime::single(values, {:?})",
+            spec.name
+        );
+        w!(w, "}}");
+        w!(w, "}}");
+        Ok(())
+    }
+
+    fn emit_codec(&self, w: &mut Writer, name: &str) {
+        w!(w, "impl ::thrift_codec::BinaryEncode for {} {{", name);
+        w!(
+            w,
+            "fn binary_encode<W: ::std::io::Write>(&self, writer: &mut W) -> ::thrift_codec::Result<()> {{"
+        );
+        w!(
+            w,
+            "let s = ::thrift_codec::codegen::runtime::to_struct(self);"
+        );
+        w!(w, "::thrift_codec::BinaryEncode::binary_encode(&s, writer)");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "impl ::thrift_codec::BinaryDecode for {} {{", name);
+        w!(
+            w,
+            "fn binary_decode<R: ::std::io::Read>(reader: &mut R) -> ::thrift_codec::Result<Self> {{"
+        );
+        w!(
+            w,
+            "let s: ::thrift_codec::data::Struct = ::thrift_codec::BinaryDecode::binary_decode(reader)?;"
+        );
+        w!(w, "ThriftValue::from_data(s.into())");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "impl ::thrift_codec::CompactEncode for {} {{", name);
+        w!(
+            w,
+            "fn compact_encode<W: ::std::io::Write>(&self, writer: &mut W) -> ::thrift_codec::Result<()> {{"
+        );
+        w!(
+            w,
+            "let s = ::thrift_codec::codegen::runtime::to_struct(self);"
+        );
+        w!(
+            w,
+            "::thrift_codec::CompactEncode::compact_encode(&s, writer)"
+        );
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "impl ::thrift_codec::CompactDecode for {} {{", name);
+        w!(
+            w,
+            "fn compact_decode<R: ::std::io::Read>(reader: &mut R) -> ::thrift_codec::Result<Self> {{"
+        );
+        w!(
+            w,
+            "let s: ::thrift_codec::data::Struct = ::thrift_codec::CompactDecode::compact_decode(reader)?;"
+        );
+        w!(w, "ThriftValue::from_data(s.into())");
+        w!(w, "}}");
+        w!(w, "}}");
+    }
+
+    fn emit_error_impls(&self, w: &mut Writer, name: &str) {
+        w!(w, "impl ::std::fmt::Display for {} {{", name);
+        w!(
+            w,
+            "fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{"
+        );
+        w!(w, "write!(f, \"{{:?}}\", self)");
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "impl ::std::error::Error for {} {{}}", name);
+    }
+
+    fn emit_service(&self, w: &mut Writer, module: usize, s: &idl::Service) -> Result<()> {
+        let mut functions = Vec::new();
+        for f in &s.functions {
+            functions.push(track!(self.function_spec(module, s, f))?);
+        }
+        for f in &functions {
+            self.emit_struct(w, &f.args);
+            w!(w);
+            if let Some(result) = &f.result {
+                self.emit_struct(w, result);
+                w!(w);
+            }
+            if let Some(error) = &f.error {
+                w!(w, "#[derive(Debug, Clone, PartialEq)]");
+                w!(w, "pub enum {} {{", error);
+                for e in &f.exceptions {
+                    w!(w, "{}({}),", variant_ident(&e.ident), e.rust_type);
+                }
+                w!(w, "}}");
+                self.emit_error_impls(w, error);
+                w!(w);
+            }
+        }
+        let parent = if let Some(extends) = &s.extends {
+            let (j, definition) = track!(self.lookup(module, extends))?;
+            track_assert!(
+                matches!(definition, Definition::Service(_)),
+                ErrorKind::InvalidInput,
+                "Not a service: {}",
+                extends
+            );
+            Some(format!(
+                "{}{}",
+                self.relative_path(module, j),
+                ident(definition.name())
+            ))
+        } else {
+            None
+        };
+        track!(self.emit_server_trait(w, s, parent.as_deref(), &functions))?;
+        w!(w);
+        track!(self.emit_client_trait(w, s, parent.as_deref(), &functions))?;
+        Ok(())
+    }
+
+    fn emit_server_trait(
+        &self,
+        w: &mut Writer,
+        s: &idl::Service,
+        parent: Option<&str>,
+        functions: &[FunctionSpec],
+    ) -> Result<()> {
+        let name = format!("{}Server", ident(&s.name));
+        if let Some(parent) = parent {
+            w!(w, "pub trait {}: {}Server {{", name, parent);
+        } else {
+            w!(w, "pub trait {} {{", name);
+        }
+        for f in functions {
+            w!(
+                w,
+                "fn {}(&mut self{}){};",
+                f.ident,
+                f.params_decl(),
+                f.server_return_type()
+                    .map(|t| format!(" -> {}", t))
+                    .unwrap_or_default()
+            );
+            w!(w);
+        }
+        w!(
+            w,
+            "/// Handles a request message and returns the reply message (if the request is not oneway)."
+        );
+        w!(
+            w,
+            "fn handle_message(&mut self, message: &::thrift_codec::message::Message) -> Option<::thrift_codec::message::Message> {{"
+        );
+        w!(w, "match message.method_name() {{");
+        for f in functions {
+            w!(w, "{:?} => {{", f.name);
+            let args_var = if f.args.fields.is_empty() {
+                "_args"
+            } else {
+                "args"
+            };
+            w!(
+                w,
+                "let {} = match {}::from_data(message.body().clone().into()) {{",
+                args_var,
+                f.args.name
+            );
+            w!(w, "Ok(args) => args,");
+            w!(
+                w,
+                "Err(e) => return Some(::thrift_codec::codegen::runtime::protocol_error(message, &e)),"
+            );
+            w!(w, "}};");
+            let call = format!(
+                "self.{}({})",
+                f.ident,
+                f.args
+                    .fields
+                    .iter()
+                    .map(|p| format!("args.{}", p.ident))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let result = match f.result {
+                None => {
+                    w!(w, "{};", call);
+                    w!(w, "None");
+                    w!(w, "}}");
+                    continue;
+                }
+                Some(ref result) => result,
+            };
+            if let Some(error) = &f.error {
+                w!(w, "let mut result = {}::default();", result.name);
+                w!(w, "match {} {{", call);
+                if f.return_type.is_some() {
+                    w!(w, "Ok(v) => result.success = Some(v),");
+                } else {
+                    w!(w, "Ok(()) => {{}}");
+                }
+                for e in &f.exceptions {
+                    w!(
+                        w,
+                        "Err({}::{}(e)) => result.{} = Some(e),",
+                        error,
+                        variant_ident(&e.ident),
+                        e.ident
+                    );
+                }
+                w!(w, "}}");
+            } else if f.return_type.is_some() {
+                w!(w, "let result = {} {{", result.name);
+                w!(w, "success: Some({}),", call);
+                w!(w, "}};");
+            } else {
+                w!(w, "{};", call);
+                w!(w, "let result = {} {{}};", result.name);
+            }
+            w!(
+                w,
+                "Some(::thrift_codec::codegen::runtime::reply(message, &result))"
+            );
+            w!(w, "}}");
+        }
+        if let Some(parent) = parent {
+            w!(w, "_ => {}Server::handle_message(self, message),", parent);
+        } else {
+            w!(
+                w,
+                "_ => Some(::thrift_codec::codegen::runtime::unknown_method(message)),"
+            );
+        }
+        w!(w, "}}");
+        w!(w, "}}");
+        w!(w, "}}");
+        Ok(())
+    }
+
+    fn emit_client_trait(
+        &self,
+        w: &mut Writer,
+        s: &idl::Service,
+        parent: Option<&str>,
+        functions: &[FunctionSpec],
+    ) -> Result<()> {
+        let name = format!("{}Client", ident(&s.name));
+        if let Some(parent) = parent {
+            w!(w, "pub trait {}: {}Client {{", name, parent);
+        } else {
+            w!(w, "pub trait {} {{", name);
+            w!(
+                w,
+                "/// Sends a request and returns the body of the reply message."
+            );
+            w!(w, "///");
+            w!(
+                w,
+                "/// `kind` is either `MessageKind::Call` or `MessageKind::Oneway`."
+            );
+            w!(
+                w,
+                "/// For oneway requests, implementations must return `Ok(None)` without waiting for a reply."
+            );
+            w!(
+                w,
+                "/// If the reply is a `MessageKind::Exception` message, implementations must return an error."
+            );
+            w!(w, "fn send_request(");
+            w!(w, "&mut self,");
+            w!(w, "method_name: &str,");
+            w!(w, "kind: ::thrift_codec::message::MessageKind,");
+            w!(w, "args: ::thrift_codec::data::Struct,");
+            w!(
+                w,
+                ") -> ::thrift_codec::Result<Option<::thrift_codec::data::Struct>>;"
+            );
+            w!(w);
+        }
+        for f in functions {
+            w!(
+                w,
+                "fn {}(&mut self{}) -> {} {{",
+                f.ident,
+                f.params_decl(),
+                f.client_return_type()
+            );
+            if f.args.fields.is_empty() {
+                w!(w, "let args = {} {{}};", f.args.name);
+            } else {
+                let params = f
+                    .args
+                    .fields
+                    .iter()
+                    .map(|p| p.ident.as_str())
+                    .collect::<Vec<_>>();
+                w!(w, "let args = {} {{ {} }};", f.args.name, params.join(", "));
+            }
+            let result = match f.result {
+                None => {
+                    w!(w, "self.send_request(");
+                    w!(w, "{:?},", f.name);
+                    w!(w, "::thrift_codec::message::MessageKind::Oneway,");
+                    w!(w, "::thrift_codec::codegen::runtime::to_struct(&args),");
+                    w!(w, ")?;");
+                    w!(w, "Ok(())");
+                    w!(w, "}}");
+                    w!(w);
+                    continue;
+                }
+                Some(ref result) => result,
+            };
+            w!(w, "let reply = self.send_request(");
+            w!(w, "{:?},", f.name);
+            w!(w, "::thrift_codec::message::MessageKind::Call,");
+            w!(w, "::thrift_codec::codegen::runtime::to_struct(&args),");
+            w!(w, ")?;");
+            w!(
+                w,
+                "let result: {} = ::thrift_codec::codegen::runtime::from_reply(reply, {:?})?;",
+                result.name,
+                f.name
+            );
+            let wrap = if let Some(error) = &f.error {
+                for e in &f.exceptions {
+                    w!(w, "if let Some(e) = result.{} {{", e.ident);
+                    w!(
+                        w,
+                        "return Ok(Err({}::{}(e)));",
+                        error,
+                        variant_ident(&e.ident)
+                    );
+                    w!(w, "}}");
+                }
+                ".map(Ok)"
+            } else {
+                ""
+            };
+            if f.return_type.is_some() {
+                w!(
+                    w,
+                    "::thrift_codec::codegen::runtime::required(result.success, {:?}, \"success\"){}",
+                    result.name,
+                    wrap
+                );
+            } else {
+                if f.error.is_none() {
+                    w!(w, "let _ = result;");
+                }
+                if wrap.is_empty() {
+                    w!(w, "Ok(())");
+                } else {
+                    w!(w, "Ok(Ok(()))");
+                }
+            }
+            w!(w, "}}");
+            w!(w);
+        }
+        w!(w, "}}");
+        Ok(())
+    }
+
+    fn function_spec(
+        &self,
+        module: usize,
+        s: &idl::Service,
+        f: &idl::Function,
+    ) -> Result<FunctionSpec> {
+        let prefix = format!("{}{}", ident(&s.name), pascal_case(&f.name));
+        let mut params = Vec::new();
+        for p in &f.params {
+            let mut spec = track!(self.field_spec(module, "", p))?;
+            spec.optional = p.requiredness == Requiredness::Optional;
+            params.push(spec);
+        }
+        let args = StructSpec {
+            name: format!("{}Args", prefix),
+            orderable: false,
+            fields: params,
+        };
+        let mut exceptions = Vec::new();
+        for e in &f.throws {
+            let mut spec = track!(self.field_spec(module, "", e))?;
+            spec.optional = true;
+            exceptions.push(spec);
+        }
+        let return_type = match f.return_type {
+            None => None,
+            Some(ref t) => Some(track!(self.rust_type(module, t))?),
+        };
+        let result = if f.oneway {
+            track_assert!(
+                return_type.is_none() && exceptions.is_empty(),
+                ErrorKind::InvalidInput,
+                "Oneway function must be void and cannot throw exceptions: {}.{}",
+                s.name,
+                f.name
+            );
+            None
+        } else {
+            let mut fields = Vec::new();
+            if let Some(t) = &return_type {
+                fields.push(FieldSpec {
+                    id: 0,
+                    ident: "success".to_owned(),
+                    rust_type: t.clone(),
+                    optional: true,
+                    default: None,
+                });
+            }
+            fields.extend(exceptions.iter().map(|e| FieldSpec {
+                id: e.id,
+                ident: e.ident.clone(),
+                rust_type: e.rust_type.clone(),
+                optional: true,
+                default: None,
+            }));
+            Some(StructSpec {
+                name: format!("{}Result", prefix),
+                orderable: false,
+                fields,
+            })
+        };
+        let error = if exceptions.is_empty() {
+            None
+        } else {
+            Some(format!("{}Error", prefix))
+        };
+        Ok(FunctionSpec {
+            name: f.name.clone(),
+            ident: ident(&snake_case(&f.name)),
+            args,
+            result,
+            return_type,
+            exceptions,
+            error,
+        })
+    }
+
+    fn struct_spec(&self, module: usize, s: &idl::Struct) -> Result<StructSpec> {
+        let mut fields = Vec::new();
+        for f in &s.fields {
+            fields.push(track!(self.field_spec(module, &s.name, f))?);
+        }
+        let mut visiting = HashSet::new();
+        visiting.insert((module, s.name.clone()));
+        let mut orderable = true;
+        for f in &s.fields {
+            orderable &= track!(self.is_orderable(module, &f.field_type, &mut visiting))?;
+        }
+        Ok(StructSpec {
+            name: ident(&s.name),
+            fields,
+            orderable,
+        })
+    }
+
+    fn field_spec(&self, module: usize, struct_name: &str, f: &idl::Field) -> Result<FieldSpec> {
+        let mut rust_type = track!(self.rust_type(module, &f.field_type))?;
+        if self
+            .boxed_fields
+            .contains(&(module, struct_name.to_owned(), f.id))
+        {
+            rust_type = format!("Box<{}>", rust_type);
+        }
+        let default = match f.default {
+            None => None,
+            Some(ref v) => Some(track!(self.const_expr(module, &f.field_type, v))?),
+        };
+        Ok(FieldSpec {
+            id: f.id,
+            ident: ident(&snake_case(&f.name)),
+            rust_type,
+            optional: f.requiredness != Requiredness::Required,
+            default,
+        })
+    }
+
+    fn rust_type(&self, module: usize, ty: &FieldType) -> Result<String> {
+        Ok(match *ty {
+            FieldType::Bool => "bool".to_owned(),
+            FieldType::I8 => "i8".to_owned(),
+            FieldType::I16 => "i16".to_owned(),
+            FieldType::I32 => "i32".to_owned(),
+            FieldType::I64 => "i64".to_owned(),
+            FieldType::Double => "f64".to_owned(),
+            FieldType::String => "String".to_owned(),
+            FieldType::Binary => "Vec<u8>".to_owned(),
+            FieldType::Uuid => "::thrift_codec::data::Uuid".to_owned(),
+            FieldType::List(ref t) => format!("Vec<{}>", track!(self.rust_type(module, t))?),
+            FieldType::Set(ref t) => {
+                track!(self.ensure_orderable(module, t))?;
+                format!(
+                    "::std::collections::BTreeSet<{}>",
+                    track!(self.rust_type(module, t))?
+                )
+            }
+            FieldType::Map(ref k, ref v) => {
+                track!(self.ensure_orderable(module, k))?;
+                format!(
+                    "::std::collections::BTreeMap<{}, {}>",
+                    track!(self.rust_type(module, k))?,
+                    track!(self.rust_type(module, v))?
+                )
+            }
+            FieldType::Named(ref name) => {
+                let (j, definition) = track!(self.lookup(module, name))?;
+                track_assert!(
+                    !matches!(definition, Definition::Const(_) | Definition::Service(_)),
+                    ErrorKind::InvalidInput,
+                    "Not a type: {}",
+                    name
+                );
+                format!(
+                    "{}{}",
+                    self.relative_path(module, j),
+                    ident(definition.name())
+                )
+            }
+        })
+    }
+
+    fn ensure_orderable(&self, module: usize, ty: &FieldType) -> Result<()> {
+        let orderable = track!(self.is_orderable(module, ty, &mut HashSet::new()))?;
+        track_assert!(
+            orderable,
+            ErrorKind::InvalidInput,
+            "Set elements and map keys must not contain `double`: {:?}",
+            ty
+        );
+        Ok(())
+    }
+
+    fn is_orderable(
+        &self,
+        module: usize,
+        ty: &FieldType,
+        visiting: &mut HashSet<(usize, String)>,
+    ) -> Result<bool> {
+        Ok(match *ty {
+            FieldType::Double => false,
+            FieldType::List(ref t) | FieldType::Set(ref t) => {
+                track!(self.is_orderable(module, t, visiting))?
+            }
+            FieldType::Map(ref k, ref v) => {
+                track!(self.is_orderable(module, k, visiting))?
+                    && track!(self.is_orderable(module, v, visiting))?
+            }
+            FieldType::Named(ref name) => {
+                let (j, definition) = track!(self.lookup(module, name))?;
+                match *definition {
+                    Definition::Typedef(ref d) => {
+                        track!(self.is_orderable(j, &d.field_type, visiting))?
+                    }
+                    Definition::Struct(ref d)
+                    | Definition::Union(ref d)
+                    | Definition::Exception(ref d) => {
+                        if !visiting.insert((j, d.name.clone())) {
+                            return Ok(true);
+                        }
+                        let mut orderable = true;
+                        for f in &d.fields {
+                            orderable &= track!(self.is_orderable(j, &f.field_type, visiting))?;
+                        }
+                        orderable
+                    }
+                    _ => true,
+                }
+            }
+            _ => true,
+        })
+    }
+
+    fn const_expr(&self, module: usize, ty: &FieldType, value: &ConstValue) -> Result<String> {
+        let (j, resolved) = track!(self.resolve_type(module, ty))?;
+        if let FieldType::Named(ref name) = resolved {
+            let (k, definition) = track!(self.lookup(j, name))?;
+            let path = format!(
+                "{}{}",
+                self.relative_path(module, k),
+                ident(definition.name())
+            );
+            match (definition, value) {
+                (Definition::Enum(e), ConstValue::Identifier(s)) => {
+                    let variant = s.rsplit('.').next().expect("Never fails");
+                    if e.values.iter().any(|v| v.name == variant) {
+                        return Ok(format!("{}::{}", path, ident(&pascal_case(variant))));
+                    }
+                }
+                (Definition::Enum(e), ConstValue::Int(n)) => {
+                    if let Some(v) = e.values.iter().find(|v| i64::from(v.value) == *n) {
+                        return Ok(format!("{}::{}", path, ident(&pascal_case(&v.name))));
+                    }
+                }
+                (Definition::Struct(s) | Definition::Exception(s), ConstValue::Map(entries)) => {
+                    let mut fields = Vec::new();
+                    for (key, value) in entries {
+                        let f = track!(self.field_by_key(s, key))?;
+                        let spec = track!(self.field_spec(k, &s.name, f))?;
+                        let mut expr = track!(self.const_expr_in(module, k, &f.field_type, value))?;
+                        if spec.rust_type.starts_with("Box<") {
+                            expr = format!("Box::new({})", expr);
+                        }
+                        if spec.optional {
+                            expr = format!("Some({})", expr);
+                        }
+                        fields.push(format!("{}: {}", spec.ident, expr));
+                    }
+                    fields.push("..Default::default()".to_owned());
+                    return Ok(format!("{} {{ {} }}", path, fields.join(", ")));
+                }
+                (Definition::Union(s), ConstValue::Map(entries)) if entries.len() == 1 => {
+                    let f = track!(self.field_by_key(s, &entries[0].0))?;
+                    let expr = track!(self.const_expr_in(module, k, &f.field_type, &entries[0].1))?;
+                    let variant = variant_ident(&ident(&snake_case(&f.name)));
+                    return Ok(format!("{}::{}({})", path, variant, expr));
+                }
+                _ => {}
+            }
+        }
+        if let ConstValue::Identifier(ref name) = *value {
+            let (k, definition) = track!(self.lookup(module, name))?;
+            if let Definition::Const(c) = definition {
+                let path = self.relative_path(module, k);
+                let (_, const_type) = track!(self.resolve_type(k, &c.field_type))?;
+                return Ok(match const_type {
+                    FieldType::String => format!("{}{}.to_owned()", path, ident(&c.name)),
+                    FieldType::Bool
+                    | FieldType::I8
+                    | FieldType::I16
+                    | FieldType::I32
+                    | FieldType::I64
+                    | FieldType::Double => format!("{}{}", path, ident(&c.name)),
+                    _ => format!("{}{}()", path, ident(&snake_case(&c.name))),
+                });
+            }
+        }
+
+        Ok(match (&resolved, value) {
+            (FieldType::Bool, ConstValue::Int(n)) => (*n != 0).to_string(),
+            (
+                FieldType::I8 | FieldType::I16 | FieldType::I32 | FieldType::I64,
+                ConstValue::Int(n),
+            ) => n.to_string(),
+            (FieldType::Double, ConstValue::Int(n)) => format!("{}.0", n),
+            (FieldType::Double, ConstValue::Double(n)) => format!("{:?}", n),
+            (FieldType::String, ConstValue::String(s)) => format!("{:?}.to_owned()", s),
+            (FieldType::Binary, ConstValue::String(s)) => format!("{:?}.as_bytes().to_vec()", s),
+            (FieldType::Uuid, ConstValue::String(s)) => {
+                let bytes = track!(parse_uuid(s))?;
+                format!("::thrift_codec::data::Uuid::new({:?})", bytes)
+            }
+            (FieldType::List(t), ConstValue::List(values)) => {
+                let values = track!(self.const_exprs(module, j, t, values))?;
+                format!("vec![{}]", values.join(", "))
+            }
+            (FieldType::Set(t), ConstValue::List(values)) => {
+                let values = track!(self.const_exprs(module, j, t, values))?;
+                format!(
+                    "::std::collections::BTreeSet::from([{}])",
+                    values.join(", ")
+                )
+            }
+            (FieldType::Map(k, v), ConstValue::Map(entries)) => {
+                let mut pairs = Vec::new();
+                for (key, value) in entries {
+                    let key = track!(self.const_expr_in(module, j, k, key))?;
+                    let value = track!(self.const_expr_in(module, j, v, value))?;
+                    pairs.push(format!("({}, {})", key, value));
+                }
+                format!("::std::collections::BTreeMap::from([{}])", pairs.join(", "))
+            }
+            _ => track_panic!(
+                ErrorKind::InvalidInput,
+                "Invalid constant value: type={:?}, value={:?}",
+                ty,
+                value
+            ),
+        })
+    }
+
+    fn const_exprs(
+        &self,
+        module: usize,
+        type_module: usize,
+        ty: &FieldType,
+        values: &[ConstValue],
+    ) -> Result<Vec<String>> {
+        values
+            .iter()
+            .map(|v| track!(self.const_expr_in(module, type_module, ty, v)))
+            .collect()
+    }
+
+    // Makes the expression (in `module`) of `value` which type `ty` is defined in `type_module`.
+    fn const_expr_in(
+        &self,
+        module: usize,
+        type_module: usize,
+        ty: &FieldType,
+        value: &ConstValue,
+    ) -> Result<String> {
+        if module == type_module {
+            return track!(self.const_expr(module, ty, value));
+        }
+        // Qualify the named types so that they can be resolved from `module`.
+        let ty = track!(self.qualify(type_module, module, ty))?;
+        track!(self.const_expr(module, &ty, value))
+    }
+
+    fn qualify(&self, from: usize, to: usize, ty: &FieldType) -> Result<FieldType> {
+        Ok(match *ty {
+            FieldType::List(ref t) => FieldType::List(Box::new(track!(self.qualify(from, to, t))?)),
+            FieldType::Set(ref t) => FieldType::Set(Box::new(track!(self.qualify(from, to, t))?)),
+            FieldType::Map(ref k, ref v) => FieldType::Map(
+                Box::new(track!(self.qualify(from, to, k))?),
+                Box::new(track!(self.qualify(from, to, v))?),
+            ),
+            FieldType::Named(ref name) => {
+                let (j, definition) = track!(self.lookup(from, name))?;
+                if j == to {
+                    FieldType::Named(definition.name().to_owned())
+                } else {
+                    let include = self.modules[to]
+                        .includes
+                        .iter()
+                        .find(|(_, &m)| m == j)
+                        .map(|(name, _)| name.clone());
+                    let include = track_assert_some!(
+                        include,
+                        ErrorKind::InvalidInput,
+                        "Type {} is not visible from {}",
+                        name,
+                        self.modules[to].name
+                    );
+                    FieldType::Named(format!("{}.{}", include, definition.name()))
+                }
+            }
+            ref t => t.clone(),
+        })
+    }
+
+    fn field_by_key<'b>(&self, s: &'b idl::Struct, key: &ConstValue) -> Result<&'b idl::Field> {
+        let name = match key {
+            ConstValue::String(name) => name,
+            _ => track_panic!(ErrorKind::InvalidInput, "Invalid field name: {:?}", key),
+        };
+        let field = track_assert_some!(
+            s.fields.iter().find(|f| &f.name == name),
+            ErrorKind::InvalidInput,
+            "Unknown field: {}.{}",
+            s.name,
+            name
+        );
+        Ok(field)
+    }
+
+    fn resolve_type(&self, module: usize, ty: &FieldType) -> Result<(usize, FieldType)> {
+        if let FieldType::Named(ref name) = *ty {
+            let (j, definition) = track!(self.lookup(module, name))?;
+            if let Definition::Typedef(ref d) = *definition {
+                return track!(self.resolve_type(j, &d.field_type));
+            }
+            return Ok((j, FieldType::Named(definition.name().to_owned())));
+        }
+        Ok((module, ty.clone()))
+    }
+
+    fn lookup(&self, module: usize, name: &str) -> Result<(usize, &'a Definition)> {
+        let m = &self.modules[module];
+        if let Some((prefix, rest)) = name.split_once('.') {
+            if let Some(&j) = m.includes.get(prefix) {
+                if let Some(definition) = self.modules[j].document.definition(rest) {
+                    return Ok((j, definition));
+                }
+            }
+        }
+        let definition = track_assert_some!(
+            m.document.definition(name),
+            ErrorKind::InvalidInput,
+            "Undefined name: {:?} (in {:?})",
+            name,
+            m.name
+        );
+        Ok((module, definition))
+    }
+
+    fn relative_path(&self, from: usize, to: usize) -> String {
+        if from == to {
+            return String::new();
+        }
+        let mut path = "super::".repeat(self.paths[from].len());
+        for name in &self.paths[to] {
+            path.push_str(name);
+            path.push_str("::");
+        }
+        path
+    }
+}
+
+#[derive(Debug)]
+struct FunctionSpec {
+    name: String,
+    ident: String,
+    args: StructSpec,
+    result: Option<StructSpec>,
+    return_type: Option<String>,
+    exceptions: Vec<FieldSpec>,
+    error: Option<String>,
+}
+impl FunctionSpec {
+    fn params_decl(&self) -> String {
+        self.args
+            .fields
+            .iter()
+            .map(|p| format!(", {}: {}", p.ident, p.full_type()))
+            .collect()
+    }
+
+    fn server_return_type(&self) -> Option<String> {
+        let ty = self.return_type.clone().unwrap_or_else(|| "()".to_owned());
+        if let Some(error) = &self.error {
+            Some(format!("Result<{}, {}>", ty, error))
+        } else {
+            self.return_type.clone()
+        }
+    }
+
+    fn client_return_type(&self) -> String {
+        let ty = self.server_return_type().unwrap_or_else(|| "()".to_owned());
+        format!("::thrift_codec::Result<{}>", ty)
+    }
+}
+
+fn struct_type(definition: &Definition) -> Option<&idl::Struct> {
+    match *definition {
+        Definition::Struct(ref s) | Definition::Union(ref s) | Definition::Exception(ref s) => {
+            Some(s)
+        }
+        _ => None,
+    }
+}
+
+fn module_path(module: &Module) -> Vec<String> {
+    if let Some(namespace) = module.document.namespace("rs") {
+        namespace
+            .split('.')
+            .map(|s| ident(&snake_case(s)))
+            .collect()
+    } else {
+        vec![ident(&snake_case(&module.name))]
+    }
+}
+
+fn parse_uuid(s: &str) -> Result<[u8; 16]> {
+    let hex = s.replace('-', "");
+    track_assert_eq!(hex.len(), 32, ErrorKind::InvalidInput, "uuid={:?}", s);
+    let mut bytes = [0; 16];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = track_assert_some!(
+            u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok(),
+            ErrorKind::InvalidInput,
+            "uuid={:?}",
+            s
+        );
+    }
+    Ok(bytes)
+}
+
+fn ident(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    if matches!(name.as_str(), "self" | "Self" | "super" | "crate") {
+        format!("{}_", name)
+    } else if RUST_KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn variant_ident(field_ident: &str) -> String {
+    ident(&pascal_case(field_ident.trim_start_matches("r#")))
+}
+
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut s = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|c| c.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                s.push('_');
+            }
+        }
+        s.push(c.to_ascii_lowercase());
+    }
+    s
+}
+
+fn pascal_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().expect("Never fails").to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn case_conversion_works() {
+        assert_eq!(snake_case("whatOp"), "what_op");
+        assert_eq!(snake_case("HTTPMethod"), "http_method");
+        assert_eq!(snake_case("num1"), "num1");
+        assert_eq!(snake_case("TRACE_ID"), "trace_id");
+        assert_eq!(pascal_case("ADD"), "Add");
+        assert_eq!(pascal_case("HTTP_METHOD"), "HttpMethod");
+        assert_eq!(pascal_case("emitBatch"), "EmitBatch");
+        assert_eq!(ident("type"), "r#type");
+        assert_eq!(ident("self"), "self_");
+    }
+}
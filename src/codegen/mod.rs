@@ -0,0 +1,278 @@
+//! Rust code generator for Thrift IDL.
+//!
+//! The generated code consists of Rust structs, enums, unions, exceptions and
+//! service client/server traits. Their serialization is built on
+//! [`ThriftValue`](crate::data::ThriftValue) and the encoding/decoding traits of this crate.
+//!
+//! This module does not depend on any external tools, so it can be used from `build.rs`.
+//!
+//! # Examples
+//!
+//! `build.rs`:
+//!
+//! ```no_run
+//! use thrift_codec::codegen::Builder;
+//!
+//! fn main() {
+//!     Builder::new()
+//!         .file("idl/tutorial.thrift")
+//!         .include_dir("idl/")
+//!         .write_to_out_dir("thrift.rs")
+//!         .unwrap();
+//! }
+//! ```
+//!
+//! `src/lib.rs`:
+//!
+//! ```ignore
+//! include!(concat!(env!("OUT_DIR"), "/thrift.rs"));
+//!
+//! use tutorial::{Operation, Work};
+//! ```
+//!
+//! Each IDL document is emitted as a module. The name of the module is
+//! the `rs` namespace of the document (e.g., `namespace rs foo.bar` yields `foo::bar`)
+//! or the file name without the extension if there is no such declaration.
+use crate::idl::Document;
+use crate::{ErrorKind, Result};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub mod runtime;
+
+mod generator;
+
+/// Code generator builder.
+#[derive(Debug, Default)]
+pub struct Builder {
+    files: Vec<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+    documents: Vec<(String, Document)>,
+}
+impl Builder {
+    /// Makes a new `Builder` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an IDL file to be compiled.
+    ///
+    /// The files included by the IDL are also compiled.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.files.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Adds a directory to search for included files.
+    ///
+    /// The directory of the including file is always searched first.
+    pub fn include_dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.include_dirs.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Adds an already parsed IDL document to be compiled.
+    ///
+    /// `name` is used to resolve `include` directives of other documents
+    /// (e.g., the name of a document included by `include "shared.thrift"` is `shared`).
+    pub fn document(&mut self, name: &str, document: Document) -> &mut Self {
+        self.documents.push((name.to_owned(), document));
+        self
+    }
+
+    /// Generates the Rust code.
+    pub fn generate(&self) -> Result<String> {
+        let modules = track!(self.load())?;
+        track!(generator::generate(&modules))
+    }
+
+    /// Generates the Rust code and writes it to `path`.
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let code = track!(self.generate())?;
+        track_io!(fs::write(path, code))
+    }
+
+    /// Generates the Rust code and writes it to the file named `file_name` in the directory `$OUT_DIR`.
+    ///
+    /// This method is intended to be called from `build.rs`.
+    /// It also emits `cargo:rerun-if-changed` directives for the loaded IDL files.
+    pub fn write_to_out_dir(&self, file_name: &str) -> Result<PathBuf> {
+        let out_dir = track_assert_some!(
+            env::var_os("OUT_DIR"),
+            ErrorKind::Other,
+            "$OUT_DIR is not set"
+        );
+        let path = Path::new(&out_dir).join(file_name);
+        let modules = track!(self.load())?;
+        let code = track!(generator::generate(&modules))?;
+        track_io!(fs::write(&path, code))?;
+        for module in &modules {
+            if let Some(idl_path) = &module.path {
+                println!("cargo:rerun-if-changed={}", idl_path.display());
+            }
+        }
+        Ok(path)
+    }
+
+    fn load(&self) -> Result<Vec<Module>> {
+        let mut loader = Loader::new(&self.include_dirs);
+        for (name, document) in &self.documents {
+            loader.add(name.clone(), None, document.clone());
+        }
+        for file in &self.files {
+            track!(loader.load(file))?;
+        }
+        track!(loader.resolve_includes())?;
+        Ok(loader.modules)
+    }
+}
+
+#[derive(Debug)]
+struct Module {
+    name: String,
+    path: Option<PathBuf>,
+    document: Document,
+    includes: HashMap<String, usize>,
+}
+
+#[derive(Debug)]
+struct Loader<'a> {
+    include_dirs: &'a [PathBuf],
+    modules: Vec<Module>,
+}
+impl<'a> Loader<'a> {
+    fn new(include_dirs: &'a [PathBuf]) -> Self {
+        Loader {
+            include_dirs,
+            modules: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, name: String, path: Option<PathBuf>, document: Document) -> usize {
+        self.modules.push(Module {
+            name,
+            path,
+            document,
+            includes: HashMap::new(),
+        });
+        self.modules.len() - 1
+    }
+
+    fn load(&mut self, path: &Path) -> Result<usize> {
+        let canonical = track!(track_io!(path.canonicalize()), "path={:?}", path)?;
+        if let Some(i) = self
+            .modules
+            .iter()
+            .position(|m| m.path.as_ref() == Some(&canonical))
+        {
+            return Ok(i);
+        }
+        let document = track!(Document::read_file(&canonical))?;
+        Ok(self.add(document_name(path), Some(canonical), document))
+    }
+
+    fn resolve_includes(&mut self) -> Result<()> {
+        let mut i = 0;
+        while i < self.modules.len() {
+            for include in self.modules[i].document.includes.clone() {
+                let name = document_name(Path::new(&include));
+                let j = if let Some(j) = self
+                    .modules
+                    .iter()
+                    .position(|m| m.path.is_none() && m.name == name)
+                {
+                    j
+                } else {
+                    let path = track!(self.find_include(i, &include))?;
+                    track!(self.load(&path))?
+                };
+                self.modules[i].includes.insert(name, j);
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+
+    fn find_include(&self, including: usize, include: &str) -> Result<PathBuf> {
+        let base_dir = self.modules[including]
+            .path
+            .as_ref()
+            .and_then(|p| p.parent().map(Path::to_path_buf));
+        for dir in base_dir.iter().chain(self.include_dirs.iter()) {
+            let path = dir.join(include);
+            if path.exists() {
+                return Ok(path);
+            }
+        }
+        track_panic!(
+            ErrorKind::InvalidInput,
+            "Included file is not found: {:?} (included by {:?})",
+            include,
+            self.modules[including].name
+        );
+    }
+}
+
+fn document_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_works() {
+        let shared = track_try_unwrap!(Document::parse(
+            r#"
+            namespace rs shared
+            enum Color { RED = 1, GREEN }
+            "#
+        ));
+        let tutorial = track_try_unwrap!(Document::parse(
+            r#"
+            include "shared.thrift"
+            struct Work {
+              1: required i32 num1 = 0,
+              2: optional shared.Color color = shared.Color.GREEN,
+              3: set<string> tags,
+              4: optional Work next,
+              5: required Work2 w2,
+            }
+            struct Work2 {
+              1: optional Work back,
+            }
+            service Calculator {
+              i32 add(1: i32 num1, 2: i32 num2),
+            }
+            "#
+        ));
+        let code = track_try_unwrap!(Builder::new()
+            .document("shared", shared)
+            .document("tutorial", tutorial)
+            .generate());
+        assert!(code.contains("pub mod shared {"));
+        assert!(code.contains("pub mod tutorial {"));
+        assert!(code.contains("pub num1: i32,"));
+        assert!(code.contains("pub color: Option<super::shared::Color>,"));
+        assert!(code.contains("color: Some(super::shared::Color::Green),"));
+        assert!(code.contains("pub tags: Option<::std::collections::BTreeSet<String>>,"));
+        assert!(code.contains("pub next: Option<Box<Work>>,"));
+        assert!(code.contains("pub w2: Work2,"));
+        assert!(code.contains("pub back: Option<Box<Work>>,"));
+        assert!(code.contains("pub trait CalculatorServer {"));
+        assert!(code.contains("fn add(&mut self, num1: i32, num2: i32) -> i32;"));
+        assert!(code.contains("pub trait CalculatorClient {"));
+    }
+
+    #[test]
+    fn unorderable_set_element_is_rejected() {
+        let document = track_try_unwrap!(Document::parse("struct Foo { 1: set<double> bar }"));
+        let e = Builder::new().document("foo", document).generate().err();
+        assert_eq!(e.map(|e| *e.kind()), Some(ErrorKind::InvalidInput));
+    }
+}
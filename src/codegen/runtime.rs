@@ -0,0 +1,99 @@
+//! Helper functions used by the generated code.
+//!
+//! These functions are not intended to be used directly.
+use crate::data::{Struct, ThriftValue};
+use crate::message::{ApplicationException, ApplicationExceptionKind, Message};
+use crate::{Error, ErrorKind, Result};
+use trackable::error::ErrorKindExt;
+
+/// Unwraps the value of a required field.
+///
+/// # Errors
+///
+/// If `value` is `None`, this function will return an error which kind is `ErrorKind::InvalidInput`.
+pub fn required<T>(value: Option<T>, struct_name: &str, field_name: &str) -> Result<T> {
+    let value = track_assert_some!(
+        value,
+        ErrorKind::InvalidInput,
+        "Required field is missing: {}.{}",
+        struct_name,
+        field_name
+    );
+    Ok(value)
+}
+
+/// Takes the only value of a union.
+///
+/// # Errors
+///
+/// If `values` does not have exactly one element,
+/// this function will return an error which kind is `ErrorKind::InvalidInput`.
+pub fn single<T>(mut values: Vec<T>, union_name: &str) -> Result<T> {
+    track_assert_eq!(
+        values.len(),
+        1,
+        ErrorKind::InvalidInput,
+        "A union must have exactly one field: {}",
+        union_name
+    );
+    Ok(values.remove(0))
+}
+
+/// Makes an error which indicates that `value` is not a member of the enum `enum_name`.
+pub fn unknown_enum_value(enum_name: &str, value: i32) -> Error {
+    track!(Error::from(ErrorKind::InvalidInput.cause(format!(
+        "Unknown enum value: {}({})",
+        enum_name, value
+    ))))
+}
+
+/// Converts `value` to a `Struct`.
+pub fn to_struct<T: ThriftValue>(value: &T) -> Struct {
+    Struct::from_data(value.to_data()).expect("Never fails")
+}
+
+/// Converts `body` to a value of type `T`.
+///
+/// # Errors
+///
+/// If `body` is `None` (i.e., no reply is received for a two-way call) or it could not be converted,
+/// this function will return an error which kind is `ErrorKind::InvalidInput`.
+pub fn from_reply<T: ThriftValue>(body: Option<Struct>, method_name: &str) -> Result<T> {
+    let body = track_assert_some!(
+        body,
+        ErrorKind::InvalidInput,
+        "No reply: method={}",
+        method_name
+    );
+    track!(T::from_data(body.into()))
+}
+
+/// Makes the reply message to `call`.
+pub fn reply<T: ThriftValue>(call: &Message, result: &T) -> Message {
+    Message::reply(call.method_name(), call.sequence_id(), to_struct(result))
+}
+
+/// Makes the `TApplicationException` message which indicates that the method of `call` is unknown.
+pub fn unknown_method(call: &Message) -> Message {
+    let reason = format!("Unknown method: {:?}", call.method_name());
+    application_exception(call, ApplicationExceptionKind::UnknownMethod, reason)
+}
+
+/// Makes the `TApplicationException` message which indicates that
+/// the arguments of `call` could not be decoded.
+pub fn protocol_error(call: &Message, error: &Error) -> Message {
+    application_exception(
+        call,
+        ApplicationExceptionKind::ProtocolError,
+        error.reason(),
+    )
+}
+
+fn application_exception(
+    call: &Message,
+    kind: ApplicationExceptionKind,
+    reason: String,
+) -> Message {
+    let exception = ApplicationException::new(kind, reason);
+    Message::application_exception(call.method_name(), call.sequence_id(), exception)
+}
@@ -1,7 +1,17 @@
 use crate::data::{List, Map, Set, Struct, Uuid};
 
 /// Data.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// `Data::String` is a binary which is known to be a valid UTF-8 string
+/// (e.g., the field is declared as `string` in the schema).
+/// It has the same kind (`DataKind::Binary`) and the same wire encoding as `Data::Binary`,
+/// and a string is equal to the binary consisting of the same bytes.
+/// Decoders always produce `Data::Binary`; use [`Struct::apply_schema`] to turn them into strings.
+///
+/// `Data` (and the other data types) implement `Eq`, `Ord` and `Hash`
+/// based on the total order described in [`DataRef::total_cmp`].
+/// Note that, unlike `f64`, `NaN` is equal to itself and `-0.0` is not equal to `0.0` under this order.
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(missing_docs)]
 pub enum Data {
@@ -12,6 +22,7 @@ pub enum Data {
     I64(i64),
     Double(f64),
     Binary(Vec<u8>),
+    String(String),
     Struct(Struct),
     Map(Map),
     Set(Set),
@@ -28,7 +39,7 @@ impl Data {
             Data::I32(_) => DataKind::I32,
             Data::I64(_) => DataKind::I64,
             Data::Double(_) => DataKind::Double,
-            Data::Binary(_) => DataKind::Binary,
+            Data::Binary(_) | Data::String(_) => DataKind::Binary,
             Data::Struct(_) => DataKind::Struct,
             Data::Map(_) => DataKind::Map,
             Data::Set(_) => DataKind::Set,
@@ -37,8 +48,68 @@ impl Data {
         }
     }
 
+    /// Canonicalizes this data.
+    ///
+    /// After the canonicalization, logically equal data are also structurally equal:
+    ///
+    /// - The fields of structs are sorted by their identifiers.
+    /// - The elements of sets are sorted and deduplicated.
+    /// - The entries of maps are sorted by their keys.
+    ///
+    /// The canonicalization is applied recursively, and the order used for sorting
+    /// is described in [`DataRef::total_cmp`].
+    /// Note that the entries of maps which have duplicate keys are not removed
+    /// because which one should be retained depends on the application (see `Map::dedup_keys`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Data, Field, Set, Struct};
+    ///
+    /// let mut a = Data::from(Struct::new(vec![
+    ///     Field::new(2, Set::from(vec![3, 1, 3])),
+    ///     Field::new(1, "foo"),
+    /// ]));
+    /// let b = Data::from(Struct::new(vec![
+    ///     Field::new(1, "foo"),
+    ///     Field::new(2, Set::from(vec![1, 3])),
+    /// ]));
+    /// assert_ne!(a, b);
+    ///
+    /// a.canonicalize();
+    /// assert_eq!(a, b);
+    /// ```
+    pub fn canonicalize(&mut self) {
+        match *self {
+            Data::Struct(ref mut v) => v.canonicalize(),
+            Data::Map(ref mut v) => v.canonicalize(),
+            Data::Set(ref mut v) => v.canonicalize(),
+            Data::List(ref mut v) => v.canonicalize(),
+            _ => {}
+        }
+    }
+
+    /// Returns the mutable reference to this data.
+    pub fn as_mut(&mut self) -> DataMut<'_> {
+        match *self {
+            Data::Bool(ref mut v) => DataMut::Bool(v),
+            Data::I8(ref mut v) => DataMut::I8(v),
+            Data::I16(ref mut v) => DataMut::I16(v),
+            Data::I32(ref mut v) => DataMut::I32(v),
+            Data::I64(ref mut v) => DataMut::I64(v),
+            Data::Double(ref mut v) => DataMut::Double(v),
+            Data::Binary(ref mut v) => DataMut::Binary(v),
+            Data::String(ref mut v) => DataMut::String(v),
+            Data::Struct(ref mut v) => DataMut::Struct(v),
+            Data::Map(ref mut v) => DataMut::Map(v),
+            Data::Set(ref mut v) => DataMut::Set(v),
+            Data::List(ref mut v) => DataMut::List(v),
+            Data::Uuid(ref mut v) => DataMut::Uuid(v),
+        }
+    }
+
     /// Returns the reference to this data.
-    pub fn as_ref(&self) -> DataRef {
+    pub fn as_ref(&self) -> DataRef<'_> {
         match *self {
             Data::Bool(ref v) => DataRef::Bool(v),
             Data::I8(ref v) => DataRef::I8(v),
@@ -47,6 +118,7 @@ impl Data {
             Data::I64(ref v) => DataRef::I64(v),
             Data::Double(ref v) => DataRef::Double(v),
             Data::Binary(ref v) => DataRef::Binary(v),
+            Data::String(ref v) => DataRef::String(v),
             Data::Struct(ref v) => DataRef::Struct(v),
             Data::Map(ref v) => DataRef::Map(v),
             Data::Set(ref v) => DataRef::Set(v),
@@ -87,12 +159,12 @@ impl From<f64> for Data {
 }
 impl<'a> From<&'a str> for Data {
     fn from(f: &'a str) -> Self {
-        Data::Binary(f.as_bytes().to_owned())
+        Data::String(f.to_owned())
     }
 }
 impl From<String> for Data {
     fn from(f: String) -> Self {
-        Data::Binary(f.into())
+        Data::String(f)
     }
 }
 impl<'a> From<&'a [u8]> for Data {
@@ -132,7 +204,7 @@ impl From<Uuid> for Data {
 }
 
 /// The reference to a `Data`.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(missing_docs)]
 pub enum DataRef<'a> {
@@ -143,6 +215,7 @@ pub enum DataRef<'a> {
     I64(&'a i64),
     Double(&'a f64),
     Binary(&'a [u8]),
+    String(&'a str),
     Struct(&'a Struct),
     Map(&'a Map),
     Set(&'a Set),
@@ -159,7 +232,7 @@ impl<'a> DataRef<'a> {
             DataRef::I32(_) => DataKind::I32,
             DataRef::I64(_) => DataKind::I64,
             DataRef::Double(_) => DataKind::Double,
-            DataRef::Binary(_) => DataKind::Binary,
+            DataRef::Binary(_) | DataRef::String(_) => DataKind::Binary,
             DataRef::Struct(_) => DataKind::Struct,
             DataRef::Map(_) => DataKind::Map,
             DataRef::Set(_) => DataKind::Set,
@@ -178,6 +251,7 @@ impl<'a> DataRef<'a> {
             DataRef::I64(v) => Data::I64(v.to_owned()),
             DataRef::Double(v) => Data::Double(v.to_owned()),
             DataRef::Binary(v) => Data::Binary(v.to_owned()),
+            DataRef::String(v) => Data::String(v.to_owned()),
             DataRef::Struct(v) => Data::Struct(v.to_owned()),
             DataRef::Map(v) => Data::Map(v.to_owned()),
             DataRef::Set(v) => Data::Set(v.to_owned()),
@@ -187,6 +261,144 @@ impl<'a> DataRef<'a> {
     }
 }
 
+/// The mutable reference to a `Data`.
+///
+/// The kind of the referred data can not be changed via this reference.
+#[derive(Debug, PartialEq)]
+#[allow(missing_docs)]
+pub enum DataMut<'a> {
+    Bool(&'a mut bool),
+    I8(&'a mut i8),
+    I16(&'a mut i16),
+    I32(&'a mut i32),
+    I64(&'a mut i64),
+    Double(&'a mut f64),
+    Binary(&'a mut Vec<u8>),
+    String(&'a mut String),
+    Struct(&'a mut Struct),
+    Map(&'a mut Map),
+    Set(&'a mut Set),
+    List(&'a mut List),
+    Uuid(&'a mut Uuid),
+}
+impl<'a> DataMut<'a> {
+    /// Returns the kind of this data.
+    pub fn kind(&self) -> DataKind {
+        match *self {
+            DataMut::Bool(_) => DataKind::Bool,
+            DataMut::I8(_) => DataKind::I8,
+            DataMut::I16(_) => DataKind::I16,
+            DataMut::I32(_) => DataKind::I32,
+            DataMut::I64(_) => DataKind::I64,
+            DataMut::Double(_) => DataKind::Double,
+            DataMut::Binary(_) | DataMut::String(_) => DataKind::Binary,
+            DataMut::Struct(_) => DataKind::Struct,
+            DataMut::Map(_) => DataKind::Map,
+            DataMut::Set(_) => DataKind::Set,
+            DataMut::List(_) => DataKind::List,
+            DataMut::Uuid(_) => DataKind::Uuid,
+        }
+    }
+
+    /// Returns the immutable reference to the referred data.
+    pub fn as_ref(&self) -> DataRef<'_> {
+        match *self {
+            DataMut::Bool(ref v) => DataRef::Bool(v),
+            DataMut::I8(ref v) => DataRef::I8(v),
+            DataMut::I16(ref v) => DataRef::I16(v),
+            DataMut::I32(ref v) => DataRef::I32(v),
+            DataMut::I64(ref v) => DataRef::I64(v),
+            DataMut::Double(ref v) => DataRef::Double(v),
+            DataMut::Binary(ref v) => DataRef::Binary(v),
+            DataMut::String(ref v) => DataRef::String(v),
+            DataMut::Struct(ref v) => DataRef::Struct(v),
+            DataMut::Map(ref v) => DataRef::Map(v),
+            DataMut::Set(ref v) => DataRef::Set(v),
+            DataMut::List(ref v) => DataRef::List(v),
+            DataMut::Uuid(ref v) => DataRef::Uuid(v),
+        }
+    }
+}
+
+macro_rules! impl_accessors {
+    ($($method:ident, $variant:ident, $ty:ty, $deref:tt;)*) => {
+        impl Data {
+            $(
+                #[doc = concat!(
+                    "Returns the value of this data if the kind is `DataKind::",
+                    stringify!($variant),
+                    "`, otherwise `None`."
+                )]
+                pub fn $method(&self) -> Option<impl_accessors!(@type $deref '_ $ty)> {
+                    self.as_ref().$method()
+                }
+            )*
+
+            /// Returns the bytes of this data if the kind is `DataKind::Binary`, otherwise `None`.
+            pub fn as_bytes(&self) -> Option<&[u8]> {
+                self.as_ref().as_bytes()
+            }
+
+            /// Returns the UTF-8 string of this data if the kind is `DataKind::Binary`
+            /// and it is a valid UTF-8 sequence, otherwise `None`.
+            pub fn as_str(&self) -> Option<&str> {
+                self.as_ref().as_str()
+            }
+        }
+        impl<'a> DataRef<'a> {
+            $(
+                #[doc = concat!(
+                    "Returns the value of this data if the kind is `DataKind::",
+                    stringify!($variant),
+                    "`, otherwise `None`."
+                )]
+                pub fn $method(&self) -> Option<impl_accessors!(@type $deref 'a $ty)> {
+                    if let DataRef::$variant(v) = *self {
+                        Some(impl_accessors!(@value $deref v))
+                    } else {
+                        None
+                    }
+                }
+            )*
+
+            /// Returns the bytes of this data if the kind is `DataKind::Binary`, otherwise `None`.
+            pub fn as_bytes(&self) -> Option<&'a [u8]> {
+                match *self {
+                    DataRef::Binary(v) => Some(v),
+                    DataRef::String(v) => Some(v.as_bytes()),
+                    _ => None,
+                }
+            }
+
+            /// Returns the UTF-8 string of this data if the kind is `DataKind::Binary`
+            /// and it is a valid UTF-8 sequence, otherwise `None`.
+            pub fn as_str(&self) -> Option<&'a str> {
+                match *self {
+                    DataRef::String(v) => Some(v),
+                    _ => self.as_bytes().and_then(|v| std::str::from_utf8(v).ok()),
+                }
+            }
+        }
+    };
+    (@type copy $lt:lifetime $ty:ty) => { $ty };
+    (@type ref $lt:lifetime $ty:ty) => { &$lt $ty };
+    (@value copy $v:ident) => { *$v };
+    (@value ref $v:ident) => { $v };
+}
+impl_accessors! {
+    as_bool, Bool, bool, copy;
+    as_i8, I8, i8, copy;
+    as_i16, I16, i16, copy;
+    as_i32, I32, i32, copy;
+    as_i64, I64, i64, copy;
+    as_f64, Double, f64, copy;
+    as_struct, Struct, Struct, ref;
+    as_map, Map, Map, ref;
+    as_set, Set, Set, ref;
+    as_list, List, List, ref;
+    as_uuid, Uuid, Uuid, ref;
+}
+
 /// Available data kinds.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
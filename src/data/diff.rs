@@ -0,0 +1,431 @@
+use crate::data::{Data, DataKind, DataRef, Elements, Map, Path, PathSegment, Struct};
+use crate::message::Message;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Compares `left` and `right` structurally and returns the differences between them.
+///
+/// - The fields of structs are matched by their identifiers.
+/// - The elements of lists are matched by their indices.
+/// - The entries of maps are matched by their keys.
+/// - Sets are compared as (unordered) sets.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{self, Data, DifferenceKind, Field, List, Struct};
+///
+/// let left = Data::from(Struct::new(vec![Field::new(1, List::from(vec![1, 2]))]));
+/// let right = Data::from(Struct::new(vec![
+///     Field::new(1, List::from(vec![1, 3])),
+///     Field::new(2, "foo"),
+/// ]));
+///
+/// let diff = data::diff(&left, &right);
+/// assert_eq!(diff.differences.len(), 2);
+/// assert_eq!(diff.differences[0].path.to_string(), "1[1]");
+/// assert_eq!(
+///     diff.differences[0].kind,
+///     DifferenceKind::ValueChanged { left: Data::I32(2), right: Data::I32(3) }
+/// );
+/// assert_eq!(
+///     diff.to_string(),
+///     "1[1]: value changed: 2 -> 3\n2: added: \"foo\"\n"
+/// );
+/// ```
+pub fn diff(left: &Data, right: &Data) -> Diff {
+    let mut differ = Differ::default();
+    differ.diff(left.as_ref(), right.as_ref());
+    Diff {
+        differences: differ.differences,
+    }
+}
+
+/// Compares the messages `left` and `right` and returns the differences between them.
+///
+/// The differences of the message headers are reported with the paths `method_name`, `kind` and `sequence_id`.
+/// The paths of the differences of the bodies start with `body`.
+pub fn diff_messages(left: &Message, right: &Message) -> Diff {
+    let mut differ = Differ::default();
+    differ.enter(PathSegment::Name("method_name"), |d| {
+        if left.method_name() != right.method_name() {
+            d.push(DifferenceKind::ValueChanged {
+                left: Data::from(left.method_name()),
+                right: Data::from(right.method_name()),
+            });
+        }
+    });
+    differ.enter(PathSegment::Name("kind"), |d| {
+        if left.kind() != right.kind() {
+            d.push(DifferenceKind::ValueChanged {
+                left: Data::I8(left.kind() as i8),
+                right: Data::I8(right.kind() as i8),
+            });
+        }
+    });
+    differ.enter(PathSegment::Name("sequence_id"), |d| {
+        d.diff(
+            DataRef::I32(&left.sequence_id()),
+            DataRef::I32(&right.sequence_id()),
+        );
+    });
+    differ.enter(PathSegment::Name("body"), |d| {
+        d.diff(DataRef::Struct(left.body()), DataRef::Struct(right.body()));
+    });
+    Diff {
+        differences: differ.differences,
+    }
+}
+
+/// The differences between two data.
+///
+/// The `Display` implementation renders one difference per line, which is handy for test failure messages.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diff {
+    /// The detected differences.
+    pub differences: Vec<Difference>,
+}
+impl Diff {
+    /// Returns `true` if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for d in &self.differences {
+            writeln!(f, "{}", d)?;
+        }
+        Ok(())
+    }
+}
+
+/// A difference between two data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Difference {
+    /// The location of the difference.
+    pub path: Path,
+
+    /// The kind of the difference.
+    pub kind: DifferenceKind,
+}
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+/// The kind of a difference.
+///
+/// `left` and `right` denote the data given as the first and second arguments of `diff` respectively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DifferenceKind {
+    /// The field, list element, map entry or set element exists only in the right side.
+    Added(Data),
+
+    /// The field, list element, map entry or set element exists only in the left side.
+    Removed(Data),
+
+    /// The kinds of the data are different.
+    KindChanged {
+        /// The left data.
+        left: Data,
+
+        /// The right data.
+        right: Data,
+    },
+
+    /// The values of the data are different.
+    ValueChanged {
+        /// The left data.
+        left: Data,
+
+        /// The right data.
+        right: Data,
+    },
+
+    /// The lengths of the lists are different.
+    LengthChanged {
+        /// The length of the left list.
+        left: usize,
+
+        /// The length of the right list.
+        right: usize,
+    },
+
+    /// The element kinds of the lists or sets are different.
+    ElementKindChanged {
+        /// The element kind of the left collection.
+        left: DataKind,
+
+        /// The element kind of the right collection.
+        right: DataKind,
+    },
+
+    /// The key kinds of the maps are different.
+    KeyKindChanged {
+        /// The key kind of the left map.
+        left: DataKind,
+
+        /// The key kind of the right map.
+        right: DataKind,
+    },
+
+    /// The value kinds of the maps are different.
+    ValueKindChanged {
+        /// The value kind of the left map.
+        left: DataKind,
+
+        /// The value kind of the right map.
+        right: DataKind,
+    },
+}
+impl fmt::Display for DifferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DifferenceKind::Added(ref v) => write!(f, "added: {}", v),
+            DifferenceKind::Removed(ref v) => write!(f, "removed: {}", v),
+            DifferenceKind::KindChanged {
+                ref left,
+                ref right,
+            } => write!(f, "kind changed: {} -> {}", left, right),
+            DifferenceKind::ValueChanged {
+                ref left,
+                ref right,
+            } => write!(f, "value changed: {} -> {}", left, right),
+            DifferenceKind::LengthChanged { left, right } => {
+                write!(f, "length changed: {} -> {}", left, right)
+            }
+            DifferenceKind::ElementKindChanged { left, right } => {
+                write!(f, "element kind changed: {:?} -> {:?}", left, right)
+            }
+            DifferenceKind::KeyKindChanged { left, right } => {
+                write!(f, "key kind changed: {:?} -> {:?}", left, right)
+            }
+            DifferenceKind::ValueKindChanged { left, right } => {
+                write!(f, "value kind changed: {:?} -> {:?}", left, right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Differ {
+    path: Vec<PathSegment>,
+    differences: Vec<Difference>,
+}
+impl Differ {
+    fn enter<F>(&mut self, segment: PathSegment, f: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        self.path.push(segment);
+        f(self);
+        self.path.pop();
+    }
+
+    fn push(&mut self, kind: DifferenceKind) {
+        self.differences.push(Difference {
+            path: Path::new(self.path.clone()),
+            kind,
+        });
+    }
+
+    fn diff(&mut self, left: DataRef<'_>, right: DataRef<'_>) {
+        match (left, right) {
+            (DataRef::Struct(l), DataRef::Struct(r)) => self.diff_struct(l, r),
+            (DataRef::Map(l), DataRef::Map(r)) => self.diff_map(l, r),
+            (DataRef::Set(l), DataRef::Set(r)) => self.diff_set(l, r),
+            (DataRef::List(l), DataRef::List(r)) => self.diff_list(l, r),
+            (l, r) if l.kind() != r.kind() => self.push(DifferenceKind::KindChanged {
+                left: l.to_owned(),
+                right: r.to_owned(),
+            }),
+            (l, r) if l != r => self.push(DifferenceKind::ValueChanged {
+                left: l.to_owned(),
+                right: r.to_owned(),
+            }),
+            _ => {}
+        }
+    }
+
+    fn diff_struct(&mut self, left: &Struct, right: &Struct) {
+        for field in left.fields() {
+            self.enter(PathSegment::Field(field.id()), |d| {
+                if let Some(r) = right.get(field.id()) {
+                    d.diff(field.data().as_ref(), r.as_ref());
+                } else {
+                    d.push(DifferenceKind::Removed(field.data().clone()));
+                }
+            });
+        }
+        for field in right.fields() {
+            if !left.contains(field.id()) {
+                self.enter(PathSegment::Field(field.id()), |d| {
+                    d.push(DifferenceKind::Added(field.data().clone()));
+                });
+            }
+        }
+    }
+
+    fn diff_map(&mut self, left: &Map, right: &Map) {
+        if left.key_kind() != right.key_kind() {
+            self.push(DifferenceKind::KeyKindChanged {
+                left: left.key_kind(),
+                right: right.key_kind(),
+            });
+            return;
+        }
+        if left.value_kind() != right.value_kind() {
+            self.push(DifferenceKind::ValueKindChanged {
+                left: left.value_kind(),
+                right: right.value_kind(),
+            });
+            return;
+        }
+        for (k, v) in left.iter() {
+            self.enter(PathSegment::Key(k.to_owned()), |d| {
+                if let Some(r) = right.get_by_key(k) {
+                    d.diff(v, r);
+                } else {
+                    d.push(DifferenceKind::Removed(v.to_owned()));
+                }
+            });
+        }
+        for (k, v) in right.iter() {
+            if !left.contains_key(k) {
+                self.enter(PathSegment::Key(k.to_owned()), |d| {
+                    d.push(DifferenceKind::Added(v.to_owned()));
+                });
+            }
+        }
+    }
+
+    fn diff_set(&mut self, left: &Elements, right: &Elements) {
+        if left.kind() != right.kind() {
+            self.push(DifferenceKind::ElementKindChanged {
+                left: left.kind(),
+                right: right.kind(),
+            });
+            return;
+        }
+        let left_set = left.iter().collect::<BTreeSet<_>>();
+        let right_set = right.iter().collect::<BTreeSet<_>>();
+        for e in left.iter() {
+            if !right_set.contains(&e) {
+                self.push(DifferenceKind::Removed(e.to_owned()));
+            }
+        }
+        for e in right.iter() {
+            if !left_set.contains(&e) {
+                self.push(DifferenceKind::Added(e.to_owned()));
+            }
+        }
+    }
+
+    fn diff_list(&mut self, left: &Elements, right: &Elements) {
+        if left.kind() != right.kind() {
+            self.push(DifferenceKind::ElementKindChanged {
+                left: left.kind(),
+                right: right.kind(),
+            });
+            return;
+        }
+        if left.len() != right.len() {
+            self.push(DifferenceKind::LengthChanged {
+                left: left.len(),
+                right: right.len(),
+            });
+        }
+        for i in 0..left.len().max(right.len()) {
+            self.enter(PathSegment::Index(i), |d| {
+                match (left.get(i), right.get(i)) {
+                    (Some(l), Some(r)) => d.diff(l, r),
+                    (Some(l), None) => d.push(DifferenceKind::Removed(l.to_owned())),
+                    (None, Some(r)) => d.push(DifferenceKind::Added(r.to_owned())),
+                    (None, None) => unreachable!(),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Field, List, Set};
+
+    #[test]
+    fn diff_works() {
+        let mut tags = Map::empty_of(DataKind::Binary, DataKind::Binary);
+        track_try_unwrap!(tags.insert("http.method", "GET"));
+        track_try_unwrap!(tags.insert("http.url", "/foo"));
+        let left = Struct::new(vec![
+            Field::new(1, 10i64),
+            Field::new(2, tags.clone()),
+            Field::new(3, Set::from(vec![1, 2])),
+            Field::new(4, List::from(vec![1, 2, 3])),
+            Field::new(5, 1.5),
+        ]);
+
+        let mut tags2 = Map::empty_of(DataKind::Binary, DataKind::Binary);
+        track_try_unwrap!(tags2.insert("http.url", "/bar"));
+        track_try_unwrap!(tags2.insert("http.status", "200"));
+        let right = Struct::new(vec![
+            Field::new(4, List::from(vec![1, 5])),
+            Field::new(3, Set::from(vec![2, 3])),
+            Field::new(2, tags2),
+            Field::new(1, 10i32),
+            Field::new(6, true),
+        ]);
+
+        let diff = diff(&Data::from(left.clone()), &Data::from(right));
+        assert_eq!(
+            diff.to_string(),
+            r#"1: kind changed: i64:10 -> 10
+2{"http.method"}: removed: "GET"
+2{"http.url"}: value changed: "/foo" -> "/bar"
+2{"http.status"}: added: "200"
+3: removed: 1
+3: added: 3
+4: length changed: 3 -> 2
+4[1]: value changed: 2 -> 5
+4[2]: removed: 3
+5: removed: 1.5
+6: added: true
+"#
+        );
+
+        assert!(super::diff(&Data::from(left.clone()), &Data::from(left)).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_map_key_and_value_kind_changes() {
+        let map = |k, v| Data::from(Map::empty_of(k, v));
+        let left = map(DataKind::I32, DataKind::Binary);
+        assert_eq!(
+            diff(&left, &map(DataKind::I64, DataKind::Binary)).differences[0].kind,
+            DifferenceKind::KeyKindChanged {
+                left: DataKind::I32,
+                right: DataKind::I64
+            }
+        );
+        assert_eq!(
+            diff(&left, &map(DataKind::I32, DataKind::Bool)).to_string(),
+            "(root): value kind changed: Binary -> Bool\n"
+        );
+    }
+
+    #[test]
+    fn diff_messages_works() {
+        let left = Message::call("foo", 1, Struct::from((1i32,)));
+        let right = Message::reply("foo", 2, Struct::from((2i32,)));
+        assert_eq!(
+            diff_messages(&left, &right).to_string(),
+            "kind: value changed: i8:1 -> i8:2\nsequence_id: value changed: 1 -> 2\nbody.1: value changed: 1 -> 2\n"
+        );
+    }
+}
@@ -1,7 +1,33 @@
-use crate::data::{DataKind, DataRef, List, Map, Set, Struct, Uuid};
+use crate::data::order::TotalOrd;
+use crate::data::value::unexpected_kind;
+use crate::data::{Data, DataKind, DataMut, DataRef, List, Map, Set, Struct, Uuid};
+use crate::{Error, Result};
+use std::cmp::Ordering;
 
 /// A sequence of the values of a data kind.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Like `Data::String`, `Elements::String` is a sequence of binaries which are known to be valid UTF-8 strings.
+/// Pushing a binary which is not a valid UTF-8 sequence to it turns the sequence into `Elements::Binary`.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::ErrorKind;
+/// use thrift_codec::data::{Data, DataKind, Elements};
+///
+/// let mut elements: Elements = (0..3).collect();
+/// elements.push(Data::I32(3)).unwrap();
+/// elements.insert(0, Data::I32(-1)).unwrap();
+/// assert_eq!(elements.pop(), Some(Data::I32(3)));
+/// assert_eq!(elements, Elements::I32(vec![-1, 0, 1, 2]));
+///
+/// let e = elements.push(Data::I64(4)).unwrap_err();
+/// assert_eq!(
+///     *e.kind(),
+///     ErrorKind::KindMismatch { expected: DataKind::I32, actual: DataKind::I64 }
+/// );
+/// ```
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(missing_docs)]
 pub enum Elements {
@@ -12,12 +38,33 @@ pub enum Elements {
     I64(Vec<i64>),
     Double(Vec<f64>),
     Binary(Vec<Vec<u8>>),
+    String(Vec<String>),
     Struct(Vec<Struct>),
     Map(Vec<Map>),
     Set(Vec<Set>),
     List(Vec<List>),
     Uuid(Vec<Uuid>),
 }
+macro_rules! with_vec {
+    ($elements:expr, $v:ident => $body:expr) => {
+        match $elements {
+            Elements::Bool($v) => $body,
+            Elements::I8($v) => $body,
+            Elements::I16($v) => $body,
+            Elements::I32($v) => $body,
+            Elements::I64($v) => $body,
+            Elements::Double($v) => $body,
+            Elements::Binary($v) => $body,
+            Elements::String($v) => $body,
+            Elements::Struct($v) => $body,
+            Elements::Map($v) => $body,
+            Elements::Set($v) => $body,
+            Elements::List($v) => $body,
+            Elements::Uuid($v) => $body,
+        }
+    };
+}
+
 impl Elements {
     /// Makes an empty sequence which can have the elements belonging to `kind`.
     pub fn new(kind: DataKind) -> Self {
@@ -38,7 +85,7 @@ impl Elements {
     }
 
     /// Returns the element placed at the specified index.
-    pub fn get(&self, index: usize) -> Option<DataRef> {
+    pub fn get(&self, index: usize) -> Option<DataRef<'_>> {
         match *self {
             Elements::Bool(ref v) => v.get(index).map(DataRef::Bool),
             Elements::I8(ref v) => v.get(index).map(DataRef::I8),
@@ -47,6 +94,7 @@ impl Elements {
             Elements::I64(ref v) => v.get(index).map(DataRef::I64),
             Elements::Double(ref v) => v.get(index).map(DataRef::Double),
             Elements::Binary(ref v) => v.get(index).map(|e| DataRef::Binary(e.as_ref())),
+            Elements::String(ref v) => v.get(index).map(|e| DataRef::String(e.as_ref())),
             Elements::Struct(ref v) => v.get(index).map(DataRef::Struct),
             Elements::Map(ref v) => v.get(index).map(DataRef::Map),
             Elements::Set(ref v) => v.get(index).map(DataRef::Set),
@@ -65,6 +113,7 @@ impl Elements {
             Elements::I64(ref v) => v.len(),
             Elements::Double(ref v) => v.len(),
             Elements::Binary(ref v) => v.len(),
+            Elements::String(ref v) => v.len(),
             Elements::Struct(ref v) => v.len(),
             Elements::Map(ref v) => v.len(),
             Elements::Set(ref v) => v.len(),
@@ -79,7 +128,7 @@ impl Elements {
     }
 
     /// Returns an iterator over the elements of this sequence.
-    pub fn iter(&self) -> ElementIter {
+    pub fn iter(&self) -> ElementIter<'_> {
         ElementIter {
             elements: self,
             index: 0,
@@ -95,7 +144,7 @@ impl Elements {
             Elements::I32(_) => DataKind::I32,
             Elements::I64(_) => DataKind::I64,
             Elements::Double(_) => DataKind::Double,
-            Elements::Binary(_) => DataKind::Binary,
+            Elements::Binary(_) | Elements::String(_) => DataKind::Binary,
             Elements::Struct(_) => DataKind::Struct,
             Elements::Map(_) => DataKind::Map,
             Elements::Set(_) => DataKind::Set,
@@ -103,6 +152,344 @@ impl Elements {
             Elements::Uuid(_) => DataKind::Uuid,
         }
     }
+
+    /// Returns an iterator which yields the mutable references to the elements of this sequence.
+    pub fn iter_mut(&mut self) -> ElementIterMut<'_> {
+        let inner = match *self {
+            Elements::Bool(ref mut v) => IterMutInner::Bool(v.iter_mut()),
+            Elements::I8(ref mut v) => IterMutInner::I8(v.iter_mut()),
+            Elements::I16(ref mut v) => IterMutInner::I16(v.iter_mut()),
+            Elements::I32(ref mut v) => IterMutInner::I32(v.iter_mut()),
+            Elements::I64(ref mut v) => IterMutInner::I64(v.iter_mut()),
+            Elements::Double(ref mut v) => IterMutInner::Double(v.iter_mut()),
+            Elements::Binary(ref mut v) => IterMutInner::Binary(v.iter_mut()),
+            Elements::String(ref mut v) => IterMutInner::String(v.iter_mut()),
+            Elements::Struct(ref mut v) => IterMutInner::Struct(v.iter_mut()),
+            Elements::Map(ref mut v) => IterMutInner::Map(v.iter_mut()),
+            Elements::Set(ref mut v) => IterMutInner::Set(v.iter_mut()),
+            Elements::List(ref mut v) => IterMutInner::List(v.iter_mut()),
+            Elements::Uuid(ref mut v) => IterMutInner::Uuid(v.iter_mut()),
+        };
+        ElementIterMut(inner)
+    }
+
+    /// Appends `data` to the end of this sequence.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of `data` is different from the kind of this sequence,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    pub fn push(&mut self, data: Data) -> Result<()> {
+        let data = self.adapt(data);
+        match (self, data) {
+            (Elements::Bool(v), Data::Bool(d)) => v.push(d),
+            (Elements::I8(v), Data::I8(d)) => v.push(d),
+            (Elements::I16(v), Data::I16(d)) => v.push(d),
+            (Elements::I32(v), Data::I32(d)) => v.push(d),
+            (Elements::I64(v), Data::I64(d)) => v.push(d),
+            (Elements::Double(v), Data::Double(d)) => v.push(d),
+            (Elements::Binary(v), Data::Binary(d)) => v.push(d),
+            (Elements::String(v), Data::String(d)) => v.push(d),
+            (Elements::Struct(v), Data::Struct(d)) => v.push(d),
+            (Elements::Map(v), Data::Map(d)) => v.push(d),
+            (Elements::Set(v), Data::Set(d)) => v.push(d),
+            (Elements::List(v), Data::List(d)) => v.push(d),
+            (Elements::Uuid(v), Data::Uuid(d)) => v.push(d),
+            (elements, data) => return Err(unexpected_kind(elements.kind(), data.kind())),
+        }
+        Ok(())
+    }
+
+    /// Appends all the data yielded by `iter` to the end of this sequence.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of some data is different from the kind of this sequence,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    /// In that case, this sequence is left unchanged.
+    pub fn extend<I>(&mut self, iter: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Data>,
+    {
+        let mut elements = self.empty_like();
+        for data in iter {
+            track!(elements.push(data))?;
+        }
+        self.append(elements);
+        Ok(())
+    }
+
+    fn append(&mut self, other: Elements) {
+        if matches!(other, Elements::Binary(_)) {
+            self.demote();
+        }
+        match (self, other) {
+            (Elements::Bool(v), Elements::Bool(mut o)) => v.append(&mut o),
+            (Elements::I8(v), Elements::I8(mut o)) => v.append(&mut o),
+            (Elements::I16(v), Elements::I16(mut o)) => v.append(&mut o),
+            (Elements::I32(v), Elements::I32(mut o)) => v.append(&mut o),
+            (Elements::I64(v), Elements::I64(mut o)) => v.append(&mut o),
+            (Elements::Double(v), Elements::Double(mut o)) => v.append(&mut o),
+            (Elements::Binary(v), Elements::Binary(mut o)) => v.append(&mut o),
+            (Elements::String(v), Elements::String(mut o)) => v.append(&mut o),
+            (Elements::Struct(v), Elements::Struct(mut o)) => v.append(&mut o),
+            (Elements::Map(v), Elements::Map(mut o)) => v.append(&mut o),
+            (Elements::Set(v), Elements::Set(mut o)) => v.append(&mut o),
+            (Elements::List(v), Elements::List(mut o)) => v.append(&mut o),
+            (Elements::Uuid(v), Elements::Uuid(mut o)) => v.append(&mut o),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Removes the last element from this sequence and returns it.
+    pub fn pop(&mut self) -> Option<Data> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove(self.len() - 1))
+        }
+    }
+
+    /// Inserts `data` at position `index` within this sequence,
+    /// shifting all the elements after it to the right.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of `data` is different from the kind of this sequence,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, data: Data) -> Result<()> {
+        let data = self.adapt(data);
+        match (self, data) {
+            (Elements::Bool(v), Data::Bool(d)) => v.insert(index, d),
+            (Elements::I8(v), Data::I8(d)) => v.insert(index, d),
+            (Elements::I16(v), Data::I16(d)) => v.insert(index, d),
+            (Elements::I32(v), Data::I32(d)) => v.insert(index, d),
+            (Elements::I64(v), Data::I64(d)) => v.insert(index, d),
+            (Elements::Double(v), Data::Double(d)) => v.insert(index, d),
+            (Elements::Binary(v), Data::Binary(d)) => v.insert(index, d),
+            (Elements::String(v), Data::String(d)) => v.insert(index, d),
+            (Elements::Struct(v), Data::Struct(d)) => v.insert(index, d),
+            (Elements::Map(v), Data::Map(d)) => v.insert(index, d),
+            (Elements::Set(v), Data::Set(d)) => v.insert(index, d),
+            (Elements::List(v), Data::List(d)) => v.insert(index, d),
+            (Elements::Uuid(v), Data::Uuid(d)) => v.insert(index, d),
+            (elements, data) => return Err(unexpected_kind(elements.kind(), data.kind())),
+        }
+        Ok(())
+    }
+
+    /// Shortens this sequence, keeping the first `len` elements and dropping the rest.
+    ///
+    /// If `len` is greater than the current length, this has no effect.
+    pub fn truncate(&mut self, len: usize) {
+        match *self {
+            Elements::Bool(ref mut v) => v.truncate(len),
+            Elements::I8(ref mut v) => v.truncate(len),
+            Elements::I16(ref mut v) => v.truncate(len),
+            Elements::I32(ref mut v) => v.truncate(len),
+            Elements::I64(ref mut v) => v.truncate(len),
+            Elements::Double(ref mut v) => v.truncate(len),
+            Elements::Binary(ref mut v) => v.truncate(len),
+            Elements::String(ref mut v) => v.truncate(len),
+            Elements::Struct(ref mut v) => v.truncate(len),
+            Elements::Map(ref mut v) => v.truncate(len),
+            Elements::Set(ref mut v) => v.truncate(len),
+            Elements::List(ref mut v) => v.truncate(len),
+            Elements::Uuid(ref mut v) => v.truncate(len),
+        }
+    }
+
+    /// Replaces the element placed at `index` with `data` and returns the old one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub(crate) fn replace(&mut self, index: usize, data: Data) -> Result<Data> {
+        use std::mem::replace;
+        let data = self.adapt(data);
+        Ok(match (self, data) {
+            (Elements::Bool(v), Data::Bool(d)) => Data::Bool(replace(&mut v[index], d)),
+            (Elements::I8(v), Data::I8(d)) => Data::I8(replace(&mut v[index], d)),
+            (Elements::I16(v), Data::I16(d)) => Data::I16(replace(&mut v[index], d)),
+            (Elements::I32(v), Data::I32(d)) => Data::I32(replace(&mut v[index], d)),
+            (Elements::I64(v), Data::I64(d)) => Data::I64(replace(&mut v[index], d)),
+            (Elements::Double(v), Data::Double(d)) => Data::Double(replace(&mut v[index], d)),
+            (Elements::Binary(v), Data::Binary(d)) => Data::Binary(replace(&mut v[index], d)),
+            (Elements::String(v), Data::String(d)) => Data::String(replace(&mut v[index], d)),
+            (Elements::Struct(v), Data::Struct(d)) => Data::Struct(replace(&mut v[index], d)),
+            (Elements::Map(v), Data::Map(d)) => Data::Map(replace(&mut v[index], d)),
+            (Elements::Set(v), Data::Set(d)) => Data::Set(replace(&mut v[index], d)),
+            (Elements::List(v), Data::List(d)) => Data::List(replace(&mut v[index], d)),
+            (Elements::Uuid(v), Data::Uuid(d)) => Data::Uuid(replace(&mut v[index], d)),
+            (elements, data) => return Err(unexpected_kind(elements.kind(), data.kind())),
+        })
+    }
+
+    /// Removes the element placed at `index` and returns it,
+    /// shifting all the elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Data {
+        match *self {
+            Elements::Bool(ref mut v) => Data::Bool(v.remove(index)),
+            Elements::I8(ref mut v) => Data::I8(v.remove(index)),
+            Elements::I16(ref mut v) => Data::I16(v.remove(index)),
+            Elements::I32(ref mut v) => Data::I32(v.remove(index)),
+            Elements::I64(ref mut v) => Data::I64(v.remove(index)),
+            Elements::Double(ref mut v) => Data::Double(v.remove(index)),
+            Elements::Binary(ref mut v) => Data::Binary(v.remove(index)),
+            Elements::String(ref mut v) => Data::String(v.remove(index)),
+            Elements::Struct(ref mut v) => Data::Struct(v.remove(index)),
+            Elements::Map(ref mut v) => Data::Map(v.remove(index)),
+            Elements::Set(ref mut v) => Data::Set(v.remove(index)),
+            Elements::List(ref mut v) => Data::List(v.remove(index)),
+            Elements::Uuid(ref mut v) => Data::Uuid(v.remove(index)),
+        }
+    }
+
+    /// Converts the binary elements of this sequence to strings.
+    ///
+    /// # Errors
+    ///
+    /// If some element is not a valid UTF-8 sequence,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    /// In that case, this sequence is left unchanged.
+    pub(crate) fn promote(&mut self) -> Result<()> {
+        if let Elements::Binary(v) = self {
+            let strings = v
+                .iter()
+                .map(|b| std::str::from_utf8(b).map(str::to_owned))
+                .collect::<std::result::Result<Vec<_>, _>>();
+            *self = Elements::String(track!(strings.map_err(Error::from))?);
+        }
+        Ok(())
+    }
+
+    /// Converts the string elements of this sequence to binaries.
+    pub(crate) fn demote(&mut self) {
+        if let Elements::String(v) = self {
+            let v = std::mem::take(v);
+            *self = Elements::Binary(v.into_iter().map(String::into_bytes).collect());
+        }
+    }
+
+    /// Makes an empty sequence which has the same representation as this sequence.
+    fn empty_like(&self) -> Self {
+        match *self {
+            Elements::String(_) => Elements::String(Vec::new()),
+            _ => Elements::new(self.kind()),
+        }
+    }
+
+    /// Converts the representation of `data` to the one of this sequence (binary or string).
+    ///
+    /// If `data` is a binary which is not a valid UTF-8 sequence, this sequence is converted to binaries.
+    fn adapt(&mut self, data: Data) -> Data {
+        match (data, matches!(self, Elements::String(_))) {
+            (Data::String(d), false) => Data::Binary(d.into_bytes()),
+            (Data::Binary(d), true) => match String::from_utf8(d) {
+                Ok(d) => Data::String(d),
+                Err(e) => {
+                    self.demote();
+                    Data::Binary(e.into_bytes())
+                }
+            },
+            (data, _) => data,
+        }
+    }
+
+    /// Canonicalizes the elements of this sequence (see `Data::canonicalize`).
+    pub(crate) fn canonicalize_each(&mut self) {
+        match *self {
+            Elements::Struct(ref mut v) => v.iter_mut().for_each(Struct::canonicalize),
+            Elements::Map(ref mut v) => v.iter_mut().for_each(Map::canonicalize),
+            Elements::Set(ref mut v) => v.iter_mut().for_each(Set::canonicalize),
+            Elements::List(ref mut v) => v.iter_mut().for_each(List::canonicalize),
+            _ => {}
+        }
+    }
+
+    /// Sorts the elements of this sequence in the total order.
+    pub(crate) fn sort(&mut self) {
+        with_vec!(self, v => v.sort_by(TotalOrd::total_cmp))
+    }
+
+    /// Returns the indices of the elements of this sequence sorted in the total order.
+    ///
+    /// The sort is stable.
+    pub(crate) fn sorted_indices(&self) -> Vec<usize> {
+        let mut indices = (0..self.len()).collect::<Vec<_>>();
+        with_vec!(self, v => indices.sort_by(|&i, &j| v[i].total_cmp(&v[j])));
+        indices
+    }
+
+    /// Compares the elements placed at `i` and `j` in the total order.
+    pub(crate) fn cmp_elements(&self, i: usize, j: usize) -> Ordering {
+        with_vec!(self, v => v[i].total_cmp(&v[j]))
+    }
+
+    /// Retains only the elements which corresponding flags in `keep` are `true`.
+    pub(crate) fn retain_by_flags(&mut self, keep: &[bool]) {
+        let mut flags = keep.iter();
+        with_vec!(self, v => v.retain(|_| *flags.next().expect("Never fails")))
+    }
+
+    /// Reorders the elements of this sequence so that the `i`-th element is the `order[i]`-th element before.
+    pub(crate) fn reorder(&mut self, order: &[usize]) {
+        with_vec!(self, v => {
+            let mut slots = std::mem::take(v).into_iter().map(Some).collect::<Vec<_>>();
+            *v = order
+                .iter()
+                .map(|&i| slots[i].take().expect("Never fails"))
+                .collect();
+        })
+    }
+
+    /// Returns the flags which indicate the elements to be retained to remove duplicates.
+    ///
+    /// For each group of equal elements, only the first (or last) one is retained.
+    pub(crate) fn dedup_flags(&self, keep_last: bool) -> Vec<bool> {
+        let indices = self.sorted_indices();
+        let mut keep = vec![false; indices.len()];
+        let mut start = 0;
+        while start < indices.len() {
+            let mut end = start + 1;
+            while end < indices.len()
+                && self.cmp_elements(indices[start], indices[end]) == Ordering::Equal
+            {
+                end += 1;
+            }
+            // The sort is stable, so the indices in the group are in ascending order.
+            let i = if keep_last { end - 1 } else { start };
+            keep[indices[i]] = true;
+            start = end;
+        }
+        keep
+    }
+
+    pub(crate) fn into_data_vec(self) -> Vec<Data> {
+        match self {
+            Elements::Bool(v) => v.into_iter().map(Data::Bool).collect(),
+            Elements::I8(v) => v.into_iter().map(Data::I8).collect(),
+            Elements::I16(v) => v.into_iter().map(Data::I16).collect(),
+            Elements::I32(v) => v.into_iter().map(Data::I32).collect(),
+            Elements::I64(v) => v.into_iter().map(Data::I64).collect(),
+            Elements::Double(v) => v.into_iter().map(Data::Double).collect(),
+            Elements::Binary(v) => v.into_iter().map(Data::Binary).collect(),
+            Elements::String(v) => v.into_iter().map(Data::String).collect(),
+            Elements::Struct(v) => v.into_iter().map(Data::Struct).collect(),
+            Elements::Map(v) => v.into_iter().map(Data::Map).collect(),
+            Elements::Set(v) => v.into_iter().map(Data::Set).collect(),
+            Elements::List(v) => v.into_iter().map(Data::List).collect(),
+            Elements::Uuid(v) => v.into_iter().map(Data::Uuid).collect(),
+        }
+    }
 }
 impl From<Vec<bool>> for Elements {
     fn from(f: Vec<bool>) -> Self {
@@ -139,6 +526,11 @@ impl From<Vec<Vec<u8>>> for Elements {
         Elements::Binary(f)
     }
 }
+impl From<Vec<String>> for Elements {
+    fn from(f: Vec<String>) -> Self {
+        Elements::String(f)
+    }
+}
 impl From<Vec<Struct>> for Elements {
     fn from(f: Vec<Struct>) -> Self {
         Elements::Struct(f)
@@ -165,6 +557,91 @@ impl From<Vec<Uuid>> for Elements {
     }
 }
 
+macro_rules! impl_try_from_elements {
+    ($($variant:ident, $ty:ty;)*) => {
+        $(
+            impl TryFrom<Elements> for Vec<$ty> {
+                type Error = Error;
+
+                fn try_from(f: Elements) -> Result<Self> {
+                    match f {
+                        Elements::$variant(v) => Ok(v),
+                        f => Err(unexpected_kind(DataKind::$variant, f.kind())),
+                    }
+                }
+            }
+        )*
+    };
+}
+impl_try_from_elements! {
+    Bool, bool;
+    I8, i8;
+    I16, i16;
+    I32, i32;
+    I64, i64;
+    Double, f64;
+    Struct, Struct;
+    Map, Map;
+    Set, Set;
+    List, List;
+    Uuid, Uuid;
+}
+impl TryFrom<Elements> for Vec<Vec<u8>> {
+    type Error = Error;
+
+    fn try_from(f: Elements) -> Result<Self> {
+        match f {
+            Elements::Binary(v) => Ok(v),
+            Elements::String(v) => Ok(v.into_iter().map(String::into_bytes).collect()),
+            f => Err(unexpected_kind(DataKind::Binary, f.kind())),
+        }
+    }
+}
+impl TryFrom<Elements> for Vec<String> {
+    type Error = Error;
+
+    fn try_from(mut f: Elements) -> Result<Self> {
+        if f.kind() != DataKind::Binary {
+            return Err(unexpected_kind(DataKind::Binary, f.kind()));
+        }
+        track!(f.promote())?;
+        match f {
+            Elements::String(v) => Ok(v),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Elements
+where
+    Vec<T>: Into<Elements>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().collect::<Vec<_>>().into()
+    }
+}
+impl IntoIterator for Elements {
+    type Item = Data;
+    type IntoIter = ElementIntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        ElementIntoIter(self.into_data_vec().into_iter())
+    }
+}
+impl<'a> IntoIterator for &'a Elements {
+    type Item = DataRef<'a>;
+    type IntoIter = ElementIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+impl<'a> IntoIterator for &'a mut Elements {
+    type Item = DataMut<'a>;
+    type IntoIter = ElementIterMut<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 /// An iterator which traverse the elements of a `Elements`.
 #[derive(Debug)]
 pub struct ElementIter<'a> {
@@ -178,3 +655,139 @@ impl<'a> Iterator for ElementIter<'a> {
         self.elements.get(self.index - 1)
     }
 }
+
+/// An iterator which yields the mutable references to the elements of a `Elements`.
+#[derive(Debug)]
+pub struct ElementIterMut<'a>(IterMutInner<'a>);
+impl<'a> Iterator for ElementIterMut<'a> {
+    type Item = DataMut<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0 {
+            IterMutInner::Bool(ref mut i) => i.next().map(DataMut::Bool),
+            IterMutInner::I8(ref mut i) => i.next().map(DataMut::I8),
+            IterMutInner::I16(ref mut i) => i.next().map(DataMut::I16),
+            IterMutInner::I32(ref mut i) => i.next().map(DataMut::I32),
+            IterMutInner::I64(ref mut i) => i.next().map(DataMut::I64),
+            IterMutInner::Double(ref mut i) => i.next().map(DataMut::Double),
+            IterMutInner::Binary(ref mut i) => i.next().map(DataMut::Binary),
+            IterMutInner::String(ref mut i) => i.next().map(DataMut::String),
+            IterMutInner::Struct(ref mut i) => i.next().map(DataMut::Struct),
+            IterMutInner::Map(ref mut i) => i.next().map(DataMut::Map),
+            IterMutInner::Set(ref mut i) => i.next().map(DataMut::Set),
+            IterMutInner::List(ref mut i) => i.next().map(DataMut::List),
+            IterMutInner::Uuid(ref mut i) => i.next().map(DataMut::Uuid),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum IterMutInner<'a> {
+    Bool(std::slice::IterMut<'a, bool>),
+    I8(std::slice::IterMut<'a, i8>),
+    I16(std::slice::IterMut<'a, i16>),
+    I32(std::slice::IterMut<'a, i32>),
+    I64(std::slice::IterMut<'a, i64>),
+    Double(std::slice::IterMut<'a, f64>),
+    Binary(std::slice::IterMut<'a, Vec<u8>>),
+    String(std::slice::IterMut<'a, String>),
+    Struct(std::slice::IterMut<'a, Struct>),
+    Map(std::slice::IterMut<'a, Map>),
+    Set(std::slice::IterMut<'a, Set>),
+    List(std::slice::IterMut<'a, List>),
+    Uuid(std::slice::IterMut<'a, Uuid>),
+}
+
+/// An iterator which moves the elements out of a `Elements`.
+#[derive(Debug)]
+pub struct ElementIntoIter(std::vec::IntoIter<Data>);
+impl Iterator for ElementIntoIter {
+    type Item = Data;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+impl ExactSizeIterator for ElementIntoIter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    fn mismatch(expected: DataKind, actual: DataKind) -> ErrorKind {
+        ErrorKind::KindMismatch { expected, actual }
+    }
+
+    #[test]
+    fn push_and_insert_reject_other_kinds() {
+        let mut elements = Elements::I32(vec![1, 2]);
+        let e = elements.push(Data::I64(3)).unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::I32, DataKind::I64));
+        let e = elements.insert(0, Data::from("foo")).unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::I32, DataKind::Binary));
+        assert_eq!(elements, Elements::I32(vec![1, 2]));
+
+        let mut elements = Elements::new(DataKind::Struct);
+        let e = elements
+            .push(Data::Map(Map::empty_of(DataKind::I8, DataKind::I8)))
+            .unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::Struct, DataKind::Map));
+        assert!(elements.is_empty());
+    }
+
+    #[test]
+    fn extend_leaves_elements_unchanged_on_failure() {
+        let mut elements = Elements::I32(vec![1]);
+        let e = elements
+            .extend(vec![Data::I32(2), Data::I64(3), Data::I32(4)])
+            .unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::I32, DataKind::I64));
+        assert_eq!(elements, Elements::I32(vec![1]));
+
+        track_try_unwrap!(elements.extend(vec![Data::I32(2), Data::I32(3)]));
+        assert_eq!(elements, Elements::I32(vec![1, 2, 3]));
+
+        // The sequence is not demoted to binaries by the elements pushed before the failure.
+        let mut elements = Elements::String(vec!["foo".to_owned()]);
+        let e = elements
+            .extend(vec![Data::Binary(vec![0xff]), Data::I32(1)])
+            .unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::Binary, DataKind::I32));
+        assert!(matches!(elements, Elements::String(ref v) if v == &["foo"]));
+    }
+
+    #[test]
+    fn removal_preserves_kind() {
+        let mut elements = Elements::I64(vec![1, 2, 3, 4]);
+        assert_eq!(elements.remove(1), Data::I64(2));
+        assert_eq!(elements.pop(), Some(Data::I64(4)));
+        elements.truncate(5);
+        assert_eq!(elements, Elements::I64(vec![1, 3]));
+        elements.truncate(0);
+        assert_eq!(elements.pop(), None);
+
+        assert_eq!(elements.kind(), DataKind::I64);
+        let e = elements.push(Data::I32(1)).unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::I64, DataKind::I32));
+        track_try_unwrap!(elements.push(Data::I64(1)));
+        assert_eq!(elements, Elements::I64(vec![1]));
+    }
+
+    #[test]
+    fn iter_mut_yields_references_of_the_kind() {
+        let mut elements = Elements::Double(vec![1.0, 2.0]);
+        for data in elements.iter_mut() {
+            assert_eq!(data.kind(), DataKind::Double);
+            if let DataMut::Double(v) = data {
+                *v *= 2.0;
+            }
+        }
+        assert_eq!(elements, Elements::Double(vec![2.0, 4.0]));
+
+        let e = elements.push(Data::I32(1)).unwrap_err();
+        assert_eq!(*e.kind(), mismatch(DataKind::Double, DataKind::I32));
+        assert_eq!(elements.len(), 2);
+    }
+}
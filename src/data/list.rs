@@ -1,10 +1,32 @@
+use crate::data::iterators::{ElementIntoIter, ElementIter, ElementIterMut};
+use crate::data::{Data, DataMut, DataRef};
 use crate::data::{Elements, Map, Set, Struct};
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 
 use super::Uuid;
 
 /// List.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{Data, DataMut, List};
+///
+/// let mut list: List = vec!["foo", "bar"].into_iter().map(String::from).collect();
+/// list.push(Data::from("baz")).unwrap();
+/// list.extend(vec![Data::from("qux")]).unwrap();
+/// list.truncate(3);
+///
+/// for data in &mut list {
+///     if let DataMut::String(v) = data {
+///         v.make_ascii_uppercase();
+///     }
+/// }
+///
+/// let strings = list.into_iter().filter_map(|d| d.as_str().map(String::from)).collect::<Vec<_>>();
+/// assert_eq!(strings, ["FOO", "BAR", "BAZ"]);
+/// ```
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct List {
     elements: Elements,
@@ -14,6 +36,18 @@ impl List {
     pub fn new(elements: Elements) -> Self {
         List { elements }
     }
+
+    /// Canonicalizes the elements of this list recursively.
+    ///
+    /// The order of the elements is preserved.
+    /// See [`Data::canonicalize`] for more details.
+    pub fn canonicalize(&mut self) {
+        self.elements.canonicalize_each();
+    }
+
+    pub(crate) fn into_elements(self) -> Elements {
+        self.elements
+    }
 }
 impl Deref for List {
     type Target = Elements;
@@ -21,6 +55,40 @@ impl Deref for List {
         &self.elements
     }
 }
+impl DerefMut for List {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.elements
+    }
+}
+impl<T> FromIterator<T> for List
+where
+    Vec<T>: Into<Elements>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        List::new(iter.into_iter().collect())
+    }
+}
+impl IntoIterator for List {
+    type Item = Data;
+    type IntoIter = ElementIntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a List {
+    type Item = DataRef<'a>;
+    type IntoIter = ElementIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+impl<'a> IntoIterator for &'a mut List {
+    type Item = DataMut<'a>;
+    type IntoIter = ElementIterMut<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter_mut()
+    }
+}
 impl From<Vec<bool>> for List {
     fn from(f: Vec<bool>) -> Self {
         List::new(Elements::Bool(f))
@@ -56,6 +124,11 @@ impl From<Vec<Vec<u8>>> for List {
         List::new(Elements::Binary(f))
     }
 }
+impl From<Vec<String>> for List {
+    fn from(f: Vec<String>) -> Self {
+        List::new(Elements::String(f))
+    }
+}
 impl From<Vec<Struct>> for List {
     fn from(f: Vec<Struct>) -> Self {
         List::new(Elements::Struct(f))
@@ -81,3 +154,41 @@ impl From<Vec<Uuid>> for List {
         List::new(Elements::Uuid(f))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataKind;
+    use crate::ErrorKind;
+
+    #[test]
+    fn mutation_checks_kinds() {
+        let mut list = List::from(vec![1i8, 2]);
+        let e = list.push(Data::I16(3)).unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::I8,
+                actual: DataKind::I16
+            }
+        );
+        assert!(list.insert(1, Data::Bool(true)).is_err());
+        assert!(list.extend(vec![Data::I8(3), Data::I16(4)]).is_err());
+        assert_eq!(list, List::from(vec![1i8, 2]));
+
+        track_try_unwrap!(list.extend(vec![Data::I8(3), Data::I8(4)]));
+        assert_eq!(list.remove(0), Data::I8(1));
+        assert_eq!(list.pop(), Some(Data::I8(4)));
+        list.truncate(1);
+        for data in &mut list {
+            if let DataMut::I8(v) = data {
+                *v = -*v;
+            }
+        }
+        assert_eq!(list, List::from(vec![-2i8]));
+
+        list.truncate(0);
+        assert_eq!(list.kind(), DataKind::I8);
+        assert!(list.push(Data::I16(1)).is_err());
+    }
+}
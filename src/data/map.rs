@@ -1,19 +1,67 @@
-use crate::data::{DataKind, DataRef, Elements};
-use crate::{ErrorKind, Result};
+use crate::data::value::unexpected_kind;
+use crate::data::{Data, DataKind, DataRef, Elements, ThriftValue};
+use crate::{Error, ErrorKind, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
 
 /// Map.
 ///
 /// Internally this is represented by the data structure called "associative array".
 /// No duplicate keys are removed.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use thrift_codec::data::{DataRef, Map};
+///
+/// let mut tags = HashMap::new();
+/// tags.insert("http.method".to_owned(), "GET".to_owned());
+///
+/// let mut map = Map::from(tags);
+/// map.insert("http.status_code", "200").unwrap();
+/// assert_eq!(
+///     map.get_by_key(DataRef::Binary(b"http.method")),
+///     Some(DataRef::Binary(b"GET"))
+/// );
+///
+/// let tags = HashMap::<String, String>::try_from(map).unwrap();
+/// assert_eq!(tags.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
-pub struct Map(Option<Inner>);
+pub struct Map {
+    keys: Elements,
+    values: Elements,
+}
 impl Map {
-    /// Makes an empty `Map` instance.
-    ///
-    /// Note that the returning value could not be encoded by the binary protocol encoding.
+    /// Makes an empty `Map` instance which key and value kinds are `DataKind::Binary`.
+    #[deprecated(note = "Use `Map::empty_of` instead")]
     pub fn empty() -> Self {
-        Map(None)
+        Map::empty_of(DataKind::Binary, DataKind::Binary)
+    }
+
+    /// Makes an empty `Map` instance which can have the entries belonging to the given kinds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::BinaryEncode;
+    /// use thrift_codec::data::{DataKind, Map};
+    ///
+    /// let map = Map::empty_of(DataKind::Binary, DataKind::I32);
+    /// assert_eq!(map.key_kind(), DataKind::Binary);
+    /// assert_eq!(map.value_kind(), DataKind::I32);
+    ///
+    /// let mut buf = Vec::new();
+    /// map.binary_encode(&mut buf).unwrap();
+    /// assert_eq!(buf, [11, 8, 0, 0, 0, 0]);
+    /// ```
+    pub fn empty_of(key_kind: DataKind, value_kind: DataKind) -> Self {
+        Map {
+            keys: Elements::new(key_kind),
+            values: Elements::new(value_kind),
+        }
     }
 
     /// Makes a new `Map` instance.
@@ -29,10 +77,10 @@ impl Map {
             keys.push(k);
             values.push(v);
         }
-        Map(Some(Inner {
+        Map {
             keys: keys.into(),
             values: values.into(),
-        }))
+        }
     }
 
     /// Makes a new `Map` instance from the separate `keys` and `values`.
@@ -43,16 +91,74 @@ impl Map {
     /// this function will return an error which kind is `ErrorKind::InvalidInput`.
     pub fn from_keys_and_values(keys: Elements, values: Elements) -> Result<Self> {
         track_assert_eq!(keys.len(), values.len(), ErrorKind::InvalidInput);
-        Ok(Map(Some(Inner { keys, values })))
+        Ok(Map { keys, values })
     }
 
     /// Returns the entry placed at the specified index.
-    pub fn get(&self, index: usize) -> Option<(DataRef, DataRef)> {
-        self.0.as_ref().and_then(|inner| inner.get(index))
+    pub fn get(&self, index: usize) -> Option<(DataRef<'_>, DataRef<'_>)> {
+        self.keys
+            .get(index)
+            .map(|k| (k, self.values.get(index).expect("Never fails")))
+    }
+
+    /// Returns the value associated with `key`.
+    ///
+    /// If there are duplicate keys, the value of the first entry is returned.
+    pub fn get_by_key(&self, key: DataRef<'_>) -> Option<DataRef<'_>> {
+        self.position(key)
+            .map(|i| self.values.get(i).expect("Never fails"))
+    }
+
+    /// Returns `true` if this map contains an entry with `key`.
+    pub fn contains_key(&self, key: DataRef<'_>) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Inserts the entry into this map.
+    ///
+    /// If this map already contains an entry with `key`, its value is replaced and the old value is returned.
+    /// Otherwise, the entry is appended to the end of this map.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of `key` or `value` is different from the key or value kind of this map,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Result<Option<Data>>
+    where
+        K: Into<Data>,
+        V: Into<Data>,
+    {
+        let key = key.into();
+        let value = value.into();
+        if key.kind() != self.key_kind() {
+            return Err(unexpected_kind(self.key_kind(), key.kind()));
+        }
+        if value.kind() != self.value_kind() {
+            return Err(unexpected_kind(self.value_kind(), value.kind()));
+        }
+        if let Some(i) = self.position(key.as_ref()) {
+            track!(self.values.replace(i, value)).map(Some)
+        } else {
+            track!(self.keys.push(key))?;
+            track!(self.values.push(value))?;
+            Ok(None)
+        }
+    }
+
+    /// Removes the entry with `key` from this map and returns it.
+    ///
+    /// If there are duplicate keys, the first entry is removed.
+    pub fn remove(&mut self, key: DataRef<'_>) -> Option<(Data, Data)> {
+        let i = self.position(key)?;
+        Some((self.keys.remove(i), self.values.remove(i)))
+    }
+
+    fn position(&self, key: DataRef<'_>) -> Option<usize> {
+        self.keys.iter().position(|k| k == key)
     }
 
     /// Returns an iterator over this map.
-    pub fn iter(&self) -> MapIter {
+    pub fn iter(&self) -> MapIter<'_> {
         MapIter {
             map: self,
             index: 0,
@@ -60,18 +166,72 @@ impl Map {
     }
 
     /// Returns the kind of the keys in this map.
-    pub fn key_kind(&self) -> Option<DataKind> {
-        self.0.as_ref().map(|inner| inner.keys.kind())
+    pub fn key_kind(&self) -> DataKind {
+        self.keys.kind()
     }
 
     /// Returns the kind of the values in this map.
-    pub fn value_kind(&self) -> Option<DataKind> {
-        self.0.as_ref().map(|inner| inner.values.kind())
+    pub fn value_kind(&self) -> DataKind {
+        self.values.kind()
+    }
+
+    /// Removes the entries which have duplicate keys from this map.
+    ///
+    /// `policy` decides which entry is retained for each key.
+    /// The order of the retained entries is preserved.
+    /// The keys are compared in the total order described in [`DataRef::total_cmp`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{DedupPolicy, Map};
+    ///
+    /// let map = Map::new(vec![(1, 10), (2, 20), (1, 30)].into_iter());
+    ///
+    /// let mut first = map.clone();
+    /// first.dedup_keys(DedupPolicy::KeepFirst);
+    /// assert_eq!(first, Map::new(vec![(1, 10), (2, 20)].into_iter()));
+    ///
+    /// let mut last = map.clone();
+    /// last.dedup_keys(DedupPolicy::KeepLast);
+    /// assert_eq!(last, Map::new(vec![(2, 20), (1, 30)].into_iter()));
+    /// ```
+    pub fn dedup_keys(&mut self, policy: DedupPolicy) {
+        let keep = self.keys.dedup_flags(policy == DedupPolicy::KeepLast);
+        self.keys.retain_by_flags(&keep);
+        self.values.retain_by_flags(&keep);
+    }
+
+    /// Canonicalizes this map.
+    ///
+    /// The keys and values are canonicalized recursively, then the entries are sorted by the keys
+    /// (entries with the same key are sorted by the values).
+    /// Note that the duplicate keys are not removed (use `Map::dedup_keys` for that purpose).
+    /// See [`Data::canonicalize`] for more details.
+    pub fn canonicalize(&mut self) {
+        self.keys.canonicalize_each();
+        self.values.canonicalize_each();
+        let mut order = (0..self.len()).collect::<Vec<_>>();
+        order.sort_by(|&i, &j| {
+            self.keys
+                .cmp_elements(i, j)
+                .then_with(|| self.values.cmp_elements(i, j))
+        });
+        self.keys.reorder(&order);
+        self.values.reorder(&order);
+    }
+
+    pub(crate) fn keys_and_values_mut(&mut self) -> (&mut Elements, &mut Elements) {
+        (&mut self.keys, &mut self.values)
+    }
+
+    pub(crate) fn into_keys_and_values(self) -> (Elements, Elements) {
+        (self.keys, self.values)
     }
 
     /// Returns the number of the entries in this map.
     pub fn len(&self) -> usize {
-        self.0.as_ref().map_or(0, |inner| inner.len())
+        self.keys.len()
     }
 
     /// Returns `true` if this map has no entries.
@@ -80,23 +240,53 @@ impl Map {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
-struct Inner {
-    keys: Elements,
-    values: Elements,
+impl<K, V, S> From<HashMap<K, V, S>> for Map
+where
+    HashMap<K, V, S>: ThriftValue,
+{
+    fn from(f: HashMap<K, V, S>) -> Self {
+        Map::from_data(f.to_data()).expect("Never fails")
+    }
 }
-impl Inner {
-    pub fn get(&self, index: usize) -> Option<(DataRef, DataRef)> {
-        self.keys
-            .get(index)
-            .map(|k| (k, self.values.get(index).expect("Never fails")))
+impl<K, V> From<BTreeMap<K, V>> for Map
+where
+    BTreeMap<K, V>: ThriftValue,
+{
+    fn from(f: BTreeMap<K, V>) -> Self {
+        Map::from_data(f.to_data()).expect("Never fails")
     }
-    pub fn len(&self) -> usize {
-        self.keys.len()
+}
+impl<K, V, S> TryFrom<Map> for HashMap<K, V, S>
+where
+    HashMap<K, V, S>: ThriftValue,
+{
+    type Error = Error;
+
+    fn try_from(f: Map) -> Result<Self> {
+        track!(ThriftValue::from_data(Data::Map(f)))
+    }
+}
+impl<K, V> TryFrom<Map> for BTreeMap<K, V>
+where
+    BTreeMap<K, V>: ThriftValue,
+{
+    type Error = Error;
+
+    fn try_from(f: Map) -> Result<Self> {
+        track!(ThriftValue::from_data(Data::Map(f)))
     }
 }
 
+/// The policy to decide which entry is retained by `Map::dedup_keys`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DedupPolicy {
+    /// Retains the first entry of each key.
+    KeepFirst,
+
+    /// Retains the last entry of each key.
+    KeepLast,
+}
+
 /// An iterator which traverse the entries of a `Map`.
 #[derive(Debug)]
 pub struct MapIter<'a> {
@@ -110,3 +300,100 @@ impl<'a> Iterator for MapIter<'a> {
         self.map.get(self.index - 1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Set;
+
+    #[test]
+    fn key_based_operations_work() {
+        let mut map = Map::empty_of(DataKind::I32, DataKind::Binary);
+        assert_eq!(track_try_unwrap!(map.insert(1i32, "foo")), None);
+        assert_eq!(track_try_unwrap!(map.insert(2i32, "bar")), None);
+        assert_eq!(
+            track_try_unwrap!(map.insert(1i32, "baz")),
+            Some(Data::from("foo"))
+        );
+        assert_eq!(map.len(), 2);
+        assert_eq!(
+            map.get_by_key(DataRef::I32(&1)),
+            Some(DataRef::Binary(b"baz"))
+        );
+
+        let e = map.insert(3i64, "qux").unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::I32,
+                actual: DataKind::I64
+            }
+        );
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(
+            map.remove(DataRef::I32(&1)),
+            Some((Data::I32(1), Data::from("baz")))
+        );
+        assert!(!map.contains_key(DataRef::I32(&1)));
+        assert!(map.contains_key(DataRef::I32(&2)));
+    }
+
+    #[test]
+    fn std_map_conversions_work() {
+        let mut tags = BTreeMap::new();
+        tags.insert("foo".to_owned(), 1i64);
+        tags.insert("bar".to_owned(), 2i64);
+        let map = Map::from(tags.clone());
+        assert_eq!(map.key_kind(), DataKind::Binary);
+        assert_eq!(map.value_kind(), DataKind::I64);
+        assert_eq!(track_try_unwrap!(BTreeMap::try_from(map.clone())), tags);
+        assert!(HashMap::<String, i32>::try_from(map).is_err());
+
+        let mut nested = HashMap::new();
+        nested.insert(1i32, vec!["foo".to_owned()]);
+        nested.insert(2i32, Vec::new());
+        let map = Map::from(nested.clone());
+        assert_eq!(map.key_kind(), DataKind::I32);
+        assert_eq!(map.value_kind(), DataKind::List);
+        assert_eq!(track_try_unwrap!(HashMap::try_from(map)), nested);
+    }
+
+    #[test]
+    fn canonicalize_works() {
+        let mut map = Map::new(
+            vec![
+                (Set::from(vec![2.0, f64::NAN, -0.0, 0.0, 2.0]), 1),
+                (Set::from(vec![0.0, -0.0]), 2),
+                (Set::from(vec![f64::NAN, 0.0, 2.0, -0.0]), 3),
+            ]
+            .into_iter(),
+        );
+        map.canonicalize();
+        let keys = map
+            .iter()
+            .map(|(k, _)| {
+                k.as_set()
+                    .unwrap()
+                    .iter()
+                    .map(|d| d.as_f64().unwrap().to_bits())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let bits = |v: &[f64]| v.iter().map(|x| x.to_bits()).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                bits(&[-0.0, 0.0]),
+                bits(&[-0.0, 0.0, 2.0, f64::NAN]),
+                bits(&[-0.0, 0.0, 2.0, f64::NAN]),
+            ]
+        );
+        let values = map.iter().map(|(_, v)| v.as_i32()).collect::<Vec<_>>();
+        assert_eq!(values, [Some(2), Some(1), Some(3)]);
+
+        map.dedup_keys(DedupPolicy::KeepLast);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(1).and_then(|(_, v)| v.as_i32()), Some(3));
+    }
+}
@@ -1,22 +1,41 @@
 //! Data types.
-pub use self::data_inner::{Data, DataKind, DataRef};
+pub use self::data_inner::{Data, DataKind, DataMut, DataRef};
+pub use self::diff::{diff, diff_messages, Diff, Difference, DifferenceKind};
 pub use self::element::Elements;
 pub use self::list::List;
-pub use self::map::Map;
+pub use self::map::{DedupPolicy, Map};
+pub use self::patch::{MergeStrategy, Patch, PatchOperation};
+pub use self::path::{Path, PathSegment};
+pub use self::query::{query, Query};
+pub use self::redact::{Redaction, Redactor};
 pub use self::set::Set;
 pub use self::thrift_struct::{Field, Struct};
+pub use self::union::Union;
 pub use self::uuid::Uuid;
+pub use self::value::ThriftValue;
+pub use self::visit::{Visitor, VisitorMut};
 
 mod data_inner;
+mod diff;
 mod element;
 mod list;
 mod map;
+mod order;
+mod patch;
+mod path;
+mod query;
+mod redact;
+mod schema;
 mod set;
 mod thrift_struct;
+mod union;
 mod uuid;
+mod value;
+
+pub mod visit;
 
 pub mod iterators {
     //! Iterators.
-    pub use super::element::ElementIter;
+    pub use super::element::{ElementIntoIter, ElementIter, ElementIterMut};
     pub use super::map::MapIter;
 }
@@ -0,0 +1,230 @@
+//! The total order, equality and hashing over the data model.
+//!
+//! All of them are consistent with each other: two data are equal if and only if
+//! they are placed at the same position in the total order, and equal data have the same hash values.
+use crate::data::{Data, DataKind, DataRef, Elements, Field, List, Map, Set, Struct, Uuid};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+/// The total order used for sorting the elements of `Elements`.
+///
+/// This is the same as `Ord` except that it is also implemented for `f64`.
+pub(crate) trait TotalOrd {
+    fn total_cmp(&self, other: &Self) -> Ordering;
+}
+macro_rules! impl_total_ord_for_ord {
+    ($($ty:ty),*) => {
+        $(
+            impl TotalOrd for $ty {
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+impl_total_ord_for_ord!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    Vec<u8>,
+    String,
+    Uuid,
+    Struct,
+    Map,
+    Set,
+    List
+);
+impl TotalOrd for f64 {
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        f64::total_cmp(self, other)
+    }
+}
+
+impl<'a> DataRef<'a> {
+    /// Compares this data with `other` in the total order defined over all the data kinds.
+    ///
+    /// The order is defined as follows:
+    ///
+    /// - Data of different kinds are ordered by their `DataKind`s.
+    /// - Integers and booleans are ordered numerically (`false < true`).
+    /// - Doubles are ordered by [`f64::total_cmp`] (e.g., `-0.0 < 0.0` and `NaN`s are placed at the ends).
+    /// - Binaries (including strings) and UUIDs are ordered lexicographically by their bytes.
+    /// - Lists and sets are ordered by their element kinds, then lexicographically by their elements.
+    /// - Maps are ordered by their key and value kinds, then lexicographically by their entries.
+    /// - Structs are ordered lexicographically by their `(field id, data)` pairs.
+    ///
+    /// This is also the order used by the `Ord` implementations of the data types.
+    ///
+    /// Note that this method does not take the semantics of sets, maps and structs into account
+    /// (e.g., `{1, 2}` and `{2, 1}` are different sets). Use `Data::canonicalize` beforehand if needed.
+    pub fn total_cmp(&self, other: &DataRef<'_>) -> Ordering {
+        match (*self, *other) {
+            (DataRef::Bool(a), DataRef::Bool(b)) => a.cmp(b),
+            (DataRef::I8(a), DataRef::I8(b)) => a.cmp(b),
+            (DataRef::I16(a), DataRef::I16(b)) => a.cmp(b),
+            (DataRef::I32(a), DataRef::I32(b)) => a.cmp(b),
+            (DataRef::I64(a), DataRef::I64(b)) => a.cmp(b),
+            (DataRef::Double(a), DataRef::Double(b)) => a.total_cmp(b),
+            (DataRef::Binary(a), DataRef::Binary(b)) => a.cmp(b),
+            (DataRef::String(a), DataRef::String(b)) => a.cmp(b),
+            (DataRef::Binary(a), DataRef::String(b)) => (*a).cmp(b.as_bytes()),
+            (DataRef::String(a), DataRef::Binary(b)) => a.as_bytes().cmp(b),
+            (DataRef::Struct(a), DataRef::Struct(b)) => a.cmp(b),
+            (DataRef::Map(a), DataRef::Map(b)) => a.cmp(b),
+            (DataRef::Set(a), DataRef::Set(b)) => a.cmp(b),
+            (DataRef::List(a), DataRef::List(b)) => a.cmp(b),
+            (DataRef::Uuid(a), DataRef::Uuid(b)) => a.cmp(b),
+            (a, b) => cmp_kind(a.kind(), b.kind()),
+        }
+    }
+}
+
+macro_rules! impl_eq_and_ord {
+    ($(impl$(<$lt:lifetime>)? for $ty:ty => |$a:ident, $b:ident| $cmp:expr;)*) => {
+        $(
+            impl$(<$lt>)? PartialEq for $ty {
+                fn eq(&self, other: &Self) -> bool {
+                    self.cmp(other) == Ordering::Equal
+                }
+            }
+            impl$(<$lt>)? Eq for $ty {}
+            impl$(<$lt>)? PartialOrd for $ty {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+            impl$(<$lt>)? Ord for $ty {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    let ($a, $b) = (self, other);
+                    $cmp
+                }
+            }
+        )*
+    };
+}
+impl_eq_and_ord! {
+    impl for Data => |a, b| a.as_ref().total_cmp(&b.as_ref());
+    impl<'a> for DataRef<'a> => |a, b| a.total_cmp(b);
+    impl for Field => |a, b| {
+        a.id()
+            .cmp(&b.id())
+            .then_with(|| a.data().as_ref().total_cmp(&b.data().as_ref()))
+    };
+    impl for Struct => |a, b| a.fields().iter().cmp(b.fields().iter());
+    impl for Map => |a, b| {
+        cmp_kind(a.key_kind(), b.key_kind())
+            .then_with(|| cmp_kind(a.value_kind(), b.value_kind()))
+            .then_with(|| a.iter().cmp(b.iter()))
+    };
+    impl for Set => |a, b| (**a).cmp(&**b);
+    impl for List => |a, b| (**a).cmp(&**b);
+    impl for Elements => |a, b| cmp_kind(a.kind(), b.kind()).then_with(|| a.iter().cmp(b.iter()));
+}
+
+impl Hash for Data {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state);
+    }
+}
+impl<'a> Hash for DataRef<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.kind() as u8).hash(state);
+        match *self {
+            DataRef::Bool(v) => v.hash(state),
+            DataRef::I8(v) => v.hash(state),
+            DataRef::I16(v) => v.hash(state),
+            DataRef::I32(v) => v.hash(state),
+            DataRef::I64(v) => v.hash(state),
+            DataRef::Double(v) => v.to_bits().hash(state),
+            DataRef::Binary(v) => v.hash(state),
+            DataRef::String(v) => v.as_bytes().hash(state),
+            DataRef::Struct(v) => v.hash(state),
+            DataRef::Map(v) => v.hash(state),
+            DataRef::Set(v) => v.hash(state),
+            DataRef::List(v) => v.hash(state),
+            DataRef::Uuid(v) => v.hash(state),
+        }
+    }
+}
+impl Hash for Field {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+        self.data().hash(state);
+    }
+}
+impl Hash for Struct {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.fields().hash(state);
+    }
+}
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.key_kind() as u8).hash(state);
+        (self.value_kind() as u8).hash(state);
+        state.write_usize(self.len());
+        for entry in self.iter() {
+            entry.hash(state);
+        }
+    }
+}
+impl Hash for Set {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+impl Hash for List {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+impl Hash for Elements {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.kind() as u8).hash(state);
+        state.write_usize(self.len());
+        for e in self.iter() {
+            e.hash(state);
+        }
+    }
+}
+
+fn cmp_kind(a: DataKind, b: DataKind) -> Ordering {
+    (a as u8).cmp(&(b as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    #[test]
+    fn total_order_works() {
+        let nan = Data::Double(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert_ne!(Data::Double(0.0), Data::Double(-0.0));
+        assert!(Data::Double(-0.0) < Data::Double(0.0));
+        assert!(Data::Bool(true) < Data::I8(0));
+        assert!(
+            Data::from(Struct::new(vec![Field::new(1, 2)]))
+                < Data::from(Struct::new(vec![Field::new(2, 1)]))
+        );
+        assert!(Data::from(List::from(vec![1, 2])) < Data::from(List::from(vec![1, 2, 0])));
+        assert!(Data::from(List::from(vec![3])) < Data::from(List::from(vec![1i64])));
+
+        let data = [
+            Data::from(Set::from(vec![f64::NAN, 1.0])),
+            Data::from(Set::from(vec![f64::NAN, 1.0])),
+            Data::from(Map::new(vec![(1, 2.0)].into_iter())),
+            Data::from(Map::new(vec![(1, -2.0)].into_iter())),
+            Data::from("foo"),
+        ];
+        let ordered = data.iter().cloned().collect::<BTreeSet<_>>();
+        let hashed = data.iter().cloned().collect::<HashSet<_>>();
+        assert_eq!(ordered.len(), 4);
+        assert_eq!(hashed.len(), 4);
+        assert!(ordered.iter().all(|d| hashed.contains(d)));
+        assert_eq!(ordered.iter().next(), Some(&Data::from("foo")));
+    }
+}
@@ -0,0 +1,604 @@
+use crate::data::path::{lookup, lookup_mut, remove};
+use crate::data::{
+    diff, Data, DataMut, DataRef, DifferenceKind, Elements, Field, List, Path, PathSegment, Struct,
+};
+use crate::{ErrorKind, Result};
+
+/// The strategy to merge collections (i.e., lists, sets and maps) by `Struct::merge`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum MergeStrategy {
+    /// Replaces the collection with the other one.
+    Replace,
+
+    /// Appends the elements (or entries) of the other collection.
+    ///
+    /// The elements already contained in a set are not appended, and
+    /// the values of the existing keys in a map are overwritten.
+    /// If the element kinds of the collections are different, the collection is replaced.
+    Append,
+}
+
+impl Struct {
+    /// Merges `other` into this struct.
+    ///
+    /// The fields are matched by their identifiers:
+    ///
+    /// - The fields only in `other` are appended to this struct.
+    /// - If the both fields are structs, they are merged recursively.
+    /// - If the both fields are collections, they are merged by `strategy`.
+    /// - Otherwise, the field of this struct is replaced with the one of `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Field, List, MergeStrategy, Struct};
+    ///
+    /// let defaults = Struct::new(vec![
+    ///     Field::new(1, "unknown"),
+    ///     Field::new(2, Struct::new(vec![Field::new(1, 80), Field::new(2, false)])),
+    ///     Field::new(3, List::from(vec![1])),
+    /// ]);
+    /// let partial = Struct::new(vec![
+    ///     Field::new(2, Struct::new(vec![Field::new(2, true)])),
+    ///     Field::new(3, List::from(vec![2])),
+    /// ]);
+    ///
+    /// let mut replaced = defaults.clone();
+    /// replaced.merge(&partial, MergeStrategy::Replace);
+    /// assert_eq!(
+    ///     replaced,
+    ///     Struct::new(vec![
+    ///         Field::new(1, "unknown"),
+    ///         Field::new(2, Struct::new(vec![Field::new(1, 80), Field::new(2, true)])),
+    ///         Field::new(3, List::from(vec![2])),
+    ///     ])
+    /// );
+    ///
+    /// let mut appended = defaults.clone();
+    /// appended.merge(&partial, MergeStrategy::Append);
+    /// assert_eq!(appended.get_list(3).unwrap(), Some(&List::from(vec![1, 2])));
+    /// ```
+    pub fn merge(&mut self, other: &Struct, strategy: MergeStrategy) {
+        for field in other.fields() {
+            if let Some(data) = self.get_mut(field.id()) {
+                merge_data(data, field.data(), strategy);
+            } else {
+                self.insert(field.clone());
+            }
+        }
+    }
+
+    /// Applies `patch` to this struct.
+    ///
+    /// The operations in the patch are applied in order.
+    /// If any of them fails, this struct is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the patch can not be applied to this struct (e.g., a path does not exist),
+    /// this method will return an error which kind is `ErrorKind::InvalidInput` or `ErrorKind::KindMismatch`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Field, List, Patch, Struct};
+    ///
+    /// let old = Struct::new(vec![Field::new(1, "foo"), Field::new(2, List::from(vec![1, 2, 3]))]);
+    /// let new = Struct::new(vec![Field::new(2, List::from(vec![1, 5])), Field::new(3, true)]);
+    ///
+    /// let patch = Patch::new(&old, &new);
+    /// let mut s = old.clone();
+    /// s.apply_patch(&patch).unwrap();
+    /// assert_eq!(s, Struct::new(vec![Field::new(2, List::from(vec![1, 5])), Field::new(3, true)]));
+    /// ```
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<()> {
+        let mut s = self.clone();
+        for op in &patch.operations {
+            track!(op.apply(&mut s), "operation={:?}", op)?;
+        }
+        *self = s;
+        Ok(())
+    }
+}
+
+fn merge_data(data: &mut Data, other: &Data, strategy: MergeStrategy) {
+    match (data.as_mut(), other) {
+        (DataMut::Struct(s), Data::Struct(o)) => s.merge(o, strategy),
+        (DataMut::List(l), Data::List(o))
+            if strategy == MergeStrategy::Append && l.kind() == o.kind() =>
+        {
+            l.extend(o.iter().map(|e| e.to_owned()))
+                .expect("Never fails");
+        }
+        (DataMut::Set(s), Data::Set(o))
+            if strategy == MergeStrategy::Append && s.kind() == o.kind() =>
+        {
+            for e in o.iter() {
+                if !s.iter().any(|x| x == e) {
+                    s.push(e.to_owned()).expect("Never fails");
+                }
+            }
+        }
+        (DataMut::Map(m), Data::Map(o))
+            if strategy == MergeStrategy::Append
+                && m.key_kind() == o.key_kind()
+                && m.value_kind() == o.value_kind() =>
+        {
+            for (k, v) in o.iter() {
+                m.insert(k.to_owned(), v.to_owned()).expect("Never fails");
+            }
+        }
+        _ => *data = other.clone(),
+    }
+}
+
+/// A sparse update of a struct.
+///
+/// A patch is typically made from the difference between two structs by `Patch::new`,
+/// and applied to a struct by `Struct::apply_patch`.
+///
+/// A patch can be converted to (and from) a `Struct`, so it can be serialized by the thrift encodings.
+/// The layout of the struct is as follows:
+///
+/// ```thrift
+/// struct Patch {
+///   1: list<Operation> operations
+/// }
+///
+/// struct Operation {
+///   1: i8 kind  // 1: SET, 2: REMOVE, 3: TRUNCATE, 4: INSERT_ELEMENT, 5: REMOVE_ELEMENT
+///   2: list<PathSegment> path
+///   3: optional any value  // SET, INSERT_ELEMENT and REMOVE_ELEMENT
+///   4: optional i64 len  // TRUNCATE
+/// }
+///
+/// union PathSegment {
+///   1: i16 field
+///   2: i64 index
+///   3: any key
+///   4: string name  // "method_name", "kind", "sequence_id" or "body"
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::{BinaryDecode, BinaryEncode};
+/// use thrift_codec::data::{Field, Patch, Struct};
+///
+/// let old = Struct::new(vec![Field::new(1, "foo")]);
+/// let new = Struct::new(vec![Field::new(1, "bar")]);
+/// let patch = Patch::new(&old, &new);
+///
+/// let mut buf = Vec::new();
+/// Struct::from(&patch).binary_encode(&mut buf).unwrap();
+/// let decoded = Patch::try_from(Struct::binary_decode(&mut &buf[..]).unwrap()).unwrap();
+/// assert_eq!(decoded, patch);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Patch {
+    /// The operations of this patch.
+    pub operations: Vec<PatchOperation>,
+}
+impl Patch {
+    /// Makes a new `Patch` instance which updates `old` to `new`.
+    pub fn new(old: &Struct, new: &Struct) -> Self {
+        let old_data = Data::Struct(old.clone());
+        let new_data = Data::Struct(new.clone());
+        let diff = diff(&old_data, &new_data);
+        let mut operations = Vec::new();
+        for d in diff.differences {
+            let path = d.path;
+            let op = match d.kind {
+                DifferenceKind::Added(value) if is_set(&path, &old_data, &new_data) => {
+                    PatchOperation::InsertElement { path, value }
+                }
+                DifferenceKind::Removed(value) if is_set(&path, &old_data, &new_data) => {
+                    PatchOperation::RemoveElement { path, value }
+                }
+                DifferenceKind::Added(value)
+                | DifferenceKind::KindChanged { right: value, .. }
+                | DifferenceKind::ValueChanged { right: value, .. } => {
+                    PatchOperation::Set { path, value }
+                }
+                DifferenceKind::Removed(_) if is_list_element(&path) => continue,
+                DifferenceKind::Removed(_) => PatchOperation::Remove { path },
+                DifferenceKind::LengthChanged { right, .. } => {
+                    PatchOperation::Truncate { path, len: right }
+                }
+                DifferenceKind::ElementKindChanged { .. }
+                | DifferenceKind::KeyKindChanged { .. }
+                | DifferenceKind::ValueKindChanged { .. } => {
+                    let value = lookup(new_data.as_ref(), path.segments())
+                        .expect("Never fails")
+                        .to_owned();
+                    PatchOperation::Set { path, value }
+                }
+            };
+            operations.push(op);
+        }
+        Patch { operations }
+    }
+
+    /// Returns `true` if this patch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+impl From<&Patch> for Struct {
+    fn from(f: &Patch) -> Self {
+        let operations = f.operations.iter().map(Struct::from).collect::<Vec<_>>();
+        Struct::new(vec![Field::new(1, List::from(operations))])
+    }
+}
+impl TryFrom<Struct> for Patch {
+    type Error = crate::Error;
+
+    fn try_from(f: Struct) -> Result<Self> {
+        let mut operations = Vec::new();
+        if let Some(list) = track!(f.get_list(1))? {
+            for op in list.iter() {
+                let op = track_assert_some!(op.as_struct(), ErrorKind::InvalidInput);
+                operations.push(track!(PatchOperation::try_from(op))?);
+            }
+        }
+        Ok(Patch { operations })
+    }
+}
+
+/// An operation of a `Patch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PatchOperation {
+    /// Sets `value` to the field, list element or map entry specified by `path`.
+    ///
+    /// If the field (or entry) does not exist, it is inserted.
+    /// If the index of a list element equals to the length of the list, the value is appended.
+    Set {
+        /// The location to be set.
+        path: Path,
+
+        /// The value to be set.
+        value: Data,
+    },
+
+    /// Removes the field, list element or map entry specified by `path`.
+    Remove {
+        /// The location to be removed.
+        path: Path,
+    },
+
+    /// Shortens the list specified by `path` to `len` elements.
+    ///
+    /// If the list is not longer than `len`, this has no effect.
+    Truncate {
+        /// The location of the list.
+        path: Path,
+
+        /// The length of the list.
+        len: usize,
+    },
+
+    /// Inserts `value` into the set specified by `path` (if it is not contained yet).
+    InsertElement {
+        /// The location of the set.
+        path: Path,
+
+        /// The element to be inserted.
+        value: Data,
+    },
+
+    /// Removes `value` from the set specified by `path`.
+    RemoveElement {
+        /// The location of the set.
+        path: Path,
+
+        /// The element to be removed.
+        value: Data,
+    },
+}
+impl PatchOperation {
+    /// Returns the path of this operation.
+    pub fn path(&self) -> &Path {
+        match *self {
+            PatchOperation::Set { ref path, .. }
+            | PatchOperation::Remove { ref path }
+            | PatchOperation::Truncate { ref path, .. }
+            | PatchOperation::InsertElement { ref path, .. }
+            | PatchOperation::RemoveElement { ref path, .. } => path,
+        }
+    }
+
+    fn apply(&self, root: &mut Struct) -> Result<()> {
+        let segments = self.path().segments();
+        match *self {
+            PatchOperation::Set { ref value, .. } => {
+                let (last, parent) =
+                    track_assert_some!(segments.split_last(), ErrorKind::InvalidInput);
+                let parent = track!(lookup_mut(DataMut::Struct(root), parent))?;
+                track!(set(parent, last, value.clone()))?;
+            }
+            PatchOperation::Remove { .. } => {
+                let (last, parent) =
+                    track_assert_some!(segments.split_last(), ErrorKind::InvalidInput);
+                let parent = track!(lookup_mut(DataMut::Struct(root), parent))?;
+                track!(remove(parent, last))?;
+            }
+            PatchOperation::Truncate { len, .. } => {
+                match track!(lookup_mut(DataMut::Struct(root), segments))? {
+                    DataMut::List(list) => list.truncate(len),
+                    d => track_panic!(ErrorKind::InvalidInput, "Not a list: {:?}", d.kind()),
+                }
+            }
+            PatchOperation::InsertElement { ref value, .. } => {
+                match track!(lookup_mut(DataMut::Struct(root), segments))? {
+                    DataMut::Set(set) => {
+                        if !set.iter().any(|e| e == value.as_ref()) {
+                            track!(set.push(value.clone()))?;
+                        }
+                    }
+                    d => track_panic!(ErrorKind::InvalidInput, "Not a set: {:?}", d.kind()),
+                }
+            }
+            PatchOperation::RemoveElement { ref value, .. } => {
+                match track!(lookup_mut(DataMut::Struct(root), segments))? {
+                    DataMut::Set(set) => {
+                        let i = track_assert_some!(
+                            set.iter().position(|e| e == value.as_ref()),
+                            ErrorKind::InvalidInput,
+                            "No such element"
+                        );
+                        set.remove(i);
+                    }
+                    d => track_panic!(ErrorKind::InvalidInput, "Not a set: {:?}", d.kind()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn kind_code(&self) -> i8 {
+        match *self {
+            PatchOperation::Set { .. } => 1,
+            PatchOperation::Remove { .. } => 2,
+            PatchOperation::Truncate { .. } => 3,
+            PatchOperation::InsertElement { .. } => 4,
+            PatchOperation::RemoveElement { .. } => 5,
+        }
+    }
+}
+impl From<&PatchOperation> for Struct {
+    fn from(f: &PatchOperation) -> Self {
+        let path = f
+            .path()
+            .segments()
+            .iter()
+            .map(|s| match *s {
+                PathSegment::Field(id) => Struct::new(vec![Field::new(1, id)]),
+                PathSegment::Index(i) => Struct::new(vec![Field::new(2, i as i64)]),
+                PathSegment::Key(ref k) => Struct::new(vec![Field::new(3, k.clone())]),
+                PathSegment::Name(name) => Struct::new(vec![Field::new(4, name)]),
+            })
+            .collect::<Vec<_>>();
+        let mut fields = vec![
+            Field::new(1, f.kind_code()),
+            Field::new(2, List::from(path)),
+        ];
+        match *f {
+            PatchOperation::Set { ref value, .. }
+            | PatchOperation::InsertElement { ref value, .. }
+            | PatchOperation::RemoveElement { ref value, .. } => {
+                fields.push(Field::new(3, value.clone()));
+            }
+            PatchOperation::Truncate { len, .. } => fields.push(Field::new(4, len as i64)),
+            PatchOperation::Remove { .. } => {}
+        }
+        Struct::new(fields)
+    }
+}
+impl TryFrom<&Struct> for PatchOperation {
+    type Error = crate::Error;
+
+    fn try_from(f: &Struct) -> Result<Self> {
+        let kind = track_assert_some!(track!(f.get_i8(1))?, ErrorKind::InvalidInput);
+        let mut segments = Vec::new();
+        if let Some(list) = track!(f.get_list(2))? {
+            for s in list.iter() {
+                let s = track_assert_some!(s.as_struct(), ErrorKind::InvalidInput);
+                let segment = if let Some(id) = track!(s.get_i16(1))? {
+                    PathSegment::Field(id)
+                } else if let Some(i) = track!(s.get_i64(2))? {
+                    track_assert!(i >= 0, ErrorKind::InvalidInput);
+                    PathSegment::Index(i as usize)
+                } else if let Some(k) = s.get(3) {
+                    PathSegment::Key(k.clone())
+                } else if let Some(name) = track!(s.get_str(4))? {
+                    track_assert_some!(
+                        PathSegment::from_name(name),
+                        ErrorKind::InvalidInput,
+                        "Unknown path segment name: {:?}",
+                        name
+                    )
+                } else {
+                    track_panic!(ErrorKind::InvalidInput, "Unsupported path segment: {:?}", s);
+                };
+                segments.push(segment);
+            }
+        }
+        let path = Path::new(segments);
+        let value = f.get(3).cloned();
+        Ok(match kind {
+            1 => PatchOperation::Set {
+                path,
+                value: track_assert_some!(value, ErrorKind::InvalidInput),
+            },
+            2 => PatchOperation::Remove { path },
+            3 => {
+                let len = track_assert_some!(track!(f.get_i64(4))?, ErrorKind::InvalidInput);
+                track_assert!(len >= 0, ErrorKind::InvalidInput);
+                PatchOperation::Truncate {
+                    path,
+                    len: len as usize,
+                }
+            }
+            4 => PatchOperation::InsertElement {
+                path,
+                value: track_assert_some!(value, ErrorKind::InvalidInput),
+            },
+            5 => PatchOperation::RemoveElement {
+                path,
+                value: track_assert_some!(value, ErrorKind::InvalidInput),
+            },
+            _ => track_panic!(ErrorKind::InvalidInput, "Unknown operation: {}", kind),
+        })
+    }
+}
+
+fn is_list_element(path: &Path) -> bool {
+    matches!(path.segments().last(), Some(PathSegment::Index(_)))
+}
+
+fn is_set(path: &Path, old: &Data, new: &Data) -> bool {
+    let is_set = |d: &Data| matches!(lookup(d.as_ref(), path.segments()), Some(DataRef::Set(_)));
+    is_set(old) && is_set(new)
+}
+
+fn set(parent: DataMut<'_>, segment: &PathSegment, value: Data) -> Result<()> {
+    match (parent, segment) {
+        (DataMut::Struct(s), PathSegment::Field(id)) => {
+            s.insert(Field::new(*id, value));
+        }
+        (DataMut::List(l), PathSegment::Index(i)) => {
+            track!(set_element(l, *i, value))?;
+        }
+        (DataMut::Map(m), PathSegment::Key(k)) => {
+            track!(m.insert(k.clone(), value))?;
+        }
+        (d, s) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Path segment {:?} is not applicable to {:?}",
+            s,
+            d.kind()
+        ),
+    }
+    Ok(())
+}
+
+fn set_element(elements: &mut Elements, index: usize, value: Data) -> Result<()> {
+    if index < elements.len() {
+        track!(elements.replace(index, value))?;
+    } else {
+        track_assert_eq!(
+            index,
+            elements.len(),
+            ErrorKind::InvalidInput,
+            "Index out of bounds"
+        );
+        track!(elements.push(value))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{diff_messages, DataKind, Map, Set};
+    use crate::message::Message;
+
+    #[test]
+    fn patch_works() {
+        let mut tags = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(tags.insert("a", 1));
+        track_try_unwrap!(tags.insert("b", 2));
+        let old = Struct::new(vec![
+            Field::new(1, tags.clone()),
+            Field::new(2, Set::from(vec![1, 2, 3])),
+            Field::new(3, List::from(vec![Struct::from((1,)), Struct::from((2,))])),
+            Field::new(4, List::from(vec![1, 2])),
+            Field::new(5, 1i64),
+        ]);
+
+        let mut tags2 = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(tags2.insert("b", 3));
+        track_try_unwrap!(tags2.insert("c", 4));
+        let new = Struct::new(vec![
+            Field::new(1, tags2),
+            Field::new(2, Set::from(vec![3, 4, 1])),
+            Field::new(3, List::from(vec![Struct::from((1, true))])),
+            Field::new(4, List::from(vec![1i64])),
+            Field::new(5, "foo"),
+        ]);
+
+        let patch = Patch::new(&old, &new);
+        let mut s = old.clone();
+        track_try_unwrap!(s.apply_patch(&patch));
+        assert!(diff(&Data::from(s), &Data::from(new.clone())).is_empty());
+
+        let decoded = track_try_unwrap!(Patch::try_from(Struct::from(&patch)));
+        assert_eq!(decoded, patch);
+
+        // A failed patch does not modify the struct.
+        let mut s = new.clone();
+        assert!(s.apply_patch(&patch).is_err());
+        assert_eq!(s, new);
+    }
+
+    #[test]
+    fn patch_with_named_segments_round_trips() {
+        let left = Message::call("foo", 1, Struct::from((1i32,)));
+        let right = Message::call("foo", 1, Struct::from((2i32,)));
+        let operations = diff_messages(&left, &right)
+            .differences
+            .into_iter()
+            .map(|d| PatchOperation::Set {
+                path: d.path,
+                value: Data::I32(2),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(operations[0].path().to_string(), "body.1");
+
+        let patch = Patch { operations };
+        let decoded = track_try_unwrap!(Patch::try_from(Struct::from(&patch)));
+        assert_eq!(decoded, patch);
+
+        let unknown = Patch {
+            operations: vec![PatchOperation::Remove {
+                path: Path::new(vec![PathSegment::Name("unknown")]),
+            }],
+        };
+        let e = Patch::try_from(Struct::from(&unknown)).unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn merge_works() {
+        let mut tags = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(tags.insert("a", 1));
+        let mut s = Struct::new(vec![
+            Field::new(1, tags),
+            Field::new(2, Set::from(vec![1, 2])),
+            Field::new(3, List::from(vec![1])),
+        ]);
+
+        let mut tags = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(tags.insert("a", 10));
+        track_try_unwrap!(tags.insert("b", 2));
+        let other = Struct::new(vec![
+            Field::new(1, tags.clone()),
+            Field::new(2, Set::from(vec![2, 3])),
+            Field::new(3, List::from(vec![1i64])),
+        ]);
+
+        s.merge(&other, MergeStrategy::Append);
+        assert_eq!(
+            s,
+            Struct::new(vec![
+                Field::new(1, tags),
+                Field::new(2, Set::from(vec![1, 2, 3])),
+                Field::new(3, List::from(vec![1i64])),
+            ])
+        );
+    }
+}
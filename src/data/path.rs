@@ -0,0 +1,191 @@
+use crate::data::{Data, DataMut, DataRef};
+use crate::{ErrorKind, Result};
+use std::fmt;
+
+/// The location of a datum in a data tree.
+///
+/// The `Display` representation consists of the segments
+/// (e.g., `1.2[3]{"foo"}` denotes the value associated with the key `"foo"` of
+/// the map placed at the index `3` of the list in the field `2` of the struct in the field `1`).
+/// The root path is rendered as `(root)`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+impl Path {
+    /// Makes a new `Path` instance.
+    pub fn new(segments: Vec<PathSegment>) -> Self {
+        Path { segments }
+    }
+
+    /// Returns the root path (i.e., the path which has no segments).
+    pub fn root() -> Self {
+        Path::default()
+    }
+
+    /// Returns a new path made by appending `segment` to this path.
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut segments = Vec::with_capacity(self.segments.len() + 1);
+        segments.extend_from_slice(&self.segments);
+        segments.push(segment);
+        Path { segments }
+    }
+
+    /// Returns the segments of this path.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns `true` if this is the root path.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "(root)");
+        }
+        for (i, s) in self.segments.iter().enumerate() {
+            match *s {
+                PathSegment::Field(id) if i == 0 => write!(f, "{}", id)?,
+                PathSegment::Field(id) => write!(f, ".{}", id)?,
+                PathSegment::Name(name) if i == 0 => write!(f, "{}", name)?,
+                PathSegment::Name(name) => write!(f, ".{}", name)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+                PathSegment::Key(ref key) => write!(f, "{{{}}}", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A segment of a `Path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PathSegment {
+    /// The field which has the identifier.
+    Field(i16),
+
+    /// The element placed at the index of a list.
+    Index(usize),
+
+    /// The value associated with the key of a map.
+    Key(Data),
+
+    /// The named component of a non-data object (e.g., the `method_name` of a message).
+    ///
+    /// The names used by this crate are `method_name`, `kind`, `sequence_id` and `body`.
+    Name(&'static str),
+}
+impl PathSegment {
+    /// The names of the message components used by `diff_messages`.
+    pub(crate) const NAMES: &'static [&'static str] =
+        &["method_name", "kind", "sequence_id", "body"];
+
+    /// Returns the `PathSegment::Name` segment which name is `name` if it is one of `NAMES`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|n| **n == name)
+            .map(|n| PathSegment::Name(n))
+    }
+}
+
+pub(crate) fn lookup<'a>(data: DataRef<'a>, segments: &[PathSegment]) -> Option<DataRef<'a>> {
+    let Some((first, rest)) = segments.split_first() else {
+        return Some(data);
+    };
+    let child = match (data, first) {
+        (DataRef::Struct(s), PathSegment::Field(id)) => s.get(*id)?.as_ref(),
+        (DataRef::List(l), PathSegment::Index(i)) => l.get(*i)?,
+        (DataRef::Set(l), PathSegment::Index(i)) => l.get(*i)?,
+        (DataRef::Map(m), PathSegment::Key(k)) => m.get_by_key(k.as_ref())?,
+        _ => return None,
+    };
+    lookup(child, rest)
+}
+
+pub(crate) fn lookup_mut<'a>(data: DataMut<'a>, segments: &[PathSegment]) -> Result<DataMut<'a>> {
+    let Some((first, rest)) = segments.split_first() else {
+        return Ok(data);
+    };
+    let child = match (data, first) {
+        (DataMut::Struct(s), PathSegment::Field(id)) => {
+            let data = track_assert_some!(
+                s.get_mut(*id),
+                ErrorKind::InvalidInput,
+                "No such field: {}",
+                id
+            );
+            data.as_mut()
+        }
+        (DataMut::List(l), PathSegment::Index(i)) => track_assert_some!(
+            l.iter_mut().nth(*i),
+            ErrorKind::InvalidInput,
+            "Index out of bounds: {}",
+            i
+        ),
+        (DataMut::Set(l), PathSegment::Index(i)) => track_assert_some!(
+            l.iter_mut().nth(*i),
+            ErrorKind::InvalidInput,
+            "Index out of bounds: {}",
+            i
+        ),
+        (DataMut::Map(m), PathSegment::Key(k)) => {
+            let (keys, values) = m.keys_and_values_mut();
+            let i = track_assert_some!(
+                keys.iter().position(|x| x == k.as_ref()),
+                ErrorKind::InvalidInput,
+                "No such key: {:?}",
+                k
+            );
+            values.iter_mut().nth(i).expect("Never fails")
+        }
+        (d, s) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Path segment {:?} is not applicable to {:?}",
+            s,
+            d.kind()
+        ),
+    };
+    track!(lookup_mut(child, rest))
+}
+
+/// Removes the field, list (or set) element or map entry specified by `segment` from `parent`.
+pub(crate) fn remove(parent: DataMut<'_>, segment: &PathSegment) -> Result<()> {
+    match (parent, segment) {
+        (DataMut::Struct(s), PathSegment::Field(id)) => {
+            track_assert_some!(
+                s.remove(*id),
+                ErrorKind::InvalidInput,
+                "No such field: {}",
+                id
+            );
+        }
+        (DataMut::List(l), PathSegment::Index(i)) => {
+            track_assert!(*i < l.len(), ErrorKind::InvalidInput, "Index out of bounds");
+            l.remove(*i);
+        }
+        (DataMut::Set(l), PathSegment::Index(i)) => {
+            track_assert!(*i < l.len(), ErrorKind::InvalidInput, "Index out of bounds");
+            l.remove(*i);
+        }
+        (DataMut::Map(m), PathSegment::Key(k)) => {
+            track_assert_some!(
+                m.remove(k.as_ref()),
+                ErrorKind::InvalidInput,
+                "No such key: {:?}",
+                k
+            );
+        }
+        (d, s) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Path segment {:?} is not applicable to {:?}",
+            s,
+            d.kind()
+        ),
+    }
+    Ok(())
+}
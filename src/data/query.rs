@@ -0,0 +1,675 @@
+use crate::data::{DataRef, Path, PathSegment, Struct};
+use crate::idl::{Definition, Document, FieldType};
+use crate::{ErrorKind, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Selects the data specified by `path` from `root`.
+///
+/// This is equivalent to `path.parse::<Query>()?.select(root)`.
+/// See [`Query`] for the syntax of paths.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{self, DataRef, Field, List, Struct};
+///
+/// let span = Struct::new(vec![
+///     Field::new(1, "GET /"),
+///     Field::new(
+///         2,
+///         List::from(vec![
+///             Struct::new(vec![Field::new(1, "http.method"), Field::new(2, "GET")]),
+///             Struct::new(vec![Field::new(1, "http.status_code"), Field::new(2, "200")]),
+///         ]),
+///     ),
+/// ]);
+///
+/// let values = data::query(&span, r#"2{1="http.method"}.2"#).unwrap();
+/// assert_eq!(values, [DataRef::Binary(b"GET")]);
+///
+/// let keys = data::query(&span, "2[*].1").unwrap();
+/// assert_eq!(keys.len(), 2);
+/// ```
+pub fn query<'a>(root: &'a Struct, path: &str) -> Result<Vec<DataRef<'a>>> {
+    let query: Query = track!(path.parse())?;
+    track!(query.select(root))
+}
+
+/// A query which selects data from a struct.
+///
+/// # Syntax
+///
+/// A query consists of the following steps (the first one must be a field step):
+///
+/// | Step | Description |
+/// |------|-------------|
+/// | `1`, `.1` | The field which has the identifier `1` |
+/// | `name`, `.name` | The field named `name` (requires [`Query::resolve`]) |
+/// | `[0]` | The element placed at the index `0` of a list |
+/// | `[*]` | All the elements of a list or a set |
+/// | `{"foo"}` | The value associated with the key `"foo"` of a map |
+/// | `{*}` | All the values of a map |
+/// | `{1="foo"}`, `{name="foo"}` | The struct elements of a list or a set which field `1` (or `name`) is `"foo"` |
+///
+/// Literals are strings (`"foo"`, matching binaries), integers (`10`, matching integers of any width),
+/// doubles (`1.5`) and booleans (`true`, `false`).
+/// Strings may contain the escapes `\n`, `\r`, `\t`, `\0` and `\u{1b}`,
+/// and any other character preceded by `\` stands for itself (e.g., `\"`).
+///
+/// Each step is applied to all the data selected by the previous steps,
+/// and the data to which a step is not applicable are just skipped.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{DataRef, Field, List, Query, Struct};
+/// use thrift_codec::idl::Document;
+///
+/// let doc = Document::parse(r#"
+///     struct Tag { 1: string key, 2: string value }
+///     struct Span { 1: string name, 2: list<Tag> tags }
+/// "#).unwrap();
+///
+/// let span = Struct::new(vec![
+///     Field::new(1, "GET /"),
+///     Field::new(2, List::from(vec![Struct::new(vec![Field::new(1, "http.method"), Field::new(2, "GET")])])),
+/// ]);
+///
+/// let query: Query = r#"tags{key="http.method"}.value"#.parse().unwrap();
+/// assert!(query.select(&span).is_err()); // Names are not resolved yet.
+///
+/// let query = query.resolve(&doc, "Span").unwrap();
+/// assert_eq!(query.to_string(), r#"2{1="http.method"}.2"#);
+/// assert_eq!(query.select(&span).unwrap(), [DataRef::Binary(b"GET")]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+impl Query {
+    /// Parses the given query string.
+    ///
+    /// # Errors
+    ///
+    /// If `text` is malformed, this function will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        track!(parser.parse(), "query={:?}", text)
+    }
+
+    /// Resolves the field names in this query by using the type definitions in `document`.
+    ///
+    /// `root` is the name of the struct type to which this query is applied.
+    ///
+    /// # Errors
+    ///
+    /// If a name can not be resolved, this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn resolve(&self, document: &Document, root: &str) -> Result<Self> {
+        let root = FieldType::Named(root.to_owned());
+        let mut current = Some(&root);
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let step = match *step {
+                Step::Field(ref field) => {
+                    let (id, t) = track!(resolve_field(document, current, field))?;
+                    current = t;
+                    Step::Field(FieldRef::Id(id))
+                }
+                Step::Index(i) => {
+                    current = element_type(document, current);
+                    Step::Index(i)
+                }
+                Step::Filter(ref field, ref value) => {
+                    current = element_type(document, current);
+                    let (id, _) = track!(resolve_field(document, current, field))?;
+                    Step::Filter(FieldRef::Id(id), value.clone())
+                }
+                Step::Key(ref key) => {
+                    current = map_value_type(document, current);
+                    Step::Key(key.clone())
+                }
+                Step::AllValues => {
+                    current = map_value_type(document, current);
+                    Step::AllValues
+                }
+            };
+            steps.push(step);
+        }
+        Ok(Query { steps })
+    }
+
+    /// Selects the data matched with this query from `root`.
+    ///
+    /// # Errors
+    ///
+    /// If this query contains unresolved field names,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn select<'a>(&self, root: &'a Struct) -> Result<Vec<DataRef<'a>>> {
+        let selected = track!(self.select_with_paths(root))?;
+        Ok(selected.into_iter().map(|(_, data)| data).collect())
+    }
+
+    /// Selects the data matched with this query from `root` together with their paths.
+    ///
+    /// The selected data are in the order of their appearance in `root`.
+    ///
+    /// # Errors
+    ///
+    /// If this query contains unresolved field names,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn select_with_paths<'a>(&self, root: &'a Struct) -> Result<Vec<(Path, DataRef<'a>)>> {
+        let mut current = vec![(Path::root(), DataRef::Struct(root))];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for (path, data) in current {
+                track!(step.apply(&path, data, &mut next))?;
+            }
+            current = next;
+        }
+        Ok(current)
+    }
+}
+impl FromStr for Query {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        track!(Query::parse(s))
+    }
+}
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            match *step {
+                Step::Field(ref field) if i == 0 => write!(f, "{}", field)?,
+                Step::Field(ref field) => write!(f, ".{}", field)?,
+                Step::Index(Some(i)) => write!(f, "[{}]", i)?,
+                Step::Index(None) => write!(f, "[*]")?,
+                Step::Key(ref key) => write!(f, "{{{}}}", key)?,
+                Step::AllValues => write!(f, "{{*}}")?,
+                Step::Filter(ref field, ref value) => write!(f, "{{{}={}}}", field, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(FieldRef),
+    Index(Option<usize>),
+    Key(Literal),
+    AllValues,
+    Filter(FieldRef, Literal),
+}
+impl Step {
+    fn apply<'a>(
+        &self,
+        path: &Path,
+        data: DataRef<'a>,
+        next: &mut Vec<(Path, DataRef<'a>)>,
+    ) -> Result<()> {
+        let index = |i| path.join(PathSegment::Index(i));
+        match (self, data) {
+            (Step::Field(field), DataRef::Struct(s)) => {
+                let id = track!(field.id())?;
+                next.extend(
+                    s.fields()
+                        .iter()
+                        .filter(|f| f.id() == id)
+                        .map(|f| (path.join(PathSegment::Field(id)), f.data().as_ref())),
+                );
+            }
+            (Step::Index(Some(i)), DataRef::List(l)) => {
+                next.extend(l.get(*i).map(|e| (index(*i), e)));
+            }
+            (Step::Index(None), DataRef::List(l)) => {
+                next.extend(l.iter().enumerate().map(|(i, e)| (index(i), e)));
+            }
+            (Step::Index(None), DataRef::Set(s)) => {
+                next.extend(s.iter().enumerate().map(|(i, e)| (index(i), e)));
+            }
+            (Step::Key(key), DataRef::Map(m)) => {
+                next.extend(
+                    m.iter()
+                        .filter(|(k, _)| key.matches(*k))
+                        .map(|(k, v)| (path.join(PathSegment::Key(k.to_owned())), v)),
+                );
+            }
+            (Step::AllValues, DataRef::Map(m)) => {
+                next.extend(
+                    m.iter()
+                        .map(|(k, v)| (path.join(PathSegment::Key(k.to_owned())), v)),
+                );
+            }
+            (Step::Filter(field, value), DataRef::List(l)) => {
+                let elements = l.iter().enumerate().map(|(i, e)| (index(i), e));
+                track!(filter(field, value, elements, next))?;
+            }
+            (Step::Filter(field, value), DataRef::Set(s)) => {
+                let elements = s.iter().enumerate().map(|(i, e)| (index(i), e));
+                track!(filter(field, value, elements, next))?;
+            }
+            (Step::Filter(field, value), DataRef::Struct(_)) => {
+                let elements = std::iter::once((path.clone(), data));
+                track!(filter(field, value, elements, next))?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn filter<'a, I>(
+    field: &FieldRef,
+    value: &Literal,
+    elements: I,
+    next: &mut Vec<(Path, DataRef<'a>)>,
+) -> Result<()>
+where
+    I: Iterator<Item = (Path, DataRef<'a>)>,
+{
+    let id = track!(field.id())?;
+    next.extend(elements.filter(|(_, e)| {
+        e.as_struct()
+            .and_then(|s| s.get(id))
+            .is_some_and(|d| value.matches(d.as_ref()))
+    }));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldRef {
+    Id(i16),
+    Name(String),
+}
+impl FieldRef {
+    fn id(&self) -> Result<i16> {
+        match *self {
+            FieldRef::Id(id) => Ok(id),
+            FieldRef::Name(ref name) => track_panic!(
+                ErrorKind::InvalidInput,
+                "Unresolved field name: {:?} (use `Query::resolve`)",
+                name
+            ),
+        }
+    }
+}
+impl fmt::Display for FieldRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FieldRef::Id(id) => write!(f, "{}", id),
+            FieldRef::Name(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    String(String),
+}
+impl Literal {
+    fn matches(&self, data: DataRef<'_>) -> bool {
+        match (self, data) {
+            (Literal::Bool(a), DataRef::Bool(b)) => a == b,
+            (Literal::Int(a), DataRef::I8(b)) => *a == i64::from(*b),
+            (Literal::Int(a), DataRef::I16(b)) => *a == i64::from(*b),
+            (Literal::Int(a), DataRef::I32(b)) => *a == i64::from(*b),
+            (Literal::Int(a), DataRef::I64(b)) => a == b,
+            (Literal::Double(a), DataRef::Double(b)) => a == b,
+            (Literal::String(a), DataRef::Binary(b)) => a.as_bytes() == b,
+            (Literal::String(a), DataRef::String(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Literal::Bool(v) => write!(f, "{}", v),
+            Literal::Int(v) => write!(f, "{}", v),
+            Literal::Double(v) => write!(f, "{:?}", v),
+            Literal::String(ref v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+impl Parser {
+    fn parse(&mut self) -> Result<Query> {
+        let mut steps = vec![Step::Field(track!(self.parse_field_ref())?)];
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            let step = match c {
+                '.' => Step::Field(track!(self.parse_field_ref())?),
+                '[' => {
+                    let step = if self.peek() == Some('*') {
+                        self.position += 1;
+                        Step::Index(None)
+                    } else {
+                        let start = self.position;
+                        let n = self.take_while(|c| c.is_ascii_digit());
+                        let n = track_assert_some!(
+                            n.parse().ok(),
+                            ErrorKind::InvalidInput,
+                            "Index expected at {}",
+                            start
+                        );
+                        Step::Index(Some(n))
+                    };
+                    track!(self.expect(']'))?;
+                    step
+                }
+                '{' => {
+                    let step = if self.peek() == Some('*') {
+                        self.position += 1;
+                        Step::AllValues
+                    } else if self.peek() == Some('"') {
+                        Step::Key(track!(self.parse_literal())?)
+                    } else {
+                        let start = self.position;
+                        match self.parse_literal() {
+                            Ok(literal) if self.peek() != Some('=') => Step::Key(literal),
+                            _ => {
+                                self.position = start;
+                                let field = track!(self.parse_field_ref())?;
+                                track!(self.expect('='))?;
+                                Step::Filter(field, track!(self.parse_literal())?)
+                            }
+                        }
+                    };
+                    track!(self.expect('}'))?;
+                    step
+                }
+                _ => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Unexpected character {:?} at {}",
+                    c,
+                    self.position - 1
+                ),
+            };
+            steps.push(step);
+        }
+        Ok(Query { steps })
+    }
+
+    fn parse_field_ref(&mut self) -> Result<FieldRef> {
+        let start = self.position;
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                self.position += 1;
+                self.take_while(|c| c.is_ascii_digit());
+                let s = self.chars[start..self.position].iter().collect::<String>();
+                let id = track_assert_some!(
+                    s.parse().ok(),
+                    ErrorKind::InvalidInput,
+                    "Invalid field identifier {:?} at {}",
+                    s,
+                    start
+                );
+                Ok(FieldRef::Id(id))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                Ok(FieldRef::Name(name))
+            }
+            _ => track_panic!(
+                ErrorKind::InvalidInput,
+                "Field identifier or name expected at {}",
+                start
+            ),
+        }
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal> {
+        let start = self.position;
+        if self.peek() == Some('"') {
+            self.position += 1;
+            let mut s = String::new();
+            loop {
+                let c = track_assert_some!(
+                    self.next(),
+                    ErrorKind::InvalidInput,
+                    "Unterminated string starting at {}",
+                    start
+                );
+                match c {
+                    '"' => break,
+                    '\\' => s.push(track!(self.parse_escape())?),
+                    c => s.push(c),
+                }
+            }
+            return Ok(Literal::String(s));
+        }
+
+        let token = self.take_while(|c| {
+            c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+' || c == '.'
+        });
+        let literal = match token.as_str() {
+            "true" => Some(Literal::Bool(true)),
+            "false" => Some(Literal::Bool(false)),
+            _ => token
+                .parse()
+                .ok()
+                .map(Literal::Int)
+                .or_else(|| token.parse().ok().map(Literal::Double)),
+        };
+        Ok(track_assert_some!(
+            literal,
+            ErrorKind::InvalidInput,
+            "Literal expected at {}",
+            start
+        ))
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        let position = self.position;
+        let c = self.next();
+        track_assert_eq!(
+            c,
+            Some(expected),
+            ErrorKind::InvalidInput,
+            "Unexpected input at {}",
+            position
+        );
+        Ok(())
+    }
+
+    fn parse_escape(&mut self) -> Result<char> {
+        let start = self.position;
+        let c = track_assert_some!(self.next(), ErrorKind::InvalidInput);
+        Ok(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'u' if self.peek() == Some('{') => {
+                self.position += 1;
+                let hex = self.take_while(|c| c.is_ascii_hexdigit());
+                let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                track_assert_eq!(
+                    self.next(),
+                    Some('}'),
+                    ErrorKind::InvalidInput,
+                    "Invalid unicode escape at {}",
+                    start
+                );
+                track_assert_some!(
+                    c,
+                    ErrorKind::InvalidInput,
+                    "Invalid unicode escape at {}",
+                    start
+                )
+            }
+            c => c,
+        })
+    }
+
+    fn take_while<F>(&mut self, f: F) -> String
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.position;
+        while self.peek().is_some_and(&f) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+}
+
+pub(crate) fn resolve_type<'a>(
+    document: &'a Document,
+    t: Option<&'a FieldType>,
+) -> Option<&'a FieldType> {
+    let mut t = t?;
+    for _ in 0..64 {
+        match *t {
+            FieldType::Named(ref name) => match document.definition(name) {
+                Some(Definition::Typedef(d)) => t = &d.field_type,
+                _ => return Some(t),
+            },
+            _ => return Some(t),
+        }
+    }
+    None
+}
+
+fn resolve_field<'a>(
+    document: &'a Document,
+    t: Option<&'a FieldType>,
+    field: &FieldRef,
+) -> Result<(i16, Option<&'a FieldType>)> {
+    let s = match resolve_type(document, t) {
+        Some(FieldType::Named(name)) => match document.definition(name) {
+            Some(Definition::Struct(s) | Definition::Union(s) | Definition::Exception(s)) => {
+                Some(s)
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let found = s.and_then(|s| {
+        s.fields.iter().find(|f| match *field {
+            FieldRef::Id(id) => f.id == id,
+            FieldRef::Name(ref name) => f.name == *name,
+        })
+    });
+    match (field, found) {
+        (_, Some(f)) => Ok((f.id, Some(&f.field_type))),
+        (FieldRef::Id(id), None) => Ok((*id, None)),
+        (FieldRef::Name(name), None) => track_panic!(
+            ErrorKind::InvalidInput,
+            "Cannot resolve the field name {:?}",
+            name
+        ),
+    }
+}
+
+fn element_type<'a>(document: &'a Document, t: Option<&'a FieldType>) -> Option<&'a FieldType> {
+    match resolve_type(document, t)? {
+        FieldType::List(t) | FieldType::Set(t) => Some(t),
+        t @ FieldType::Named(_) => Some(t), // A filter applied to a struct
+        _ => None,
+    }
+}
+
+fn map_value_type<'a>(document: &'a Document, t: Option<&'a FieldType>) -> Option<&'a FieldType> {
+    match resolve_type(document, t)? {
+        FieldType::Map(_, v) => Some(v),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataKind, Field, List, Map, Set};
+
+    #[test]
+    fn parse_works() {
+        for s in [
+            "1",
+            "1.2[*].5",
+            "-1[0]{*}",
+            r#"1.tags{key="http.method"}.value"#,
+            r#"1{"a\"b"}{10}{1.5}{true}"#,
+        ] {
+            let query = track_try_unwrap!(Query::parse(s));
+            assert_eq!(query.to_string(), s);
+        }
+
+        let query = track_try_unwrap!(Query::parse(r#"1{"\u{1b}\'\n"}"#));
+        assert_eq!(query.to_string(), r#"1{"\u{1b}'\n"}"#);
+        assert_eq!(track_try_unwrap!(Query::parse(&query.to_string())), query);
+
+        for s in [
+            "",
+            ".1",
+            "1.",
+            "1[",
+            "1[x]",
+            "1{",
+            r#"1{"foo}"#,
+            r#"1{"\u{110000}"}"#,
+            r#"1{"\u{1b"}"#,
+            "1{a=}",
+            "1 ",
+        ] {
+            assert!(Query::parse(s).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn select_works() {
+        let mut map = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(map.insert("a", 1));
+        track_try_unwrap!(map.insert("b", 2));
+        let root = Struct::new(vec![
+            Field::new(1, map),
+            Field::new(2, Set::from(vec![3, 4])),
+            Field::new(
+                3,
+                List::from(vec![
+                    Struct::new(vec![Field::new(1, 10i64), Field::new(2, "x")]),
+                    Struct::new(vec![Field::new(1, 20i64), Field::new(2, "y")]),
+                ]),
+            ),
+        ]);
+
+        let select = |s: &str| track_try_unwrap!(query(&root, s));
+        assert_eq!(select(r#"1{"b"}"#), [DataRef::I32(&2)]);
+        assert_eq!(select("1{*}"), [DataRef::I32(&1), DataRef::I32(&2)]);
+        assert_eq!(select("2[*]"), [DataRef::I32(&3), DataRef::I32(&4)]);
+        assert_eq!(select("3[1].2"), [DataRef::Binary(b"y")]);
+        assert_eq!(select("3{1=10}.2"), [DataRef::Binary(b"x")]);
+        assert_eq!(select("3[*].2").len(), 2);
+        assert!(select("3[2]").is_empty());
+        assert!(select("4").is_empty());
+        assert!(select("1.1").is_empty());
+
+        let query = track_try_unwrap!(Query::parse("3{1=20}.2"));
+        let selected = track_try_unwrap!(query.select_with_paths(&root));
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].0.to_string(), "3[1].2");
+    }
+}
@@ -0,0 +1,255 @@
+use crate::canonical::Fnv1a64;
+use crate::data::path::{lookup_mut, remove};
+use crate::data::{DataMut, Map, Query, Struct, Uuid};
+use crate::idl::Document;
+use crate::message::Message;
+use crate::{ErrorKind, Result};
+use std::io::Write;
+
+/// The way to redact data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Redaction {
+    /// Removes the field, list (or set) element or map entry.
+    Drop,
+
+    /// Replaces the data with the "zero" value of the kind.
+    ///
+    /// Numbers become `0`, booleans become `false`, UUIDs become the nil UUID,
+    /// and binaries, structs and collections become empty.
+    Zero,
+
+    /// Replaces binaries with the given bytes.
+    ///
+    /// Data of the other kinds are redacted as `Redaction::Zero`.
+    Placeholder(Vec<u8>),
+
+    /// Replaces binaries with the hexadecimal string of their 64-bit FNV-1a hash values.
+    ///
+    /// The hash value is computed over `salt` followed by the original bytes,
+    /// so the same values are replaced with the same strings (e.g., for correlating requests).
+    /// Note that the hash function is not cryptographically secure;
+    /// use a secret salt if the original values have low entropy.
+    ///
+    /// Data of the other kinds are redacted as `Redaction::Zero`.
+    Hash {
+        /// The salt prepended to the hashed bytes.
+        salt: Vec<u8>,
+    },
+}
+
+/// Redactor which scrubs sensitive data from structs and messages.
+///
+/// Each rule consists of a [`Query`] which selects the sensitive data and a [`Redaction`] applied to them.
+/// The kinds of the redacted data are preserved (unless they are dropped),
+/// so the redacted structs and messages can be encoded as usual.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{DataRef, Field, List, Redaction, Redactor, Struct};
+/// use thrift_codec::idl::Document;
+///
+/// let doc = Document::parse(r#"
+///     struct Tag { 1: string key, 2: string value }
+///     struct Request { 1: string user, 2: i64 card_number, 3: string token, 4: list<Tag> tags }
+/// "#).unwrap();
+///
+/// let mut redactor = Redactor::new();
+/// redactor
+///     .rule("user".parse().unwrap(), Redaction::Hash { salt: b"secret".to_vec() })
+///     .rule("card_number".parse().unwrap(), Redaction::Zero)
+///     .rule("token".parse().unwrap(), Redaction::Drop)
+///     .rule(r#"tags{key="cookie"}.value"#.parse().unwrap(), Redaction::Placeholder(b"***".to_vec()));
+/// let redactor = redactor.resolve(&doc, "Request").unwrap();
+///
+/// let mut request = Struct::new(vec![
+///     Field::new(1, "alice"),
+///     Field::new(2, 1234_5678_9012_3456i64),
+///     Field::new(3, "xyz"),
+///     Field::new(4, List::from(vec![Struct::new(vec![Field::new(1, "cookie"), Field::new(2, "abc")])])),
+/// ]);
+/// redactor.redact_struct(&mut request).unwrap();
+///
+/// assert_eq!(request.get_str(1).unwrap().map(|s| s.len()), Some(16));
+/// assert_eq!(request.get_i64(2).unwrap(), Some(0));
+/// assert!(!request.contains(3));
+/// assert_eq!(
+///     thrift_codec::data::query(&request, "4[0].2").unwrap(),
+///     [DataRef::Binary(b"***")]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    rules: Vec<(Query, Redaction)>,
+}
+impl Redactor {
+    /// Makes a new `Redactor` instance which has no rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule which applies `redaction` to the data selected by `query`.
+    ///
+    /// The rules are applied in the order of addition.
+    pub fn rule(&mut self, query: Query, redaction: Redaction) -> &mut Self {
+        self.rules.push((query, redaction));
+        self
+    }
+
+    /// Resolves the field names in the rules by using the type definitions in `document`.
+    ///
+    /// See [`Query::resolve`] for more details.
+    pub fn resolve(&self, document: &Document, root: &str) -> Result<Self> {
+        let mut rules = Vec::with_capacity(self.rules.len());
+        for (query, redaction) in &self.rules {
+            let query = track!(query.resolve(document, root), "query={}", query)?;
+            rules.push((query, redaction.clone()));
+        }
+        Ok(Redactor { rules })
+    }
+
+    /// Redacts `target` by applying the rules of this redactor.
+    ///
+    /// If an error occurs, `target` is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the rules contain unresolved field names,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn redact_struct(&self, target: &mut Struct) -> Result<()> {
+        let mut s = target.clone();
+        for (query, redaction) in &self.rules {
+            let paths = track!(query.select_with_paths(&s), "query={}", query)?
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>();
+
+            // Processes the paths in the reverse order, so that removing an element
+            // does not change the indices of the remaining elements to be processed.
+            for path in paths.iter().rev() {
+                let segments = path.segments();
+                if *redaction == Redaction::Drop {
+                    let (last, parent) =
+                        track_assert_some!(segments.split_last(), ErrorKind::InvalidInput);
+                    let parent = track!(lookup_mut(DataMut::Struct(&mut s), parent))?;
+                    track!(remove(parent, last))?;
+                } else {
+                    let data = track!(lookup_mut(DataMut::Struct(&mut s), segments))?;
+                    redact(data, redaction);
+                }
+            }
+        }
+        *target = s;
+        Ok(())
+    }
+
+    /// Redacts the body of `target` by applying the rules of this redactor.
+    ///
+    /// See [`Redactor::redact_struct`] for more details.
+    pub fn redact_message(&self, target: &mut Message) -> Result<()> {
+        track!(self.redact_struct(target.body_mut()))
+    }
+}
+
+fn redact(data: DataMut<'_>, redaction: &Redaction) {
+    match (data, redaction) {
+        (DataMut::Binary(v), Redaction::Placeholder(placeholder)) => {
+            *v = placeholder.clone();
+        }
+        (DataMut::Binary(v), Redaction::Hash { salt }) => {
+            let mut hasher = Fnv1a64::new();
+            hasher.write_all(salt).expect("Never fails");
+            hasher.write_all(v).expect("Never fails");
+            *v = format!("{:016x}", hasher.finish()).into_bytes();
+        }
+        (DataMut::String(v), Redaction::Placeholder(placeholder)) => {
+            *v = String::from_utf8_lossy(placeholder).into_owned();
+        }
+        (DataMut::String(v), Redaction::Hash { salt }) => {
+            let mut hasher = Fnv1a64::new();
+            hasher.write_all(salt).expect("Never fails");
+            hasher.write_all(v.as_bytes()).expect("Never fails");
+            *v = format!("{:016x}", hasher.finish());
+        }
+        (data, _) => zero(data),
+    }
+}
+
+fn zero(data: DataMut<'_>) {
+    match data {
+        DataMut::Bool(v) => *v = false,
+        DataMut::I8(v) => *v = 0,
+        DataMut::I16(v) => *v = 0,
+        DataMut::I32(v) => *v = 0,
+        DataMut::I64(v) => *v = 0,
+        DataMut::Double(v) => *v = 0.0,
+        DataMut::Binary(v) => v.clear(),
+        DataMut::String(v) => v.clear(),
+        DataMut::Struct(v) => *v = Struct::new(Vec::new()),
+        DataMut::Map(v) => *v = Map::empty_of(v.key_kind(), v.value_kind()),
+        DataMut::Set(v) => v.truncate(0),
+        DataMut::List(v) => v.truncate(0),
+        DataMut::Uuid(v) => *v = Uuid::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataKind, DataRef, Field, List, Set};
+    use crate::{BinaryDecode, BinaryEncode};
+
+    #[test]
+    fn redact_works() {
+        let mut headers = Map::empty_of(DataKind::Binary, DataKind::Binary);
+        track_try_unwrap!(headers.insert("authorization", "Bearer xyz"));
+        track_try_unwrap!(headers.insert("accept", "*/*"));
+        let body = Struct::new(vec![
+            Field::new(1, headers),
+            Field::new(2, List::from(vec![1, 2, 3])),
+            Field::new(3, Set::from(vec![4, 5])),
+            Field::new(
+                4,
+                Struct::new(vec![Field::new(1, 1.5), Field::new(2, true)]),
+            ),
+        ]);
+        let mut message = Message::call("foo", 1, body);
+
+        let mut redactor = Redactor::new();
+        redactor
+            .rule(
+                track_try_unwrap!(r#"1{"authorization"}"#.parse()),
+                Redaction::Placeholder(b"<redacted>".to_vec()),
+            )
+            .rule(track_try_unwrap!("2[*]".parse()), Redaction::Drop)
+            .rule(track_try_unwrap!("3[*]".parse()), Redaction::Zero)
+            .rule(track_try_unwrap!("4".parse()), Redaction::Zero)
+            .rule(track_try_unwrap!("5".parse()), Redaction::Drop);
+        track_try_unwrap!(redactor.redact_message(&mut message));
+
+        let body = message.body();
+        assert_eq!(
+            track_try_unwrap!(crate::data::query(body, r#"1{"authorization"}"#)),
+            [DataRef::Binary(b"<redacted>")]
+        );
+        assert_eq!(body.get_list(2).ok().flatten().map(|l| l.len()), Some(0));
+        assert_eq!(body.get_set(3).ok().flatten(), Some(&Set::from(vec![0, 0])));
+        assert_eq!(
+            body.get_struct(4).ok().flatten(),
+            Some(&Struct::new(Vec::new()))
+        );
+
+        let mut buf = Vec::new();
+        track_try_unwrap!(message.binary_encode(&mut buf));
+        assert_eq!(
+            track_try_unwrap!(Message::binary_decode(&mut &buf[..])),
+            message
+        );
+
+        // Unresolved names are rejected.
+        let mut redactor = Redactor::new();
+        redactor.rule(track_try_unwrap!("foo".parse()), Redaction::Drop);
+        assert!(redactor.redact_message(&mut message).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::data::query::resolve_type;
+use crate::data::{Data, Elements, Struct};
+use crate::idl::{Definition, Document, FieldType};
+use crate::{Error, ErrorKind, Result};
+
+impl Struct {
+    /// Converts the representation of the binaries in this struct according to the type definitions in `document`.
+    ///
+    /// `name` is the name of the struct type of this struct.
+    ///
+    /// The binaries declared as `string` (including the elements, keys and values of the collections)
+    /// are converted to `Data::String`, and the strings declared as `binary` are converted to `Data::Binary`.
+    /// The fields which are not defined in the schema or which kinds are different from the declared ones
+    /// are left untouched.
+    ///
+    /// # Errors
+    ///
+    /// If `name` is not a struct, union or exception defined in `document`, or
+    /// a binary declared as `string` is not a valid UTF-8 sequence,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    /// In that case, this struct is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Data, Field, Struct};
+    /// use thrift_codec::idl::Document;
+    ///
+    /// let doc = Document::parse(r#"
+    ///     struct User {
+    ///         1: string name
+    ///         2: binary avatar
+    ///     }
+    /// "#).unwrap();
+    ///
+    /// let mut user = Struct::new(vec![
+    ///     Field::new(1, Data::Binary(b"alice".to_vec())),
+    ///     Field::new(2, Data::Binary(b"\x89PNG".to_vec())),
+    /// ]);
+    /// user.apply_schema(&doc, "User").unwrap();
+    /// assert_eq!(user.get(1), Some(&Data::String("alice".to_owned())));
+    /// assert!(matches!(user.get(2), Some(Data::Binary(_))));
+    /// ```
+    pub fn apply_schema(&mut self, document: &Document, name: &str) -> Result<()> {
+        track_assert!(
+            struct_fields(document, name).is_some(),
+            ErrorKind::InvalidInput,
+            "Unknown struct: {:?}",
+            name
+        );
+        let mut s = self.clone();
+        track!(apply_struct(document, name, &mut s))?;
+        *self = s;
+        Ok(())
+    }
+}
+
+fn struct_fields<'a>(document: &'a Document, name: &str) -> Option<&'a [crate::idl::Field]> {
+    match document.definition(name) {
+        Some(Definition::Struct(s) | Definition::Union(s) | Definition::Exception(s)) => {
+            Some(&s.fields)
+        }
+        _ => None,
+    }
+}
+
+fn apply_struct(document: &Document, name: &str, target: &mut Struct) -> Result<()> {
+    let fields = match struct_fields(document, name) {
+        Some(fields) => fields,
+        None => return Ok(()),
+    };
+    for field in target.iter_mut() {
+        if let Some(f) = fields.iter().find(|f| f.id == field.id()) {
+            track!(
+                apply_data(document, &f.field_type, field.data_mut()),
+                "field={}",
+                f.name
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn apply_data(document: &Document, t: &FieldType, data: &mut Data) -> Result<()> {
+    match (resolve_type(document, Some(t)), &mut *data) {
+        (Some(FieldType::String), Data::Binary(v)) => {
+            let s = track!(String::from_utf8(std::mem::take(v)).map_err(Error::from))?;
+            *data = Data::String(s);
+        }
+        (Some(FieldType::Binary), Data::String(v)) => {
+            *data = Data::Binary(std::mem::take(v).into_bytes());
+        }
+        (Some(FieldType::Named(name)), Data::Struct(s)) => track!(apply_struct(document, name, s))?,
+        (Some(FieldType::List(t)), Data::List(v)) => track!(apply_elements(document, t, v))?,
+        (Some(FieldType::Set(t)), Data::Set(v)) => track!(apply_elements(document, t, v))?,
+        (Some(FieldType::Map(k, v)), Data::Map(m)) => {
+            let (keys, values) = m.keys_and_values_mut();
+            track!(apply_elements(document, k, keys))?;
+            track!(apply_elements(document, v, values))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn apply_elements(document: &Document, t: &FieldType, elements: &mut Elements) -> Result<()> {
+    match (resolve_type(document, Some(t)), elements) {
+        (Some(FieldType::String), elements) => track!(elements.promote())?,
+        (Some(FieldType::Binary), elements) => elements.demote(),
+        (Some(FieldType::Named(name)), Elements::Struct(v)) => {
+            for s in v {
+                track!(apply_struct(document, name, s))?;
+            }
+        }
+        (Some(FieldType::List(t)), Elements::List(v)) => {
+            for e in v {
+                track!(apply_elements(document, t, e))?;
+            }
+        }
+        (Some(FieldType::Set(t)), Elements::Set(v)) => {
+            for e in v {
+                track!(apply_elements(document, t, e))?;
+            }
+        }
+        (Some(FieldType::Map(k, v)), Elements::Map(maps)) => {
+            for m in maps {
+                let (keys, values) = m.keys_and_values_mut();
+                track!(apply_elements(document, k, keys))?;
+                track!(apply_elements(document, v, values))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{DataKind, DataRef, Field, List, Map};
+    use crate::{BinaryDecode, BinaryEncode};
+
+    #[test]
+    fn apply_schema_works() {
+        let doc = track_try_unwrap!(Document::parse(
+            r#"
+            typedef string Name
+            struct Tag { 1: Name key }
+            struct Item {
+                1: Name name
+                2: list<Tag> tags
+                3: map<string, binary> attrs
+                4: binary raw
+            }
+            "#
+        ));
+
+        let mut attrs = Map::empty_of(DataKind::Binary, DataKind::Binary);
+        track_try_unwrap!(attrs.insert(b"k".to_vec(), b"\xff".to_vec()));
+        let item = Struct::new(vec![
+            Field::new(1, "foo"),
+            Field::new(2, List::from(vec![Struct::new(vec![Field::new(1, "bar")])])),
+            Field::new(3, attrs),
+            Field::new(4, "baz"),
+        ]);
+        let mut buf = Vec::new();
+        track_try_unwrap!(item.binary_encode(&mut buf));
+        let mut decoded = track_try_unwrap!(Struct::binary_decode(&mut &buf[..]));
+        assert!(matches!(decoded.get(1), Some(Data::Binary(_))));
+
+        track_try_unwrap!(decoded.apply_schema(&doc, "Item"));
+        assert_eq!(decoded, item);
+        assert_eq!(decoded.get(1), Some(&Data::String("foo".to_owned())));
+        let tags = track_try_unwrap!(decoded.get_list(2)).unwrap();
+        assert!(
+            matches!(tags.iter().next(), Some(DataRef::Struct(s)) if matches!(s.get(1), Some(Data::String(_))))
+        );
+        let attrs = track_try_unwrap!(decoded.get_map(3)).unwrap();
+        assert!(matches!(
+            attrs.get(0),
+            Some((DataRef::String("k"), DataRef::Binary(b"\xff")))
+        ));
+        assert!(matches!(decoded.get(4), Some(Data::Binary(_))));
+
+        let mut invalid = Struct::new(vec![Field::new(1, b"\xff".to_vec())]);
+        let e = decoded.apply_schema(&doc, "Unknown").unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        let e = invalid.apply_schema(&doc, "Item").unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        assert!(matches!(invalid.get(1), Some(Data::Binary(_))));
+    }
+}
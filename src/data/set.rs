@@ -1,5 +1,7 @@
+use crate::data::iterators::{ElementIntoIter, ElementIter, ElementIterMut};
+use crate::data::{Data, DataMut, DataRef};
 use crate::data::{Elements, List, Map, Struct};
-use std::ops::Deref;
+use std::ops::{Deref, DerefMut};
 
 use super::Uuid;
 
@@ -7,7 +9,7 @@ use super::Uuid;
 ///
 /// Note that internally this has the same representation with `List`.
 /// No duplicate elements are removed.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Set {
     elements: Elements,
@@ -17,6 +19,40 @@ impl Set {
     pub fn new(elements: Elements) -> Self {
         Set { elements }
     }
+
+    /// Removes the duplicate elements from this set.
+    ///
+    /// The first occurrence of each element is retained and the order of the elements is preserved.
+    /// The elements are compared in the total order described in [`DataRef::total_cmp`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::Set;
+    ///
+    /// let mut set = Set::from(vec![3, 1, 3, 2, 1]);
+    /// set.dedup();
+    /// assert_eq!(set, Set::from(vec![3, 1, 2]));
+    /// ```
+    pub fn dedup(&mut self) {
+        let keep = self.elements.dedup_flags(false);
+        self.elements.retain_by_flags(&keep);
+    }
+
+    /// Canonicalizes this set.
+    ///
+    /// The elements are canonicalized recursively, sorted and deduplicated.
+    /// See [`Data::canonicalize`] for more details.
+    pub fn canonicalize(&mut self) {
+        self.elements.canonicalize_each();
+        self.elements.sort();
+        let keep = self.elements.dedup_flags(false);
+        self.elements.retain_by_flags(&keep);
+    }
+
+    pub(crate) fn into_elements(self) -> Elements {
+        self.elements
+    }
 }
 impl Deref for Set {
     type Target = Elements;
@@ -24,6 +60,40 @@ impl Deref for Set {
         &self.elements
     }
 }
+impl DerefMut for Set {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.elements
+    }
+}
+impl<T> FromIterator<T> for Set
+where
+    Vec<T>: Into<Elements>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Set::new(iter.into_iter().collect())
+    }
+}
+impl IntoIterator for Set {
+    type Item = Data;
+    type IntoIter = ElementIntoIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+impl<'a> IntoIterator for &'a Set {
+    type Item = DataRef<'a>;
+    type IntoIter = ElementIter<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter()
+    }
+}
+impl<'a> IntoIterator for &'a mut Set {
+    type Item = DataMut<'a>;
+    type IntoIter = ElementIterMut<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.iter_mut()
+    }
+}
 impl From<Vec<bool>> for Set {
     fn from(f: Vec<bool>) -> Self {
         Set::new(Elements::Bool(f))
@@ -59,6 +129,11 @@ impl From<Vec<Vec<u8>>> for Set {
         Set::new(Elements::Binary(f))
     }
 }
+impl From<Vec<String>> for Set {
+    fn from(f: Vec<String>) -> Self {
+        Set::new(Elements::String(f))
+    }
+}
 impl From<Vec<Struct>> for Set {
     fn from(f: Vec<Struct>) -> Self {
         Set::new(Elements::Struct(f))
@@ -84,3 +159,40 @@ impl From<Vec<Uuid>> for Set {
         Set::new(Elements::Uuid(f))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::DataKind;
+    use crate::ErrorKind;
+
+    #[test]
+    fn mutation_checks_kinds() {
+        let mut set = Set::from(vec!["foo".to_owned()]);
+        let e = set.insert(0, Data::I32(1)).unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::Binary,
+                actual: DataKind::I32
+            }
+        );
+        assert!(set.push(Data::Bool(false)).is_err());
+        assert!(set
+            .extend(vec![Data::from("bar"), Data::Bool(false)])
+            .is_err());
+        assert_eq!(set, Set::from(vec!["foo".to_owned()]));
+
+        track_try_unwrap!(set.extend(vec![Data::from("bar"), Data::Binary(b"baz".to_vec())]));
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.pop(), Some(Data::from("baz")));
+        assert_eq!(set.remove(0), Data::from("foo"));
+        for data in &mut set {
+            assert_eq!(data.kind(), DataKind::Binary);
+        }
+
+        set.truncate(0);
+        assert_eq!(set.kind(), DataKind::Binary);
+        assert!(set.push(Data::I32(1)).is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::data::Data;
+use crate::data::value::unexpected_kind;
+use crate::data::{Data, DataKind, List, Map, Set, Uuid};
+use crate::{Error, Result};
+use std::collections::HashSet;
 
 /// Structure.
 ///
@@ -11,7 +14,7 @@ use crate::data::Data;
 /// let b = Struct::from(("foo", "bar"));
 /// assert_eq!(a, b);
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Struct {
     fields: Vec<Field>,
@@ -26,7 +29,239 @@ impl Struct {
     pub fn fields(&self) -> &[Field] {
         &self.fields
     }
+
+    /// Takes ownership of the fields of this.
+    pub fn into_fields(self) -> Vec<Field> {
+        self.fields
+    }
+
+    /// Returns the data of the field which has the identifier `id`.
+    ///
+    /// If there are multiple fields with the same identifier, the first one is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Data, Struct};
+    ///
+    /// let s = Struct::from(("foo", 10i32));
+    /// assert_eq!(s.get(2), Some(&Data::I32(10)));
+    /// assert_eq!(s.get(3), None);
+    /// ```
+    pub fn get(&self, id: i16) -> Option<&Data> {
+        self.fields.iter().find(|f| f.id == id).map(|f| &f.data)
+    }
+
+    /// Returns the mutable reference to the data of the field which has the identifier `id`.
+    pub fn get_mut(&mut self, id: i16) -> Option<&mut Data> {
+        self.fields
+            .iter_mut()
+            .find(|f| f.id == id)
+            .map(|f| &mut f.data)
+    }
+
+    /// Returns `true` if this struct has a field which has the identifier `id`.
+    pub fn contains(&self, id: i16) -> bool {
+        self.fields.iter().any(|f| f.id == id)
+    }
+
+    /// Inserts `field` into this struct.
+    ///
+    /// If this already has a field with the same identifier, the field is replaced
+    /// (its position is kept) and the old one is returned.
+    /// Otherwise `field` is appended to the end of the fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Data, Field, Struct};
+    ///
+    /// let mut s = Struct::from(("foo", 10i32));
+    /// assert_eq!(s.insert(Field::new(1, "bar")), Some(Field::new(1, "foo")));
+    /// assert_eq!(s.insert(Field::new(3, true)), None);
+    /// assert_eq!(s, Struct::from(("bar", 10i32, true)));
+    /// ```
+    pub fn insert(&mut self, field: Field) -> Option<Field> {
+        if let Some(f) = self.fields.iter_mut().find(|f| f.id == field.id) {
+            Some(std::mem::replace(f, field))
+        } else {
+            self.fields.push(field);
+            None
+        }
+    }
+
+    /// Removes the field which has the identifier `id` and returns it.
+    ///
+    /// If there are multiple fields with the same identifier, the first one is removed.
+    pub fn remove(&mut self, id: i16) -> Option<Field> {
+        let i = self.fields.iter().position(|f| f.id == id)?;
+        Some(self.fields.remove(i))
+    }
+
+    /// Retains only the fields specified by the predicate.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&Field) -> bool,
+    {
+        self.fields.retain(f);
+    }
+
+    /// Returns an iterator which yields the mutable references to the fields of this.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Field> {
+        self.fields.iter_mut()
+    }
+
+    /// Sorts the fields of this by their identifiers.
+    ///
+    /// The sort is stable, so the order of the fields with the same identifier is preserved.
+    pub fn sort_by_id(&mut self) {
+        self.fields.sort_by_key(|f| f.id);
+    }
+
+    /// Canonicalizes this struct.
+    ///
+    /// The data of the fields are canonicalized recursively, then the fields are sorted by their identifiers.
+    /// See [`Data::canonicalize`] for more details.
+    pub fn canonicalize(&mut self) {
+        for f in &mut self.fields {
+            f.data.canonicalize();
+        }
+        self.sort_by_id();
+    }
+
+    /// Returns the identifiers which are shared by multiple fields of this struct.
+    ///
+    /// The result is in the order of the first duplicate occurrence of each identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Field, Struct};
+    ///
+    /// let s = Struct::new(vec![
+    ///     Field::new(2, 1i32),
+    ///     Field::new(1, 1i32),
+    ///     Field::new(2, 2i32),
+    ///     Field::new(1, 3i32),
+    ///     Field::new(2, 4i32),
+    /// ]);
+    /// assert_eq!(s.duplicate_ids(), vec![2, 1]);
+    /// ```
+    pub fn duplicate_ids(&self) -> Vec<i16> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for f in &self.fields {
+            if !seen.insert(f.id) && !duplicates.contains(&f.id) {
+                duplicates.push(f.id);
+            }
+        }
+        duplicates
+    }
+
+    /// Returns the string value of the field which has the identifier `id`.
+    ///
+    /// If there is no such field, this method returns `Ok(None)`.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of the field is not `DataKind::Binary`,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    /// If the field is not a valid UTF-8 string,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::ErrorKind;
+    /// use thrift_codec::data::{DataKind, Struct};
+    ///
+    /// let s = Struct::from((10i64, "foo"));
+    /// assert_eq!(s.get_i64(1).unwrap(), Some(10));
+    /// assert_eq!(s.get_str(2).unwrap(), Some("foo"));
+    /// assert_eq!(s.get_str(3).unwrap(), None);
+    ///
+    /// let e = s.get_str(1).unwrap_err();
+    /// assert_eq!(
+    ///     *e.kind(),
+    ///     ErrorKind::KindMismatch { expected: DataKind::Binary, actual: DataKind::I64 }
+    /// );
+    /// ```
+    pub fn get_str(&self, id: i16) -> Result<Option<&str>> {
+        match self.get(id) {
+            Some(Data::String(v)) => Ok(Some(v)),
+            _ => match track!(self.get_binary(id))? {
+                None => Ok(None),
+                Some(v) => {
+                    let s = track!(std::str::from_utf8(v).map_err(Error::from))?;
+                    Ok(Some(s))
+                }
+            },
+        }
+    }
+
+    /// Returns the value of the field which has the identifier `id` if the kind is `DataKind::Binary`.
+    ///
+    /// Strings are returned as their UTF-8 bytes.
+    ///
+    /// If there is no such field, this method returns `Ok(None)`.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of the field is different,
+    /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+    pub fn get_binary(&self, id: i16) -> Result<Option<&[u8]>> {
+        match self.get(id) {
+            None => Ok(None),
+            Some(d) => match d.as_bytes() {
+                Some(v) => Ok(Some(v)),
+                None => Err(unexpected_kind(DataKind::Binary, d.kind())),
+            },
+        }
+    }
 }
+
+macro_rules! impl_typed_getters {
+    ($($method:ident, $variant:ident, $ty:ty, $deref:tt;)*) => {
+        impl Struct {
+            $(
+                #[doc = concat!(
+                    "Returns the value of the field which has the identifier `id` ",
+                    "if the kind is `DataKind::", stringify!($variant), "`."
+                )]
+                ///
+                /// If there is no such field, this method returns `Ok(None)`.
+                ///
+                /// # Errors
+                ///
+                /// If the kind of the field is different,
+                /// this method will return an error which kind is `ErrorKind::KindMismatch`.
+                pub fn $method(&self, id: i16) -> Result<Option<$ty>> {
+                    match self.get(id) {
+                        None => Ok(None),
+                        Some(Data::$variant(v)) => Ok(Some(impl_typed_getters!(@value $deref v))),
+                        Some(d) => Err(unexpected_kind(DataKind::$variant, d.kind())),
+                    }
+                }
+            )*
+        }
+    };
+    (@value copy $v:ident) => { *$v };
+    (@value ref $v:ident) => { $v };
+}
+impl_typed_getters! {
+    get_bool, Bool, bool, copy;
+    get_i8, I8, i8, copy;
+    get_i16, I16, i16, copy;
+    get_i32, I32, i32, copy;
+    get_i64, I64, i64, copy;
+    get_double, Double, f64, copy;
+    get_struct, Struct, &Struct, ref;
+    get_map, Map, &Map, ref;
+    get_set, Set, &Set, ref;
+    get_list, List, &List, ref;
+    get_uuid, Uuid, &Uuid, ref;
+}
+
 impl<A> From<(A,)> for Struct
 where
     A: Into<Data>,
@@ -94,7 +329,7 @@ where
 }
 
 /// A struct field.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Field {
     id: i16,
@@ -121,14 +356,74 @@ impl Field {
     pub fn data(&self) -> &Data {
         &self.data
     }
+
+    /// Returns the mutable reference to the data of this field.
+    pub fn data_mut(&mut self) -> &mut Data {
+        &mut self.data
+    }
+
+    /// Takes ownership of the data of this field.
+    pub fn into_data(self) -> Data {
+        self.data
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::data::Uuid;
+    use crate::ErrorKind;
 
     use super::*;
 
+    #[test]
+    fn struct_mutation_works() {
+        let mut s = Struct::new(vec![
+            Field::new(3, "foo"),
+            Field::new(1, 10i64),
+            Field::new(2, true),
+        ]);
+        *s.get_mut(1).unwrap() = Data::I64(20);
+        assert_eq!(s.get_i64(1).unwrap(), Some(20));
+        assert_eq!(s.get_str(3).unwrap(), Some("foo"));
+
+        for f in s.iter_mut() {
+            if let Data::Bool(b) = f.data_mut() {
+                *b = !*b;
+            }
+        }
+        assert_eq!(s.get_bool(2).unwrap(), Some(false));
+
+        assert_eq!(s.remove(3), Some(Field::new(3, "foo")));
+        assert_eq!(s.remove(3), None);
+        s.insert(Field::new(0, 1i8));
+        s.sort_by_id();
+        assert_eq!(
+            s,
+            Struct::new(vec![
+                Field::new(0, 1i8),
+                Field::new(1, 20i64),
+                Field::new(2, false)
+            ])
+        );
+
+        s.retain(|f| f.id() != 0);
+        assert_eq!(s, Struct::from((20i64, false)));
+        assert!(s.duplicate_ids().is_empty());
+    }
+
+    #[test]
+    fn typed_getter_errors_work() {
+        let s = Struct::from((vec![0xffu8], 1i32));
+        assert_eq!(*s.get_str(1).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            *s.get_str(2).unwrap_err().kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::Binary,
+                actual: DataKind::I32
+            }
+        );
+    }
+
     #[test]
     fn test_field_uuid() {
         let field = Field::new(1, Uuid::new(*b"0123456789abcdef"));
@@ -0,0 +1,207 @@
+use crate::data::value::unexpected_kind;
+use crate::data::{Data, DataKind, Field, Struct, ThriftValue};
+use crate::{Error, ErrorKind, Result};
+use std::convert::TryFrom;
+
+/// Union (i.e., a struct which has exactly one field).
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use thrift_codec::data::{Data, Field, Struct, Union};
+///
+/// let u = Union::try_from(Struct::new(vec![Field::new(2, "foo")])).unwrap();
+/// assert_eq!(u.id(), 2);
+/// assert_eq!(u.data(), &Data::from("foo"));
+///
+/// assert!(Union::try_from(Struct::new(Vec::new())).is_err());
+/// assert!(Union::try_from(Struct::from((1, 2))).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Union {
+    field: Field,
+}
+impl Union {
+    /// Makes a new `Union` instance which has the only field `field`.
+    pub fn new(field: Field) -> Self {
+        Union { field }
+    }
+
+    /// Returns the identifier of the field of this union.
+    pub fn id(&self) -> i16 {
+        self.field.id()
+    }
+
+    /// Returns the data of the field of this union.
+    pub fn data(&self) -> &Data {
+        self.field.data()
+    }
+
+    /// Returns the field of this union.
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    /// Takes ownership of the field of this union.
+    pub fn into_field(self) -> Field {
+        self.field
+    }
+}
+impl From<Field> for Union {
+    fn from(f: Field) -> Self {
+        Union::new(f)
+    }
+}
+impl From<Union> for Struct {
+    fn from(f: Union) -> Self {
+        Struct::new(vec![f.field])
+    }
+}
+impl TryFrom<Struct> for Union {
+    type Error = Error;
+
+    fn try_from(f: Struct) -> Result<Self> {
+        let mut fields = f.into_fields();
+        track_assert_eq!(
+            fields.len(),
+            1,
+            ErrorKind::InvalidInput,
+            "A union must have exactly one field"
+        );
+        Ok(Union::new(fields.remove(0)))
+    }
+}
+impl ThriftValue for Union {
+    fn kind() -> DataKind {
+        DataKind::Struct
+    }
+    fn to_data(&self) -> Data {
+        Data::Struct(self.clone().into())
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        match data {
+            Data::Struct(v) => track!(Union::try_from(v)),
+            data => Err(unexpected_kind(DataKind::Struct, data.kind())),
+        }
+    }
+}
+
+/// Helpers for the `result` structs (i.e., the bodies of reply messages).
+///
+/// By convention, the field `0` of a `result` struct holds the return value of the method and
+/// the fields `1..` hold the exceptions declared by the method.
+/// Methods returning `void` reply with an empty struct on success.
+impl Struct {
+    /// Makes the `result` struct which holds the successful return value `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Data, Struct};
+    /// use thrift_codec::message::Message;
+    ///
+    /// let reply = Message::reply("add", 1, Struct::success_result(3));
+    /// assert_eq!(reply.body().result_success(), Some(&Data::I32(3)));
+    /// assert_eq!(reply.body().result_exception(), None);
+    /// ```
+    pub fn success_result<T: Into<Data>>(value: T) -> Self {
+        Struct::new(vec![Field::new(0, value)])
+    }
+
+    /// Makes the `result` struct of a successful `void` method.
+    pub fn void_result() -> Self {
+        Struct::new(Vec::new())
+    }
+
+    /// Makes the `result` struct which holds the declared exception `exception`.
+    ///
+    /// `id` is the identifier of the exception in the `throws` clause of the method.
+    ///
+    /// # Errors
+    ///
+    /// If `id` is not positive, this function will return an error which kind is `ErrorKind::InvalidInput`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::data::{Field, Struct};
+    ///
+    /// let e = Struct::new(vec![Field::new(1, "not found")]);
+    /// let result = Struct::exception_result(2, e.clone()).unwrap();
+    /// assert_eq!(result.result_success(), None);
+    /// assert_eq!(result.result_exception(), Some((2, &e)));
+    ///
+    /// assert!(Struct::exception_result(0, e).is_err());
+    /// ```
+    pub fn exception_result(id: i16, exception: Struct) -> Result<Self> {
+        track_assert!(
+            id > 0,
+            ErrorKind::InvalidInput,
+            "Exception field identifier must be positive: {}",
+            id
+        );
+        Ok(Struct::new(vec![Field::new(id, exception)]))
+    }
+
+    /// Returns the successful return value (i.e., the field `0`) if this is a `result` struct holding it.
+    pub fn result_success(&self) -> Option<&Data> {
+        self.get(0)
+    }
+
+    /// Returns the identifier and the value of the declared exception
+    /// (i.e., the first struct field which identifier is positive) if this is a `result` struct holding it.
+    pub fn result_exception(&self) -> Option<(i16, &Struct)> {
+        self.fields()
+            .iter()
+            .filter(|f| f.id() > 0)
+            .find_map(|f| match f.data() {
+                Data::Struct(s) => Some((f.id(), s)),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn union_works() {
+        let u = Union::new(Field::new(3, 1.5));
+        let data = u.to_data();
+        assert_eq!(data, Data::from(Struct::new(vec![Field::new(3, 1.5)])));
+        assert_eq!(track_try_unwrap!(Union::from_data(data)), u);
+        assert_eq!(Struct::from(u.clone()).fields(), [u.into_field()]);
+
+        let e = Union::from_data(Data::I32(1)).unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::Struct,
+                actual: DataKind::I32
+            }
+        );
+        let e = Union::try_from(Struct::from((1, 2))).unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn result_helpers_work() {
+        let void = Struct::void_result();
+        assert_eq!(void.result_success(), None);
+        assert_eq!(void.result_exception(), None);
+
+        let e = Struct::new(vec![Field::new(1, "oops")]);
+        let result = track_try_unwrap!(Struct::exception_result(1, e.clone()));
+        assert_eq!(result.result_exception(), Some((1, &e)));
+        assert!(Struct::exception_result(0, e.clone()).is_err());
+        assert!(Struct::exception_result(-1, e.clone()).is_err());
+        assert_eq!(Struct::success_result(e.clone()).result_exception(), None);
+        assert_eq!(
+            Struct::success_result(e.clone()).result_success(),
+            Some(&Data::Struct(e))
+        );
+    }
+}
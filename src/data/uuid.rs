@@ -1,5 +1,9 @@
+use crate::{Error, ErrorKind, Result};
+use std::fmt;
+use std::str::FromStr;
+
 /// Uuid.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Uuid([u8; 16]);
 
@@ -14,3 +18,34 @@ impl Uuid {
         self.0
     }
 }
+impl fmt::Display for Uuid {
+    /// Formats this UUID in the hyphenated form (e.g., `67e55044-10b1-426f-9247-bb680e5fe0c8`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for Uuid {
+    type Err = Error;
+
+    /// Parses a UUID in the hyphenated form or in the simple form (i.e., 32 hexadecimal digits).
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s.replace('-', "");
+        track_assert!(
+            hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+            ErrorKind::InvalidInput,
+            "invalid UUID: {:?}",
+            s
+        );
+        let mut uuid = [0; 16];
+        for (i, b) in uuid.iter_mut().enumerate() {
+            *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).expect("Never fails");
+        }
+        Ok(Uuid(uuid))
+    }
+}
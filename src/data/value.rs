@@ -0,0 +1,374 @@
+use crate::data::{Data, DataKind, Elements, List, Map, Set, Struct, Uuid};
+use crate::{Error, ErrorKind, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+use trackable::error::ErrorKindExt;
+
+/// This trait allows to convert Rust values to/from `Data`.
+///
+/// The code generated by the [`codegen`](crate::codegen) module uses this trait for
+/// (de)serializing user defined types.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use thrift_codec::data::{Data, DataKind, ThriftValue};
+///
+/// let mut tags = BTreeMap::new();
+/// tags.insert("http.method".to_owned(), vec![1i32, 2]);
+///
+/// let data = tags.to_data();
+/// assert_eq!(data.kind(), DataKind::Map);
+/// assert_eq!(BTreeMap::<String, Vec<i32>>::from_data(data).unwrap(), tags);
+/// ```
+pub trait ThriftValue: Sized {
+    /// Returns the kind of the data corresponding to this type.
+    fn kind() -> DataKind;
+
+    /// Converts this value to a `Data`.
+    fn to_data(&self) -> Data;
+
+    /// Converts `data` to a value of this type.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of `data` is not `Self::kind()`, this function will return
+    /// an error which kind is `ErrorKind::KindMismatch`.
+    /// If the content of `data` is invalid, this function will return
+    /// an error which kind is `ErrorKind::InvalidInput`.
+    fn from_data(data: Data) -> Result<Self>;
+
+    /// Converts the given values to an `Elements`.
+    fn to_elements<'a, I>(values: I) -> Elements
+    where
+        I: IntoIterator<Item = &'a Self>,
+        Self: 'a,
+    {
+        let mut elements = Elements::new(Self::kind());
+        for v in values {
+            elements.push(v.to_data()).expect("Never fails");
+        }
+        elements
+    }
+
+    /// Converts `elements` to the values of this type.
+    fn from_elements(elements: Elements) -> Result<Vec<Self>> {
+        if elements.kind() != Self::kind() {
+            return Err(unexpected_kind(Self::kind(), elements.kind()));
+        }
+        elements
+            .into_data_vec()
+            .into_iter()
+            .map(|data| track!(Self::from_data(data)))
+            .collect()
+    }
+}
+
+macro_rules! impl_primitive_value {
+    ($ty:ty, $variant:ident) => {
+        impl ThriftValue for $ty {
+            fn kind() -> DataKind {
+                DataKind::$variant
+            }
+            fn to_data(&self) -> Data {
+                Data::$variant(self.clone())
+            }
+            fn from_data(data: Data) -> Result<Self> {
+                match data {
+                    Data::$variant(v) => Ok(v),
+                    data => Err(unexpected_kind(DataKind::$variant, data.kind())),
+                }
+            }
+            fn to_elements<'a, I>(values: I) -> Elements
+            where
+                I: IntoIterator<Item = &'a Self>,
+            {
+                Elements::$variant(values.into_iter().cloned().collect())
+            }
+            fn from_elements(elements: Elements) -> Result<Vec<Self>> {
+                match elements {
+                    Elements::$variant(v) => Ok(v),
+                    elements => Err(unexpected_kind(DataKind::$variant, elements.kind())),
+                }
+            }
+        }
+    };
+}
+impl_primitive_value!(bool, Bool);
+impl_primitive_value!(i8, I8);
+impl_primitive_value!(i16, I16);
+impl_primitive_value!(i32, I32);
+impl_primitive_value!(i64, I64);
+impl_primitive_value!(f64, Double);
+impl_primitive_value!(Struct, Struct);
+impl_primitive_value!(Map, Map);
+impl_primitive_value!(Set, Set);
+impl_primitive_value!(List, List);
+impl_primitive_value!(Uuid, Uuid);
+
+impl ThriftValue for Vec<u8> {
+    fn kind() -> DataKind {
+        DataKind::Binary
+    }
+    fn to_data(&self) -> Data {
+        Data::Binary(self.clone())
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        match data {
+            Data::Binary(v) => Ok(v),
+            Data::String(v) => Ok(v.into_bytes()),
+            data => Err(unexpected_kind(DataKind::Binary, data.kind())),
+        }
+    }
+    fn to_elements<'a, I>(values: I) -> Elements
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        Elements::Binary(values.into_iter().cloned().collect())
+    }
+    fn from_elements(elements: Elements) -> Result<Vec<Self>> {
+        track!(Vec::try_from(elements))
+    }
+}
+impl ThriftValue for String {
+    fn kind() -> DataKind {
+        DataKind::Binary
+    }
+    fn to_data(&self) -> Data {
+        Data::String(self.clone())
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        match data {
+            Data::String(v) => Ok(v),
+            data => {
+                let bytes = track!(Vec::<u8>::from_data(data))?;
+                track!(String::from_utf8(bytes).map_err(Error::from))
+            }
+        }
+    }
+    fn to_elements<'a, I>(values: I) -> Elements
+    where
+        I: IntoIterator<Item = &'a Self>,
+    {
+        Elements::String(values.into_iter().cloned().collect())
+    }
+    fn from_elements(elements: Elements) -> Result<Vec<Self>> {
+        track!(Vec::try_from(elements))
+    }
+}
+impl<T: ThriftValue> ThriftValue for Box<T> {
+    fn kind() -> DataKind {
+        T::kind()
+    }
+    fn to_data(&self) -> Data {
+        (**self).to_data()
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        track!(T::from_data(data)).map(Box::new)
+    }
+}
+impl<T: ThriftValue> ThriftValue for Vec<T> {
+    fn kind() -> DataKind {
+        DataKind::List
+    }
+    fn to_data(&self) -> Data {
+        Data::List(List::new(T::to_elements(self)))
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        let list = track!(List::from_data(data))?;
+        track!(T::from_elements(list.into_elements()))
+    }
+}
+impl<T: ThriftValue + Ord> ThriftValue for BTreeSet<T> {
+    fn kind() -> DataKind {
+        DataKind::Set
+    }
+    fn to_data(&self) -> Data {
+        Data::Set(Set::new(T::to_elements(self)))
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        let set = track!(Set::from_data(data))?;
+        let values = track!(T::from_elements(set.into_elements()))?;
+        Ok(values.into_iter().collect())
+    }
+}
+impl<K: ThriftValue + Ord, V: ThriftValue> ThriftValue for BTreeMap<K, V> {
+    fn kind() -> DataKind {
+        DataKind::Map
+    }
+    fn to_data(&self) -> Data {
+        let keys = K::to_elements(self.keys());
+        let values = V::to_elements(self.values());
+        Data::Map(Map::from_keys_and_values(keys, values).expect("Never fails"))
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        let (keys, values) = track!(Map::from_data(data))?.into_keys_and_values();
+        let keys = track!(K::from_elements(keys))?;
+        let values = track!(V::from_elements(values))?;
+        Ok(keys.into_iter().zip(values).collect())
+    }
+}
+
+impl<T, S> ThriftValue for HashSet<T, S>
+where
+    T: ThriftValue + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn kind() -> DataKind {
+        DataKind::Set
+    }
+    fn to_data(&self) -> Data {
+        Data::Set(Set::new(T::to_elements(self)))
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        let set = track!(Set::from_data(data))?;
+        let values = track!(T::from_elements(set.into_elements()))?;
+        Ok(values.into_iter().collect())
+    }
+}
+impl<K, V, S> ThriftValue for HashMap<K, V, S>
+where
+    K: ThriftValue + Eq + Hash,
+    V: ThriftValue,
+    S: BuildHasher + Default,
+{
+    fn kind() -> DataKind {
+        DataKind::Map
+    }
+    fn to_data(&self) -> Data {
+        let keys = K::to_elements(self.keys());
+        let values = V::to_elements(self.values());
+        Data::Map(Map::from_keys_and_values(keys, values).expect("Never fails"))
+    }
+    fn from_data(data: Data) -> Result<Self> {
+        let (keys, values) = track!(Map::from_data(data))?.into_keys_and_values();
+        let keys = track!(K::from_elements(keys))?;
+        let values = track!(V::from_elements(values))?;
+        Ok(keys.into_iter().zip(values).collect())
+    }
+}
+
+macro_rules! impl_try_from_data {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<Data> for $ty {
+                type Error = Error;
+
+                fn try_from(data: Data) -> Result<Self> {
+                    track!(<$ty as ThriftValue>::from_data(data))
+                }
+            }
+        )*
+    };
+}
+impl_try_from_data!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    f64,
+    String,
+    Vec<u8>,
+    Struct,
+    Map,
+    Set,
+    List,
+    Uuid
+);
+
+impl<T: ThriftValue> TryFrom<Data> for Vec<T> {
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self> {
+        track!(<Self as ThriftValue>::from_data(data))
+    }
+}
+impl<T: ThriftValue + Ord> TryFrom<Data> for BTreeSet<T> {
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self> {
+        track!(<Self as ThriftValue>::from_data(data))
+    }
+}
+impl<K: ThriftValue + Ord, V: ThriftValue> TryFrom<Data> for BTreeMap<K, V> {
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self> {
+        track!(<Self as ThriftValue>::from_data(data))
+    }
+}
+impl<T, S> TryFrom<Data> for HashSet<T, S>
+where
+    T: ThriftValue + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self> {
+        track!(<Self as ThriftValue>::from_data(data))
+    }
+}
+impl<K, V, S> TryFrom<Data> for HashMap<K, V, S>
+where
+    K: ThriftValue + Eq + Hash,
+    V: ThriftValue,
+    S: BuildHasher + Default,
+{
+    type Error = Error;
+
+    fn try_from(data: Data) -> Result<Self> {
+        track!(<Self as ThriftValue>::from_data(data))
+    }
+}
+
+pub(crate) fn unexpected_kind(expected: DataKind, actual: DataKind) -> Error {
+    track!(Error::from(
+        ErrorKind::KindMismatch { expected, actual }.cause(format!(
+            "Unexpected data kind: expected={:?}, actual={:?}",
+            expected, actual
+        ))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_from_data_works() {
+        let mut map = HashMap::new();
+        map.insert("foo".to_owned(), vec![1i64, 2]);
+        let data = map.to_data();
+        assert_eq!(data.as_map().map(Map::len), Some(1));
+        assert_eq!(HashMap::try_from(data.clone()).ok(), Some(map));
+
+        let e = HashMap::<String, Vec<i32>>::try_from(data).unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::I32,
+                actual: DataKind::I64
+            }
+        );
+
+        let data = Data::from("bar");
+        assert_eq!(data.as_str(), Some("bar"));
+        assert_eq!(data.as_bytes(), Some(&b"bar"[..]));
+        assert_eq!(data.as_i32(), None);
+        assert_eq!(String::try_from(data).ok(), Some("bar".to_owned()));
+
+        let set: HashSet<i8> = [1, 2, 3].into_iter().collect();
+        assert_eq!(HashSet::try_from(set.to_data()).ok(), Some(set));
+
+        let e = String::try_from(Data::Bool(true)).unwrap_err();
+        assert_eq!(
+            *e.kind(),
+            ErrorKind::KindMismatch {
+                expected: DataKind::Binary,
+                actual: DataKind::Bool
+            }
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Visitors over the data model.
+//!
+//! [`Visitor`] and [`VisitorMut`] traverse data trees recursively.
+//! All the methods have the default implementations which just visit the children
+//! (by calling the `walk_*` functions in this module), so an implementation only needs to
+//! override the methods for the nodes which it is interested in.
+//!
+//! Every method receives the [`Path`] of the visited node:
+//!
+//! - The data of a struct field is visited with `PathSegment::Field`.
+//! - The elements of lists and sets are visited with `PathSegment::Index`.
+//! - The values of maps are visited with `PathSegment::Key`.
+//!   The keys are visited with the path of the map itself.
+//!
+//! # Examples
+//!
+//! ```
+//! use thrift_codec::data::visit::{Visitor, VisitorMut};
+//! use thrift_codec::data::{Data, DataRef, Field, List, Path, Struct};
+//!
+//! // Counts the binaries.
+//! #[derive(Default)]
+//! struct Stats {
+//!     binaries: usize,
+//!     bytes: usize,
+//! }
+//! impl Visitor for Stats {
+//!     fn visit_binary(&mut self, _path: &Path, value: &[u8]) {
+//!         self.binaries += 1;
+//!         self.bytes += value.len();
+//!     }
+//! }
+//!
+//! // Redacts the field `2` of every struct.
+//! struct Redact;
+//! impl VisitorMut for Redact {
+//!     fn visit_field_mut(&mut self, path: &Path, field: &mut Field) {
+//!         if field.id() == 2 {
+//!             *field.data_mut() = Data::from("***");
+//!         }
+//!         thrift_codec::data::visit::walk_field_mut(self, path, field);
+//!     }
+//! }
+//!
+//! let mut s = Struct::new(vec![
+//!     Field::new(1, "alice"),
+//!     Field::new(3, List::from(vec![Struct::new(vec![Field::new(2, "secret")])])),
+//! ]);
+//!
+//! let mut stats = Stats::default();
+//! stats.visit_struct(&Path::root(), &s);
+//! assert_eq!((stats.binaries, stats.bytes), (2, 11));
+//!
+//! Redact.visit_struct_mut(&Path::root(), &mut s);
+//! assert_eq!(
+//!     thrift_codec::data::query(&s, "3[0].2").unwrap(),
+//!     [DataRef::Binary(b"***")]
+//! );
+//! ```
+use crate::data::{
+    DataMut, DataRef, Elements, Field, List, Map, Path, PathSegment, Set, Struct, Uuid,
+};
+
+/// A visitor which traverses data trees.
+///
+/// See the [module level documentation](self) for more details.
+#[allow(unused_variables)]
+pub trait Visitor {
+    /// Visits a datum.
+    fn visit_data(&mut self, path: &Path, data: DataRef<'_>) {
+        walk_data(self, path, data);
+    }
+
+    /// Visits a `bool` value.
+    fn visit_bool(&mut self, path: &Path, value: bool) {}
+
+    /// Visits an `i8` value.
+    fn visit_i8(&mut self, path: &Path, value: i8) {}
+
+    /// Visits an `i16` value.
+    fn visit_i16(&mut self, path: &Path, value: i16) {}
+
+    /// Visits an `i32` value.
+    fn visit_i32(&mut self, path: &Path, value: i32) {}
+
+    /// Visits an `i64` value.
+    fn visit_i64(&mut self, path: &Path, value: i64) {}
+
+    /// Visits a `double` value.
+    fn visit_double(&mut self, path: &Path, value: f64) {}
+
+    /// Visits a binary.
+    fn visit_binary(&mut self, path: &Path, value: &[u8]) {}
+
+    /// Visits a string.
+    ///
+    /// The default implementation visits the string as a binary.
+    fn visit_string(&mut self, path: &Path, value: &str) {
+        self.visit_binary(path, value.as_bytes());
+    }
+
+    /// Visits a UUID.
+    fn visit_uuid(&mut self, path: &Path, value: &Uuid) {}
+
+    /// Visits a struct.
+    fn visit_struct(&mut self, path: &Path, value: &Struct) {
+        walk_struct(self, path, value);
+    }
+
+    /// Visits a field of a struct.
+    ///
+    /// `path` is the path of the field (i.e., it ends with `PathSegment::Field`).
+    fn visit_field(&mut self, path: &Path, field: &Field) {
+        walk_field(self, path, field);
+    }
+
+    /// Visits a map.
+    fn visit_map(&mut self, path: &Path, value: &Map) {
+        walk_map(self, path, value);
+    }
+
+    /// Visits an entry of a map.
+    ///
+    /// `path` is the path of the map.
+    fn visit_map_entry(&mut self, path: &Path, key: DataRef<'_>, value: DataRef<'_>) {
+        walk_map_entry(self, path, key, value);
+    }
+
+    /// Visits a set.
+    fn visit_set(&mut self, path: &Path, value: &Set) {
+        walk_elements(self, path, value);
+    }
+
+    /// Visits a list.
+    fn visit_list(&mut self, path: &Path, value: &List) {
+        walk_elements(self, path, value);
+    }
+}
+
+/// Visits the content of `data`.
+pub fn walk_data<V: Visitor + ?Sized>(visitor: &mut V, path: &Path, data: DataRef<'_>) {
+    match data {
+        DataRef::Bool(v) => visitor.visit_bool(path, *v),
+        DataRef::I8(v) => visitor.visit_i8(path, *v),
+        DataRef::I16(v) => visitor.visit_i16(path, *v),
+        DataRef::I32(v) => visitor.visit_i32(path, *v),
+        DataRef::I64(v) => visitor.visit_i64(path, *v),
+        DataRef::Double(v) => visitor.visit_double(path, *v),
+        DataRef::Binary(v) => visitor.visit_binary(path, v),
+        DataRef::String(v) => visitor.visit_string(path, v),
+        DataRef::Struct(v) => visitor.visit_struct(path, v),
+        DataRef::Map(v) => visitor.visit_map(path, v),
+        DataRef::Set(v) => visitor.visit_set(path, v),
+        DataRef::List(v) => visitor.visit_list(path, v),
+        DataRef::Uuid(v) => visitor.visit_uuid(path, v),
+    }
+}
+
+/// Visits the fields of `value`.
+pub fn walk_struct<V: Visitor + ?Sized>(visitor: &mut V, path: &Path, value: &Struct) {
+    for field in value.fields() {
+        visitor.visit_field(&path.join(PathSegment::Field(field.id())), field);
+    }
+}
+
+/// Visits the data of `field`.
+pub fn walk_field<V: Visitor + ?Sized>(visitor: &mut V, path: &Path, field: &Field) {
+    visitor.visit_data(path, field.data().as_ref());
+}
+
+/// Visits the entries of `value`.
+pub fn walk_map<V: Visitor + ?Sized>(visitor: &mut V, path: &Path, value: &Map) {
+    for (k, v) in value.iter() {
+        visitor.visit_map_entry(path, k, v);
+    }
+}
+
+/// Visits the key and value of a map entry.
+pub fn walk_map_entry<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    path: &Path,
+    key: DataRef<'_>,
+    value: DataRef<'_>,
+) {
+    visitor.visit_data(path, key);
+    visitor.visit_data(&path.join(PathSegment::Key(key.to_owned())), value);
+}
+
+/// Visits the elements of a list or a set.
+pub fn walk_elements<V: Visitor + ?Sized>(visitor: &mut V, path: &Path, elements: &Elements) {
+    for (i, e) in elements.iter().enumerate() {
+        visitor.visit_data(&path.join(PathSegment::Index(i)), e);
+    }
+}
+
+/// A visitor which traverses and modifies data trees.
+///
+/// The kinds of the visited data can not be changed
+/// (replacing a field with a datum of another kind is possible via `Field::data_mut`).
+///
+/// See the [module level documentation](self) for more details.
+#[allow(unused_variables)]
+pub trait VisitorMut {
+    /// Visits a datum.
+    fn visit_data_mut(&mut self, path: &Path, data: DataMut<'_>) {
+        walk_data_mut(self, path, data);
+    }
+
+    /// Visits a `bool` value.
+    fn visit_bool_mut(&mut self, path: &Path, value: &mut bool) {}
+
+    /// Visits an `i8` value.
+    fn visit_i8_mut(&mut self, path: &Path, value: &mut i8) {}
+
+    /// Visits an `i16` value.
+    fn visit_i16_mut(&mut self, path: &Path, value: &mut i16) {}
+
+    /// Visits an `i32` value.
+    fn visit_i32_mut(&mut self, path: &Path, value: &mut i32) {}
+
+    /// Visits an `i64` value.
+    fn visit_i64_mut(&mut self, path: &Path, value: &mut i64) {}
+
+    /// Visits a `double` value.
+    fn visit_double_mut(&mut self, path: &Path, value: &mut f64) {}
+
+    /// Visits a binary.
+    fn visit_binary_mut(&mut self, path: &Path, value: &mut Vec<u8>) {}
+
+    /// Visits a string.
+    ///
+    /// The default implementation visits a copy of the string as a binary and writes the result back.
+    /// If the result is not a valid UTF-8 string, the invalid sequences are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER` (use `Field::data_mut` to store arbitrary bytes instead).
+    fn visit_string_mut(&mut self, path: &Path, value: &mut String) {
+        let mut bytes = value.as_bytes().to_vec();
+        self.visit_binary_mut(path, &mut bytes);
+        if bytes != value.as_bytes() {
+            *value = match String::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            };
+        }
+    }
+
+    /// Visits a UUID.
+    fn visit_uuid_mut(&mut self, path: &Path, value: &mut Uuid) {}
+
+    /// Visits a struct.
+    fn visit_struct_mut(&mut self, path: &Path, value: &mut Struct) {
+        walk_struct_mut(self, path, value);
+    }
+
+    /// Visits a field of a struct.
+    ///
+    /// `path` is the path of the field (i.e., it ends with `PathSegment::Field`).
+    fn visit_field_mut(&mut self, path: &Path, field: &mut Field) {
+        walk_field_mut(self, path, field);
+    }
+
+    /// Visits a map.
+    fn visit_map_mut(&mut self, path: &Path, value: &mut Map) {
+        walk_map_mut(self, path, value);
+    }
+
+    /// Visits an entry of a map.
+    ///
+    /// `path` is the path of the map.
+    fn visit_map_entry_mut(&mut self, path: &Path, key: DataMut<'_>, value: DataMut<'_>) {
+        walk_map_entry_mut(self, path, key, value);
+    }
+
+    /// Visits a set.
+    fn visit_set_mut(&mut self, path: &Path, value: &mut Set) {
+        walk_elements_mut(self, path, value);
+    }
+
+    /// Visits a list.
+    fn visit_list_mut(&mut self, path: &Path, value: &mut List) {
+        walk_elements_mut(self, path, value);
+    }
+}
+
+/// Visits the content of `data`.
+pub fn walk_data_mut<V: VisitorMut + ?Sized>(visitor: &mut V, path: &Path, data: DataMut<'_>) {
+    match data {
+        DataMut::Bool(v) => visitor.visit_bool_mut(path, v),
+        DataMut::I8(v) => visitor.visit_i8_mut(path, v),
+        DataMut::I16(v) => visitor.visit_i16_mut(path, v),
+        DataMut::I32(v) => visitor.visit_i32_mut(path, v),
+        DataMut::I64(v) => visitor.visit_i64_mut(path, v),
+        DataMut::Double(v) => visitor.visit_double_mut(path, v),
+        DataMut::Binary(v) => visitor.visit_binary_mut(path, v),
+        DataMut::String(v) => visitor.visit_string_mut(path, v),
+        DataMut::Struct(v) => visitor.visit_struct_mut(path, v),
+        DataMut::Map(v) => visitor.visit_map_mut(path, v),
+        DataMut::Set(v) => visitor.visit_set_mut(path, v),
+        DataMut::List(v) => visitor.visit_list_mut(path, v),
+        DataMut::Uuid(v) => visitor.visit_uuid_mut(path, v),
+    }
+}
+
+/// Visits the fields of `value`.
+pub fn walk_struct_mut<V: VisitorMut + ?Sized>(visitor: &mut V, path: &Path, value: &mut Struct) {
+    for field in value.iter_mut() {
+        visitor.visit_field_mut(&path.join(PathSegment::Field(field.id())), field);
+    }
+}
+
+/// Visits the data of `field`.
+pub fn walk_field_mut<V: VisitorMut + ?Sized>(visitor: &mut V, path: &Path, field: &mut Field) {
+    visitor.visit_data_mut(path, field.data_mut().as_mut());
+}
+
+/// Visits the entries of `value`.
+pub fn walk_map_mut<V: VisitorMut + ?Sized>(visitor: &mut V, path: &Path, value: &mut Map) {
+    let (keys, values) = value.keys_and_values_mut();
+    for (k, v) in keys.iter_mut().zip(values.iter_mut()) {
+        visitor.visit_map_entry_mut(path, k, v);
+    }
+}
+
+/// Visits the key and value of a map entry.
+///
+/// The path of the value is determined by the key before it is visited.
+pub fn walk_map_entry_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    path: &Path,
+    key: DataMut<'_>,
+    value: DataMut<'_>,
+) {
+    let value_path = path.join(PathSegment::Key(key.as_ref().to_owned()));
+    visitor.visit_data_mut(path, key);
+    visitor.visit_data_mut(&value_path, value);
+}
+
+/// Visits the elements of a list or a set.
+pub fn walk_elements_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    path: &Path,
+    elements: &mut Elements,
+) {
+    for (i, e) in elements.iter_mut().enumerate() {
+        visitor.visit_data_mut(&path.join(PathSegment::Index(i)), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Data, DataKind};
+
+    #[test]
+    fn visitor_works() {
+        #[derive(Default)]
+        struct Collect(Vec<String>);
+        impl Visitor for Collect {
+            fn visit_i32(&mut self, path: &Path, value: i32) {
+                self.0.push(format!("{}={}", path, value));
+            }
+            fn visit_binary(&mut self, path: &Path, value: &[u8]) {
+                self.0
+                    .push(format!("{}={:?}", path, String::from_utf8_lossy(value)));
+            }
+        }
+
+        let mut map = Map::empty_of(DataKind::Binary, DataKind::I32);
+        track_try_unwrap!(map.insert("a", 1));
+        let s = Struct::new(vec![
+            Field::new(1, map),
+            Field::new(2, Set::from(vec![2, 3])),
+            Field::new(3, List::from(vec![Struct::from((4,))])),
+        ]);
+
+        let mut c = Collect::default();
+        c.visit_data(&Path::root(), DataRef::Struct(&s));
+        assert_eq!(
+            c.0,
+            [r#"1="a""#, r#"1{"a"}=1"#, "2[0]=2", "2[1]=3", "3[0].1=4"]
+        );
+    }
+
+    #[test]
+    fn visitor_mut_works() {
+        struct Increment;
+        impl VisitorMut for Increment {
+            fn visit_i32_mut(&mut self, _path: &Path, value: &mut i32) {
+                *value += 1;
+            }
+        }
+
+        let mut map = Map::empty_of(DataKind::I32, DataKind::I32);
+        track_try_unwrap!(map.insert(1, 10));
+        let mut data = Data::from(Struct::new(vec![
+            Field::new(1, map),
+            Field::new(2, List::from(vec![Struct::from((4,))])),
+        ]));
+        Increment.visit_data_mut(&Path::root(), data.as_mut());
+
+        let mut map = Map::empty_of(DataKind::I32, DataKind::I32);
+        track_try_unwrap!(map.insert(2, 11));
+        assert_eq!(
+            data,
+            Data::from(Struct::new(vec![
+                Field::new(1, map),
+                Field::new(2, List::from(vec![Struct::from((5,))])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn visitor_mut_visits_strings_as_binaries() {
+        struct Upper;
+        impl VisitorMut for Upper {
+            fn visit_binary_mut(&mut self, _path: &Path, value: &mut Vec<u8>) {
+                value.make_ascii_uppercase();
+            }
+        }
+
+        let mut s = Struct::new(vec![
+            Field::new(1, Data::from("x")),
+            Field::new(2, List::from(vec!["a".to_owned(), "b".to_owned()])),
+            Field::new(3, Data::from(b"y".to_vec())),
+        ]);
+        Upper.visit_struct_mut(&Path::root(), &mut s);
+        assert!(matches!(s.get(1), Some(Data::String(v)) if v == "X"));
+        assert_eq!(
+            s.get(2),
+            Some(&Data::from(List::from(vec![
+                "A".to_owned(),
+                "B".to_owned()
+            ])))
+        );
+        assert!(matches!(s.get(3), Some(Data::Binary(v)) if v == b"Y"));
+
+        struct Hash;
+        impl VisitorMut for Hash {
+            fn visit_binary_mut(&mut self, _path: &Path, value: &mut Vec<u8>) {
+                *value = vec![b'#', 0xFF];
+            }
+        }
+        let mut data = Data::from("secret");
+        Hash.visit_data_mut(&Path::root(), data.as_mut());
+        assert!(matches!(data, Data::String(ref v) if v == "#\u{FFFD}"));
+    }
+}
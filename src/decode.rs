@@ -4,7 +4,7 @@ use crate::message::{Message, MessageKind};
 use crate::zigzag;
 use crate::{Error, ErrorKind, Result};
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
-use std::io::Read;
+use std::io::{self, Read};
 
 /// This trait allows to decode objects which encoded by the [Thrift Binary protocol encoding][encoding].
 ///
@@ -50,9 +50,7 @@ impl BinaryDecode for Vec<u8> {
         let size = track_io!(reader.read_i32::<BigEndian>())?;
         track_assert!(size >= 0, ErrorKind::InvalidInput, "size={}", size);
 
-        let mut buf = vec![0; size as usize];
-        track_io!(reader.read_exact(&mut buf))?;
-        Ok(buf)
+        track!(read_bytes(reader, size as u64))
     }
 }
 impl BinaryDecode for Message {
@@ -180,6 +178,7 @@ fn binary_decode_element<R: Read>(reader: &mut R, elements: &mut Elements) -> Re
         Elements::I64(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
         Elements::Double(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
         Elements::Binary(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
+        Elements::String(_) => unreachable!("Decoded elements are made by `Elements::new`"),
         Elements::Struct(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
         Elements::Map(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
         Elements::Set(ref mut v) => v.push(track!(BinaryDecode::binary_decode(reader))?),
@@ -198,8 +197,12 @@ pub trait CompactDecode: Sized {
 }
 impl CompactDecode for bool {
     fn compact_decode<R: Read>(reader: &mut R) -> Result<Self> {
+        // [NOTE]
+        //
+        // Apache Thrift implementations encode `false` as `2` (i.e., `COMPACT_FIELD_BOOLEAN_FALSE`)
+        // while this crate has encoded it as `0`. Both are accepted.
         let b = track_io!(reader.read_u8())?;
-        track_assert!(b < 2, ErrorKind::InvalidInput, "b={}", b);
+        track_assert!(b < 3, ErrorKind::InvalidInput, "b={}", b);
         Ok(b == 1)
     }
 }
@@ -245,9 +248,7 @@ impl CompactDecode for Vec<u8> {
         let size = track!(read_varint(reader))?;
         track_assert!(size <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
 
-        let mut buf = vec![0; size as usize];
-        track_io!(reader.read_exact(&mut buf[..]))?;
-        Ok(buf)
+        track!(read_bytes(reader, size))
     }
 }
 impl CompactDecode for Message {
@@ -331,6 +332,9 @@ impl CompactDecode for Struct {
                 constants::COMPACT_FIELD_STRUCT => {
                     Data::Struct(track!(CompactDecode::compact_decode(reader))?)
                 }
+                constants::COMPACT_FIELD_UUID => {
+                    Data::Uuid(track!(CompactDecode::compact_decode(reader))?)
+                }
                 _ => track_panic!(ErrorKind::InvalidInput, "kind={}", kind),
             };
             fields.push(Field::new(id, data));
@@ -340,31 +344,24 @@ impl CompactDecode for Struct {
 }
 impl CompactDecode for Map {
     fn compact_decode<R: Read>(reader: &mut R) -> Result<Self> {
-        let first = track_io!(reader.read_u8())?;
-        if first == 0 {
-            Ok(Map::empty())
-        } else {
-            let size = {
-                let first = [first];
-                let mut reader = first.chain(&mut *reader);
-                track!(read_varint(&mut reader))?
-            };
-            track_assert!(size <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
-
-            let kinds = track_io!(reader.read_u8())?;
-            let key_kind =
-                track_assert_some!(DataKind::from_u8(kinds >> 4), ErrorKind::InvalidInput);
-            let value_kind =
-                track_assert_some!(DataKind::from_u8(kinds & 0b1111), ErrorKind::InvalidInput);
-
-            let mut keys = Elements::new(key_kind);
-            let mut values = Elements::new(value_kind);
-            for i in 0..size {
-                track!(compact_decode_element(reader, &mut keys), "i={}", i)?;
-                track!(compact_decode_element(reader, &mut values), "i={}", i)?;
-            }
-            track!(Map::from_keys_and_values(keys, values))
+        let size = track!(read_varint(reader))?;
+        track_assert!(size <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
+        if size == 0 {
+            // The compact protocol does not encode the key and value kinds of an empty map.
+            return Ok(Map::empty_of(DataKind::Binary, DataKind::Binary));
         }
+
+        let kinds = track_io!(reader.read_u8())?;
+        let key_kind = track!(compact_element_kind(kinds >> 4))?;
+        let value_kind = track!(compact_element_kind(kinds & 0b1111))?;
+
+        let mut keys = Elements::new(key_kind);
+        let mut values = Elements::new(value_kind);
+        for i in 0..size {
+            track!(compact_decode_element(reader, &mut keys), "i={}", i)?;
+            track!(compact_decode_element(reader, &mut values), "i={}", i)?;
+        }
+        track!(Map::from_keys_and_values(keys, values))
     }
 }
 impl CompactDecode for Set {
@@ -382,7 +379,7 @@ impl CompactDecode for Set {
             );
             size = varint_size as i32;
         }
-        let kind = track_assert_some!(DataKind::from_u8(kind), ErrorKind::InvalidInput);
+        let kind = track!(compact_element_kind(kind))?;
 
         let mut elements = Elements::new(kind);
         for i in 0..size {
@@ -406,7 +403,7 @@ impl CompactDecode for List {
             );
             size = varint_size as i32;
         }
-        let kind = track_assert_some!(DataKind::from_u8(kind), ErrorKind::InvalidInput);
+        let kind = track!(compact_element_kind(kind))?;
 
         let mut elements = Elements::new(kind);
         for i in 0..size {
@@ -423,6 +420,20 @@ impl CompactDecode for Uuid {
     }
 }
 
+/// Reads `size` bytes from `reader`.
+///
+/// The buffer grows as the bytes are read,
+/// so a corrupted size does not make a huge allocation before reaching EOF.
+fn read_bytes<R: Read>(reader: &mut R, size: u64) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    track_io!((&mut *reader).take(size).read_to_end(&mut buf))?;
+    if buf.len() as u64 != size {
+        let e = io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer");
+        return Err(track!(Error::from(e)));
+    }
+    Ok(buf)
+}
+
 // [NOTE]
 //
 // The [specification] says "We are using big-endian",
@@ -443,6 +454,26 @@ fn read_varint<R: Read>(reader: &mut R) -> Result<u64> {
     Ok(n)
 }
 
+pub(crate) fn compact_element_kind(kind: u8) -> Result<DataKind> {
+    Ok(match kind {
+        constants::COMPACT_FIELD_BOOLEAN_TRUE | constants::COMPACT_FIELD_BOOLEAN_FALSE => {
+            DataKind::Bool
+        }
+        constants::COMPACT_FIELD_I8 => DataKind::I8,
+        constants::COMPACT_FIELD_I16 => DataKind::I16,
+        constants::COMPACT_FIELD_I32 => DataKind::I32,
+        constants::COMPACT_FIELD_I64 => DataKind::I64,
+        constants::COMPACT_FIELD_DOUBLE => DataKind::Double,
+        constants::COMPACT_FIELD_BINARY => DataKind::Binary,
+        constants::COMPACT_FIELD_LIST => DataKind::List,
+        constants::COMPACT_FIELD_SET => DataKind::Set,
+        constants::COMPACT_FIELD_MAP => DataKind::Map,
+        constants::COMPACT_FIELD_STRUCT => DataKind::Struct,
+        constants::COMPACT_FIELD_UUID => DataKind::Uuid,
+        _ => track_panic!(ErrorKind::InvalidInput, "kind={}", kind),
+    })
+}
+
 fn compact_decode_element<R: Read>(reader: &mut R, elements: &mut Elements) -> Result<()> {
     match *elements {
         Elements::Bool(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
@@ -452,6 +483,7 @@ fn compact_decode_element<R: Read>(reader: &mut R, elements: &mut Elements) -> R
         Elements::I64(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
         Elements::Double(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
         Elements::Binary(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
+        Elements::String(_) => unreachable!("Decoded elements are made by `Elements::new`"),
         Elements::Struct(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
         Elements::Map(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
         Elements::Set(ref mut v) => v.push(track!(CompactDecode::compact_decode(reader))?),
@@ -521,4 +553,71 @@ mod tests {
 
         Message::compact_decode(&mut data).unwrap();
     }
+
+    #[test]
+    fn compact_map_transcoding_works() {
+        use crate::{BinaryEncode, CompactEncode};
+
+        // map<string, i32> {"a": 1}
+        let bytes = [1, 0x85, 1, b'a', 2];
+        let map = track_try_unwrap!(Map::compact_decode(&mut &bytes[..]));
+        assert_eq!(map.key_kind(), DataKind::Binary);
+        assert_eq!(map.value_kind(), DataKind::I32);
+
+        let mut buf = Vec::new();
+        track_try_unwrap!(map.compact_encode(&mut buf));
+        assert_eq!(buf, bytes);
+
+        buf.clear();
+        track_try_unwrap!(map.binary_encode(&mut buf));
+        assert_eq!(track_try_unwrap!(Map::binary_decode(&mut &buf[..])), map);
+
+        // Empty maps
+        let map = track_try_unwrap!(Map::compact_decode(&mut &[0][..]));
+        assert!(map.is_empty());
+
+        buf.clear();
+        track_try_unwrap!(map.binary_encode(&mut buf));
+        assert_eq!(buf, [11, 11, 0, 0, 0, 0]);
+
+        buf.clear();
+        let map = Map::empty_of(DataKind::I64, DataKind::Struct);
+        track_try_unwrap!(map.compact_encode(&mut buf));
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    fn compact_collection_element_kinds_work() {
+        use crate::CompactEncode;
+
+        // list<i64> [1], set<string> ["a"], list<bool> [true, false]
+        let list = List::from(vec![1i64]);
+        let mut buf = Vec::new();
+        track_try_unwrap!(list.compact_encode(&mut buf));
+        assert_eq!(buf, [0x16, 2]);
+
+        let set = Set::from(vec![b"a".to_vec()]);
+        buf.clear();
+        track_try_unwrap!(set.compact_encode(&mut buf));
+        assert_eq!(buf, [0x18, 1, b'a']);
+
+        let bools = [0x21, 1, 2];
+        let list = track_try_unwrap!(List::compact_decode(&mut &bools[..]));
+        assert_eq!(list, List::from(vec![true, false]));
+    }
+
+    #[test]
+    fn truncated_binaries_are_rejected() {
+        let bytes = [0x7F, 0xFF, 0xFF, 0xFF, b'a', b'b'];
+        let e = Vec::<u8>::binary_decode(&mut &bytes[..]).unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Other);
+
+        let bytes = [0xFF, 0xFF, 0xFF, 0xFF, 0x07, b'a', b'b'];
+        let e = Vec::<u8>::compact_decode(&mut &bytes[..]).unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Other);
+
+        let bytes = [2, b'a', b'b', b'c'];
+        let binary = track_try_unwrap!(Vec::<u8>::compact_decode(&mut &bytes[..]));
+        assert_eq!(binary, b"ab");
+    }
 }
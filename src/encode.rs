@@ -1,3 +1,4 @@
+use crate::canonical::{Canonical, CanonicalForm};
 use crate::constants;
 use crate::data::{Data, DataKind, DataRef, List, Map, Set, Struct, Uuid};
 use crate::message::Message;
@@ -13,6 +14,17 @@ use std::io::Write;
 pub trait BinaryEncode {
     /// Encodes an object.
     fn binary_encode<W: Write>(&self, writer: &mut W) -> Result<()>;
+
+    /// Encodes the canonical form of an object.
+    ///
+    /// See the [`canonical`](crate::canonical) module for the details of the canonical form.
+    fn binary_encode_canonical<W: Write>(&self, writer: &mut W) -> Result<()>
+    where
+        Self: CanonicalForm + Clone,
+    {
+        let canonical = track!(Canonical::new(self.clone()))?;
+        track!(canonical.binary_encode(writer))
+    }
 }
 impl BinaryEncode for bool {
     fn binary_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
@@ -44,7 +56,7 @@ impl BinaryEncode for f64 {
         track_io!(writer.write_f64::<BigEndian>(*self))
     }
 }
-impl<'a> BinaryEncode for &'a [u8] {
+impl BinaryEncode for &[u8] {
     fn binary_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
         track_assert!(self.len() <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
         track!((self.len() as i32).binary_encode(writer))?;
@@ -83,6 +95,7 @@ impl<'a> BinaryEncode for DataRef<'a> {
             DataRef::I64(v) => track!(v.binary_encode(writer)),
             DataRef::Double(v) => track!(v.binary_encode(writer)),
             DataRef::Binary(v) => track!(v.binary_encode(writer)),
+            DataRef::String(v) => track!(v.as_bytes().binary_encode(writer)),
             DataRef::Struct(v) => track!(v.binary_encode(writer)),
             DataRef::Map(v) => track!(v.binary_encode(writer)),
             DataRef::Set(v) => track!(v.binary_encode(writer)),
@@ -106,11 +119,8 @@ impl BinaryEncode for Map {
     fn binary_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
         track_assert!(self.len() <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
 
-        let key_kind = track_assert_some!(self.key_kind(), ErrorKind::InvalidInput);
-        track_io!(writer.write_u8(key_kind as u8))?;
-
-        let value_kind = track_assert_some!(self.value_kind(), ErrorKind::InvalidInput);
-        track_io!(writer.write_u8(value_kind as u8))?;
+        track_io!(writer.write_u8(self.key_kind() as u8))?;
+        track_io!(writer.write_u8(self.value_kind() as u8))?;
 
         track!((self.len() as i32).binary_encode(writer))?;
         for (k, v) in self.iter() {
@@ -150,6 +160,17 @@ impl BinaryEncode for List {
 pub trait CompactEncode {
     /// Encodes an object.
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()>;
+
+    /// Encodes the canonical form of an object.
+    ///
+    /// See the [`canonical`](crate::canonical) module for the details of the canonical form.
+    fn compact_encode_canonical<W: Write>(&self, writer: &mut W) -> Result<()>
+    where
+        Self: CanonicalForm + Clone,
+    {
+        let canonical = track!(Canonical::new(self.clone()))?;
+        track!(canonical.compact_encode(writer))
+    }
 }
 impl CompactEncode for bool {
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
@@ -188,7 +209,7 @@ impl CompactEncode for f64 {
         track_io!(writer.write_f64::<LittleEndian>(*self))
     }
 }
-impl<'a> CompactEncode for &'a [u8] {
+impl CompactEncode for &[u8] {
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
         track_assert!(self.len() <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
         track!(write_varint(writer, self.len() as u64))?;
@@ -228,6 +249,7 @@ impl<'a> CompactEncode for DataRef<'a> {
             DataRef::I64(v) => track!(v.compact_encode(writer)),
             DataRef::Double(v) => track!(v.compact_encode(writer)),
             DataRef::Binary(v) => track!(v.compact_encode(writer)),
+            DataRef::String(v) => track!(v.as_bytes().compact_encode(writer)),
             DataRef::Struct(v) => track!(v.compact_encode(writer)),
             DataRef::Map(v) => track!(v.compact_encode(writer)),
             DataRef::Set(v) => track!(v.compact_encode(writer)),
@@ -253,7 +275,7 @@ impl CompactEncode for Struct {
                 Data::I32(_) => constants::COMPACT_FIELD_I32,
                 Data::I64(_) => constants::COMPACT_FIELD_I64,
                 Data::Double(_) => constants::COMPACT_FIELD_DOUBLE,
-                Data::Binary(_) => constants::COMPACT_FIELD_BINARY,
+                Data::Binary(_) | Data::String(_) => constants::COMPACT_FIELD_BINARY,
                 Data::Struct(_) => constants::COMPACT_FIELD_STRUCT,
                 Data::Map(_) => constants::COMPACT_FIELD_MAP,
                 Data::Set(_) => constants::COMPACT_FIELD_SET,
@@ -275,12 +297,15 @@ impl CompactEncode for Struct {
 }
 impl CompactEncode for Map {
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
-        track_assert!(self.len() <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
-        track!((self.len() as i32).compact_encode(writer))?;
-        for (i, (k, v)) in self.iter().enumerate() {
-            if i == 0 {
-                track_io!(writer.write_u8(((k.kind() as u8) << 4) | v.kind() as u8))?;
-            }
+        let len = self.len();
+        track_assert!(len <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
+        track!(write_varint(writer, len as u64))?;
+        if len != 0 {
+            let key_kind = compact_element_kind(self.key_kind());
+            let value_kind = compact_element_kind(self.value_kind());
+            track_io!(writer.write_u8((key_kind << 4) | value_kind))?;
+        }
+        for (k, v) in self.iter() {
             track!(k.compact_encode(writer))?;
             track!(v.compact_encode(writer))?;
         }
@@ -291,10 +316,11 @@ impl CompactEncode for Set {
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
         let len = self.len();
         track_assert!(len <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
+        let kind = compact_element_kind(self.kind());
         if len < 15 {
-            track_io!(writer.write_u8((len << 4) as u8 | self.kind() as u8))?;
+            track_io!(writer.write_u8((len << 4) as u8 | kind))?;
         } else {
-            track_io!(writer.write_u8(0b1111_0000 | self.kind() as u8))?;
+            track_io!(writer.write_u8(0b1111_0000 | kind))?;
             track!(write_varint(writer, len as u64))?;
         }
         for e in self.iter() {
@@ -307,10 +333,11 @@ impl CompactEncode for List {
     fn compact_encode<W: Write>(&self, writer: &mut W) -> Result<()> {
         let len = self.len();
         track_assert!(len <= 0x7FFF_FFFF, ErrorKind::InvalidInput);
+        let kind = compact_element_kind(self.kind());
         if len < 15 {
-            track_io!(writer.write_u8((len << 4) as u8 | self.kind() as u8))?;
+            track_io!(writer.write_u8((len << 4) as u8 | kind))?;
         } else {
-            track_io!(writer.write_u8(0b1111_0000 | self.kind() as u8))?;
+            track_io!(writer.write_u8(0b1111_0000 | kind))?;
             track!(write_varint(writer, len as u64))?;
         }
         for e in self.iter() {
@@ -320,6 +347,23 @@ impl CompactEncode for List {
     }
 }
 
+fn compact_element_kind(kind: DataKind) -> u8 {
+    match kind {
+        DataKind::Bool => constants::COMPACT_FIELD_BOOLEAN_TRUE,
+        DataKind::I8 => constants::COMPACT_FIELD_I8,
+        DataKind::I16 => constants::COMPACT_FIELD_I16,
+        DataKind::I32 => constants::COMPACT_FIELD_I32,
+        DataKind::I64 => constants::COMPACT_FIELD_I64,
+        DataKind::Double => constants::COMPACT_FIELD_DOUBLE,
+        DataKind::Binary => constants::COMPACT_FIELD_BINARY,
+        DataKind::Struct => constants::COMPACT_FIELD_STRUCT,
+        DataKind::Map => constants::COMPACT_FIELD_MAP,
+        DataKind::Set => constants::COMPACT_FIELD_SET,
+        DataKind::List => constants::COMPACT_FIELD_LIST,
+        DataKind::Uuid => constants::COMPACT_FIELD_UUID,
+    }
+}
+
 // [NOTE]
 //
 // The [specification] says "We are using big-endian",
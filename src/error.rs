@@ -1,3 +1,4 @@
+use crate::data::DataKind;
 use trackable::error::TrackableError;
 use trackable::error::{ErrorKind as TrackableErrorKind, ErrorKindExt};
 
@@ -14,13 +15,44 @@ impl From<std::string::FromUtf8Error> for Error {
         ErrorKind::InvalidInput.cause(f).into()
     }
 }
+impl From<std::str::Utf8Error> for Error {
+    fn from(f: std::str::Utf8Error) -> Self {
+        ErrorKind::InvalidInput.cause(f).into()
+    }
+}
+
+impl Error {
+    /// Returns the first line of the description of this error (i.e., the kind and the cause).
+    ///
+    /// Unlike `to_string`, the result does not contain the tracking history
+    /// (which includes source file paths), so it can be sent to remote peers.
+    pub(crate) fn reason(&self) -> String {
+        self.to_string()
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_owned()
+    }
+}
 
 /// The list of the possible error kinds
+///
+/// New kinds may be added in future versions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// Input data is invalid.
     InvalidInput,
 
+    /// The kind of data is different from the expected one.
+    KindMismatch {
+        /// The expected kind.
+        expected: DataKind,
+
+        /// The actual kind.
+        actual: DataKind,
+    },
+
     /// Other errors (e.g., I/O error).
     Other,
 }
@@ -0,0 +1,729 @@
+use crate::data::DataKind;
+use crate::idl::{Definition, Document, Enum, Field, FieldType, Function, Requiredness, Service};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The result of the compatibility check between two versions of an IDL document.
+///
+/// The compatibility is defined in terms of the wire format:
+///
+/// - A change is **backward compatible** if the new version can read the data
+///   (e.g., structs, method calls and replies) written by the old version.
+/// - A change is **forward compatible** if the old version can read the data
+///   written by the new version.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::idl::{ChangeKind, CompatibilityReport, Document};
+///
+/// let old = Document::parse("struct Span { 1: i64 trace_id, 2: optional string name }").unwrap();
+/// let new = Document::parse("struct Span { 1: i32 trace_id, 2: required string name }").unwrap();
+///
+/// let report = CompatibilityReport::check(&old, &new);
+/// assert!(!report.is_backward_compatible());
+/// assert!(!report.is_forward_compatible());
+///
+/// assert_eq!(report.changes.len(), 2);
+/// assert_eq!(report.changes[0].path, "Span.trace_id");
+/// assert_eq!(report.changes[0].kind, ChangeKind::FieldTypeChanged);
+/// assert_eq!(report.changes[1].path, "Span.name");
+/// assert_eq!(report.changes[1].kind, ChangeKind::RequirednessChanged);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CompatibilityReport {
+    /// The detected changes (including compatible ones).
+    pub changes: Vec<Change>,
+}
+impl CompatibilityReport {
+    /// Compares the `old` and `new` versions of an IDL document.
+    ///
+    /// The user defined types are resolved within each document.
+    /// The types defined in included documents are compared by their names.
+    pub fn check(old: &Document, new: &Document) -> Self {
+        let mut checker = Checker {
+            old,
+            new,
+            changes: Vec::new(),
+        };
+        checker.check();
+        CompatibilityReport {
+            changes: checker.changes,
+        }
+    }
+
+    /// Returns `true` if all the changes are backward compatible.
+    pub fn is_backward_compatible(&self) -> bool {
+        self.changes.iter().all(|c| c.backward_compatible)
+    }
+
+    /// Returns `true` if all the changes are forward compatible.
+    pub fn is_forward_compatible(&self) -> bool {
+        self.changes.iter().all(|c| c.forward_compatible)
+    }
+
+    /// Returns an iterator over the changes which break backward or forward compatibility.
+    pub fn incompatible_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|c| !c.is_compatible())
+    }
+}
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// A change between two versions of an IDL document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Change {
+    /// The location of the change (e.g., `Work.num1`, `Calculator.add.args.num1`).
+    pub path: String,
+
+    /// The kind of the change.
+    pub kind: ChangeKind,
+
+    /// Whether the new version can read the data written by the old version.
+    pub backward_compatible: bool,
+
+    /// Whether the old version can read the data written by the new version.
+    pub forward_compatible: bool,
+
+    /// The human readable description of the change.
+    pub description: String,
+}
+impl Change {
+    /// Returns `true` if this change is both backward and forward compatible.
+    pub fn is_compatible(&self) -> bool {
+        self.backward_compatible && self.forward_compatible
+    }
+}
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.description)?;
+        match (self.backward_compatible, self.forward_compatible) {
+            (true, true) => Ok(()),
+            (false, true) => write!(f, " (breaks backward compatibility)"),
+            (true, false) => write!(f, " (breaks forward compatibility)"),
+            (false, false) => write!(f, " (breaks backward and forward compatibility)"),
+        }
+    }
+}
+
+/// The kind of a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[allow(missing_docs)]
+pub enum ChangeKind {
+    DefinitionAdded,
+    DefinitionRemoved,
+    DefinitionKindChanged,
+    FieldAdded,
+    FieldRemoved,
+    FieldRenamed,
+    FieldIdChanged,
+    FieldTypeChanged,
+    RequirednessChanged,
+    EnumValueAdded,
+    EnumValueRemoved,
+    EnumValueChanged,
+    EnumValueRenamed,
+    MethodAdded,
+    MethodRemoved,
+    ReturnTypeChanged,
+    OnewayChanged,
+    ExceptionAdded,
+    ExceptionRemoved,
+}
+
+/// The wire level representation of a field type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Shape {
+    Kind(DataKind),
+    List(Box<Shape>),
+    Set(Box<Shape>),
+    Map(Box<Shape>, Box<Shape>),
+    Unresolved(String),
+}
+impl Shape {
+    fn new(document: &Document, field_type: &FieldType) -> Self {
+        Self::resolve(document, field_type, 0)
+    }
+
+    fn resolve(document: &Document, field_type: &FieldType, depth: usize) -> Self {
+        let kind = match *field_type {
+            FieldType::Bool => DataKind::Bool,
+            FieldType::I8 => DataKind::I8,
+            FieldType::I16 => DataKind::I16,
+            FieldType::I32 => DataKind::I32,
+            FieldType::I64 => DataKind::I64,
+            FieldType::Double => DataKind::Double,
+            FieldType::String | FieldType::Binary => DataKind::Binary,
+            FieldType::Uuid => DataKind::Uuid,
+            FieldType::List(ref t) => {
+                return Shape::List(Box::new(Self::resolve(document, t, depth)));
+            }
+            FieldType::Set(ref t) => {
+                return Shape::Set(Box::new(Self::resolve(document, t, depth)));
+            }
+            FieldType::Map(ref k, ref v) => {
+                return Shape::Map(
+                    Box::new(Self::resolve(document, k, depth)),
+                    Box::new(Self::resolve(document, v, depth)),
+                );
+            }
+            FieldType::Named(ref name) => match document.definition(name) {
+                Some(Definition::Enum(_)) => DataKind::I32,
+                Some(Definition::Struct(_) | Definition::Union(_) | Definition::Exception(_)) => {
+                    DataKind::Struct
+                }
+                Some(Definition::Typedef(t)) if depth < 64 => {
+                    return Self::resolve(document, &t.field_type, depth + 1);
+                }
+                _ => return Shape::Unresolved(name.clone()),
+            },
+        };
+        Shape::Kind(kind)
+    }
+}
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Shape::Kind(kind) => write!(f, "{:?}", kind),
+            Shape::List(ref t) => write!(f, "List<{}>", t),
+            Shape::Set(ref t) => write!(f, "Set<{}>", t),
+            Shape::Map(ref k, ref v) => write!(f, "Map<{}, {}>", k, v),
+            Shape::Unresolved(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Checker<'a> {
+    old: &'a Document,
+    new: &'a Document,
+    changes: Vec<Change>,
+}
+impl<'a> Checker<'a> {
+    fn check(&mut self) {
+        for old in &self.old.definitions {
+            let new = self.new.definition(old.name());
+            if let Some(new) = new.filter(|_| !matches!(old, Definition::Const(_))) {
+                let (old_kind, new_kind) = (definition_kind(old), definition_kind(new));
+                if old_kind != new_kind {
+                    self.push(
+                        old.name().to_owned(),
+                        ChangeKind::DefinitionKindChanged,
+                        (false, false),
+                        format!("Definition is changed from {} to {}", old_kind, new_kind),
+                    );
+                }
+            }
+            match (old, new) {
+                (Definition::Const(_), _) => {}
+                (Definition::Typedef(_), _) => {}
+                (Definition::Enum(old), Some(Definition::Enum(new))) => {
+                    self.check_enum(old, new);
+                }
+                (
+                    Definition::Struct(old) | Definition::Union(old) | Definition::Exception(old),
+                    Some(
+                        Definition::Struct(new)
+                        | Definition::Union(new)
+                        | Definition::Exception(new),
+                    ),
+                ) => {
+                    self.check_fields(&old.name, &old.fields, &new.fields);
+                }
+                (Definition::Service(old), Some(Definition::Service(new))) => {
+                    self.check_service(old, new);
+                }
+                (Definition::Service(old), None) => {
+                    self.push(
+                        old.name.clone(),
+                        ChangeKind::DefinitionRemoved,
+                        (false, true),
+                        "Service is removed".to_owned(),
+                    );
+                }
+                (_, None) => {
+                    // The wire compatibility is affected only where the definition is referenced.
+                    self.push(
+                        old.name().to_owned(),
+                        ChangeKind::DefinitionRemoved,
+                        (true, true),
+                        "Definition is removed".to_owned(),
+                    );
+                }
+                (_, Some(_)) => {
+                    // The kinds of the definitions differ, which has been reported above.
+                }
+            }
+        }
+        for new in &self.new.definitions {
+            if matches!(new, Definition::Const(_) | Definition::Typedef(_)) {
+                continue;
+            }
+            if self.old.definition(new.name()).is_none() {
+                self.push(
+                    new.name().to_owned(),
+                    ChangeKind::DefinitionAdded,
+                    (true, true),
+                    "Definition is added".to_owned(),
+                );
+            }
+        }
+    }
+
+    fn check_enum(&mut self, old: &Enum, new: &Enum) {
+        for o in &old.values {
+            let path = format!("{}.{}", old.name, o.name);
+            if let Some(n) = new.values.iter().find(|n| n.name == o.name) {
+                if n.value != o.value {
+                    self.push(
+                        path,
+                        ChangeKind::EnumValueChanged,
+                        (false, false),
+                        format!("Enum value is changed from {} to {}", o.value, n.value),
+                    );
+                }
+            } else if !new.values.iter().any(|n| n.value == o.value) {
+                self.push(
+                    path,
+                    ChangeKind::EnumValueRemoved,
+                    (false, true),
+                    format!("Enum value {} is removed", o.value),
+                );
+            }
+        }
+        for n in &new.values {
+            let path = format!("{}.{}", new.name, n.name);
+            if old.values.iter().any(|o| o.name == n.name) {
+                continue;
+            }
+            if let Some(o) = old.values.iter().find(|o| o.value == n.value) {
+                self.push(
+                    path,
+                    ChangeKind::EnumValueRenamed,
+                    (true, true),
+                    format!("Enum value {} is renamed from {:?}", n.value, o.name),
+                );
+            } else {
+                self.push(
+                    path,
+                    ChangeKind::EnumValueAdded,
+                    (true, false),
+                    format!("Enum value {} is added", n.value),
+                );
+            }
+        }
+    }
+
+    fn check_fields(&mut self, parent: &str, old: &[Field], new: &[Field]) {
+        for o in old {
+            let path = format!("{}.{}", parent, o.name);
+            if let Some(n) = new.iter().find(|n| n.id == o.id) {
+                if n.name != o.name {
+                    self.push(
+                        format!("{}.{}", parent, n.name),
+                        ChangeKind::FieldRenamed,
+                        (true, true),
+                        format!("Field {} is renamed from {:?}", n.id, o.name),
+                    );
+                }
+                self.check_field(&path, o, n);
+            } else if let Some(n) = new.iter().find(|n| n.name == o.name) {
+                self.push(
+                    path,
+                    ChangeKind::FieldIdChanged,
+                    (false, false),
+                    format!("Field id is changed from {} to {}", o.id, n.id),
+                );
+            } else {
+                let required = o.requiredness == Requiredness::Required;
+                self.push(
+                    path,
+                    ChangeKind::FieldRemoved,
+                    (true, !required),
+                    format!(
+                        "{} field {} is removed",
+                        requiredness_name(o.requiredness),
+                        o.id
+                    ),
+                );
+            }
+        }
+        for n in new {
+            if old.iter().any(|o| o.id == n.id || o.name == n.name) {
+                continue;
+            }
+            let required = n.requiredness == Requiredness::Required;
+            self.push(
+                format!("{}.{}", parent, n.name),
+                ChangeKind::FieldAdded,
+                (!required, true),
+                format!(
+                    "{} field {} is added",
+                    requiredness_name(n.requiredness),
+                    n.id
+                ),
+            );
+        }
+    }
+
+    fn check_field(&mut self, path: &str, old: &Field, new: &Field) {
+        let old_shape = Shape::new(self.old, &old.field_type);
+        let new_shape = Shape::new(self.new, &new.field_type);
+        if old_shape != new_shape {
+            self.push(
+                path.to_owned(),
+                ChangeKind::FieldTypeChanged,
+                (false, false),
+                format!("Field type is changed from {} to {}", old_shape, new_shape),
+            );
+        }
+
+        let old_required = old.requiredness == Requiredness::Required;
+        let new_required = new.requiredness == Requiredness::Required;
+        if old_required != new_required {
+            self.push(
+                path.to_owned(),
+                ChangeKind::RequirednessChanged,
+                (!new_required, !old_required),
+                format!(
+                    "Field requiredness is changed from {} to {}",
+                    requiredness_name(old.requiredness).to_lowercase(),
+                    requiredness_name(new.requiredness).to_lowercase()
+                ),
+            );
+        }
+    }
+
+    fn check_service(&mut self, old: &Service, new: &Service) {
+        let old_functions = functions(self.old, old);
+        let new_functions = functions(self.new, new);
+        for (name, o) in &old_functions {
+            let path = format!("{}.{}", old.name, name);
+            if let Some(n) = new_functions.get(name) {
+                self.check_function(&path, o, n);
+            } else {
+                self.push(
+                    path,
+                    ChangeKind::MethodRemoved,
+                    (false, true),
+                    "Method is removed".to_owned(),
+                );
+            }
+        }
+        for name in new_functions.keys() {
+            if !old_functions.contains_key(name) {
+                self.push(
+                    format!("{}.{}", new.name, name),
+                    ChangeKind::MethodAdded,
+                    (true, false),
+                    "Method is added".to_owned(),
+                );
+            }
+        }
+    }
+
+    fn check_function(&mut self, path: &str, old: &Function, new: &Function) {
+        if old.oneway != new.oneway {
+            self.push(
+                path.to_owned(),
+                ChangeKind::OnewayChanged,
+                (false, false),
+                if new.oneway {
+                    "Method is changed to oneway".to_owned()
+                } else {
+                    "Method is changed to non-oneway".to_owned()
+                },
+            );
+        }
+
+        self.check_fields(&format!("{}.args", path), &old.params, &new.params);
+
+        let old_shape = old.return_type.as_ref().map(|t| Shape::new(self.old, t));
+        let new_shape = new.return_type.as_ref().map(|t| Shape::new(self.new, t));
+        if old_shape != new_shape {
+            let name =
+                |shape: Option<Shape>| shape.map_or_else(|| "void".to_owned(), |s| s.to_string());
+            self.push(
+                format!("{}.return", path),
+                ChangeKind::ReturnTypeChanged,
+                (false, false),
+                format!(
+                    "Return type is changed from {} to {}",
+                    name(old_shape),
+                    name(new_shape)
+                ),
+            );
+        }
+
+        let throws = format!("{}.throws", path);
+        for o in &old.throws {
+            if let Some(n) = new.throws.iter().find(|n| n.id == o.id) {
+                self.check_field(&format!("{}.{}", throws, o.name), o, n);
+            } else {
+                self.push(
+                    format!("{}.{}", throws, o.name),
+                    ChangeKind::ExceptionRemoved,
+                    (false, true),
+                    format!("Exception {} is removed", o.id),
+                );
+            }
+        }
+        for n in &new.throws {
+            if !old.throws.iter().any(|o| o.id == n.id) {
+                self.push(
+                    format!("{}.{}", throws, n.name),
+                    ChangeKind::ExceptionAdded,
+                    (true, false),
+                    format!("Exception {} is added", n.id),
+                );
+            }
+        }
+    }
+
+    fn push(
+        &mut self,
+        path: String,
+        kind: ChangeKind,
+        (backward_compatible, forward_compatible): (bool, bool),
+        description: String,
+    ) {
+        self.changes.push(Change {
+            path,
+            kind,
+            backward_compatible,
+            forward_compatible,
+            description,
+        });
+    }
+}
+
+/// Returns the functions of `service` including the inherited ones.
+fn functions<'a>(document: &'a Document, service: &'a Service) -> BTreeMap<&'a str, &'a Function> {
+    let mut functions = BTreeMap::new();
+    let mut service = Some(service);
+    let mut depth = 0;
+    while let Some(s) = service.take() {
+        for f in &s.functions {
+            functions.entry(f.name.as_str()).or_insert(f);
+        }
+        depth += 1;
+        if depth < 64 {
+            if let Some(Definition::Service(parent)) = s
+                .extends
+                .as_ref()
+                .and_then(|name| document.definition(name))
+            {
+                service = Some(parent);
+            }
+        }
+    }
+    functions
+}
+
+fn definition_kind(definition: &Definition) -> &'static str {
+    match definition {
+        Definition::Const(_) => "Const",
+        Definition::Typedef(_) => "Typedef",
+        Definition::Enum(_) => "Enum",
+        Definition::Struct(_) => "Struct",
+        Definition::Union(_) => "Union",
+        Definition::Exception(_) => "Exception",
+        Definition::Service(_) => "Service",
+    }
+}
+
+fn requiredness_name(requiredness: Requiredness) -> &'static str {
+    match requiredness {
+        Requiredness::Required => "Required",
+        Requiredness::Optional => "Optional",
+        Requiredness::Default => "Default",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(old: &str, new: &str) -> CompatibilityReport {
+        let old = track_try_unwrap!(Document::parse(old));
+        let new = track_try_unwrap!(Document::parse(new));
+        CompatibilityReport::check(&old, &new)
+    }
+
+    fn kinds(report: &CompatibilityReport) -> Vec<(&str, ChangeKind, bool, bool)> {
+        report
+            .changes
+            .iter()
+            .map(|c| {
+                (
+                    c.path.as_str(),
+                    c.kind,
+                    c.backward_compatible,
+                    c.forward_compatible,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_documents_are_compatible() {
+        let idl = r#"
+            typedef i64 Id
+            enum Color { RED, GREEN }
+            struct Foo { 1: required Id id, 2: optional list<Color> colors }
+            service Bar { Foo get(1: Id id) throws (1: Foo e) }
+        "#;
+        let report = check(idl, idl);
+        assert!(report.changes.is_empty());
+        assert!(report.is_backward_compatible());
+        assert!(report.is_forward_compatible());
+    }
+
+    #[test]
+    fn struct_changes_work() {
+        let report = check(
+            r#"
+            typedef i64 Id
+            struct Foo {
+              1: required Id id,
+              2: optional i32 a,
+              3: string b,
+              4: required i32 c,
+              5: list<i32> d,
+            }
+            "#,
+            r#"
+            struct Foo {
+              1: required i64 id,
+              2: required i32 a,
+              13: string b,
+              5: list<i64> renamed_d,
+              6: optional i32 e,
+              7: required i32 f,
+            }
+            "#,
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                ("Foo.a", ChangeKind::RequirednessChanged, false, true),
+                ("Foo.b", ChangeKind::FieldIdChanged, false, false),
+                ("Foo.c", ChangeKind::FieldRemoved, true, false),
+                ("Foo.renamed_d", ChangeKind::FieldRenamed, true, true),
+                ("Foo.d", ChangeKind::FieldTypeChanged, false, false),
+                ("Foo.e", ChangeKind::FieldAdded, true, true),
+                ("Foo.f", ChangeKind::FieldAdded, false, true),
+            ]
+        );
+        assert_eq!(
+            report.changes[4].to_string(),
+            "Foo.d: Field type is changed from List<I32> to List<I64> \
+             (breaks backward and forward compatibility)"
+        );
+    }
+
+    #[test]
+    fn enum_changes_work() {
+        let report = check(
+            "enum Color { RED = 1, GREEN = 2, BLUE = 3 }",
+            "enum Color { RED = 1, GREEN = 5, AZURE = 3, BLACK = 4 }",
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                ("Color.GREEN", ChangeKind::EnumValueChanged, false, false),
+                ("Color.AZURE", ChangeKind::EnumValueRenamed, true, true),
+                ("Color.BLACK", ChangeKind::EnumValueAdded, true, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn definition_kind_changes_work() {
+        let report = check(
+            "struct Foo { 1: i32 a } union Bar { 1: i32 a } enum Baz { A = 1 }",
+            "union Foo { 1: i32 a } exception Bar { 1: i32 a } struct Baz { 1: i32 a }",
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                ("Foo", ChangeKind::DefinitionKindChanged, false, false),
+                ("Bar", ChangeKind::DefinitionKindChanged, false, false),
+                ("Baz", ChangeKind::DefinitionKindChanged, false, false),
+            ]
+        );
+        assert_eq!(
+            report.changes[0].to_string(),
+            "Foo: Definition is changed from Struct to Union \
+             (breaks backward and forward compatibility)"
+        );
+    }
+
+    #[test]
+    fn service_changes_work() {
+        let report = check(
+            r#"
+            exception Oops { 1: string message }
+            service Base { void ping() }
+            service Foo extends Base {
+              i32 add(1: i32 a, 2: i32 b) throws (1: Oops oops),
+              void zip(),
+              oneway void log(1: string message),
+            }
+            "#,
+            r#"
+            exception Oops { 1: string message }
+            service Foo {
+              void ping(),
+              i64 add(1: i32 a, 2: required i32 b) throws (2: Oops other),
+              oneway void zap(),
+              void log(1: string message),
+            }
+            "#,
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                ("Base", ChangeKind::DefinitionRemoved, false, true),
+                (
+                    "Foo.add.args.b",
+                    ChangeKind::RequirednessChanged,
+                    false,
+                    true
+                ),
+                (
+                    "Foo.add.return",
+                    ChangeKind::ReturnTypeChanged,
+                    false,
+                    false
+                ),
+                (
+                    "Foo.add.throws.oops",
+                    ChangeKind::ExceptionRemoved,
+                    false,
+                    true
+                ),
+                (
+                    "Foo.add.throws.other",
+                    ChangeKind::ExceptionAdded,
+                    true,
+                    false
+                ),
+                ("Foo.log", ChangeKind::OnewayChanged, false, false),
+                ("Foo.zip", ChangeKind::MethodRemoved, false, true),
+                ("Foo.zap", ChangeKind::MethodAdded, true, false),
+            ]
+        );
+        assert!(!report.is_backward_compatible());
+        assert_eq!(report.incompatible_changes().count(), 8);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::idl::parser::Parser;
+use crate::Result;
+use std::fs;
+use std::path::Path;
+
+/// Thrift IDL document (i.e., the contents of a `.thrift` file).
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::idl::{Definition, Document};
+///
+/// let document = Document::parse(r#"
+///     namespace rs tutorial
+///
+///     struct Work {
+///       1: i32 num1 = 0,
+///       2: optional string comment,
+///     }
+/// "#).unwrap();
+/// assert_eq!(document.namespace("rs"), Some("tutorial"));
+///
+/// let Definition::Struct(work) = &document.definitions[0] else { panic!() };
+/// assert_eq!(work.name, "Work");
+/// assert_eq!(work.fields.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Document {
+    /// The paths of the included documents.
+    pub includes: Vec<String>,
+
+    /// The namespace declarations.
+    pub namespaces: Vec<Namespace>,
+
+    /// The definitions (types, constants and services) in this document.
+    pub definitions: Vec<Definition>,
+}
+impl Document {
+    /// Parses a Thrift IDL text.
+    ///
+    /// # Errors
+    ///
+    /// If `text` is malformed, this function will return an error which kind is `ErrorKind::InvalidInput`.
+    /// The error message contains the position (line and column) at which the parsing failed.
+    pub fn parse(text: &str) -> Result<Self> {
+        track!(Parser::new(text).parse_document())
+    }
+
+    /// Reads and parses the Thrift IDL file specified by `path`.
+    ///
+    /// Note that the included files are not loaded by this function.
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = track_io!(fs::read_to_string(path))?;
+        track!(Self::parse(&text), "path={:?}", path)
+    }
+
+    /// Returns the namespace declared for `scope`.
+    ///
+    /// If there is no such declaration, the namespace for the wildcard scope `*` is returned if exists.
+    pub fn namespace(&self, scope: &str) -> Option<&str> {
+        self.namespaces
+            .iter()
+            .find(|n| n.scope == scope)
+            .or_else(|| self.namespaces.iter().find(|n| n.scope == "*"))
+            .map(|n| n.name.as_str())
+    }
+
+    /// Returns the definition which has the name `name`.
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions.iter().find(|d| d.name() == name)
+    }
+}
+
+/// Namespace declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Namespace {
+    /// The target language of this namespace (e.g., `rs`, `java` or `*`).
+    pub scope: String,
+
+    /// The namespace identifier.
+    pub name: String,
+}
+
+/// Definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[allow(missing_docs)]
+pub enum Definition {
+    Const(Const),
+    Typedef(Typedef),
+    Enum(Enum),
+    Struct(Struct),
+    Union(Struct),
+    Exception(Struct),
+    Service(Service),
+}
+impl Definition {
+    /// Returns the name of this definition.
+    pub fn name(&self) -> &str {
+        match *self {
+            Definition::Const(ref d) => &d.name,
+            Definition::Typedef(ref d) => &d.name,
+            Definition::Enum(ref d) => &d.name,
+            Definition::Struct(ref d) => &d.name,
+            Definition::Union(ref d) => &d.name,
+            Definition::Exception(ref d) => &d.name,
+            Definition::Service(ref d) => &d.name,
+        }
+    }
+}
+
+/// Constant definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Const {
+    /// The name of this constant.
+    pub name: String,
+
+    /// The type of this constant.
+    pub field_type: FieldType,
+
+    /// The value of this constant.
+    pub value: ConstValue,
+}
+
+/// Type alias definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Typedef {
+    /// The name of the alias.
+    pub name: String,
+
+    /// The aliased type.
+    pub field_type: FieldType,
+}
+
+/// Enum definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Enum {
+    /// The name of this enum.
+    pub name: String,
+
+    /// The values of this enum.
+    pub values: Vec<EnumValue>,
+}
+
+/// A value of an enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EnumValue {
+    /// The name of this value.
+    pub name: String,
+
+    /// The integer representation of this value.
+    ///
+    /// If the value is omitted in the IDL, the previous value plus one (or zero for the first value) is set.
+    pub value: i32,
+}
+
+/// Struct, union or exception definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Struct {
+    /// The name of this struct.
+    pub name: String,
+
+    /// The fields of this struct.
+    pub fields: Vec<Field>,
+}
+
+/// A field of a struct, or a parameter of a function.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Field {
+    /// The identifier of this field.
+    ///
+    /// If the identifier is omitted in the IDL, a negative value is assigned automatically
+    /// (the same manner as the Apache Thrift compiler).
+    pub id: i16,
+
+    /// The name of this field.
+    pub name: String,
+
+    /// The type of this field.
+    pub field_type: FieldType,
+
+    /// The requiredness of this field.
+    pub requiredness: Requiredness,
+
+    /// The default value of this field.
+    pub default: Option<ConstValue>,
+}
+
+/// The requiredness of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Requiredness {
+    /// The field is declared as `required`.
+    Required,
+
+    /// The field is declared as `optional`.
+    Optional,
+
+    /// The field has no requiredness qualifier.
+    Default,
+}
+
+/// Service definition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Service {
+    /// The name of this service.
+    pub name: String,
+
+    /// The name of the parent service.
+    pub extends: Option<String>,
+
+    /// The functions of this service.
+    pub functions: Vec<Function>,
+}
+
+/// A function of a service.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Function {
+    /// The name of this function.
+    pub name: String,
+
+    /// Whether this function is declared as `oneway`.
+    pub oneway: bool,
+
+    /// The return type of this function (`None` means `void`).
+    pub return_type: Option<FieldType>,
+
+    /// The parameters of this function.
+    pub params: Vec<Field>,
+
+    /// The exceptions which this function may throw.
+    pub throws: Vec<Field>,
+}
+
+/// Field type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[allow(missing_docs)]
+pub enum FieldType {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    Double,
+    String,
+    Binary,
+    Uuid,
+    List(Box<FieldType>),
+    Set(Box<FieldType>),
+    Map(Box<FieldType>, Box<FieldType>),
+
+    /// User defined type (struct, union, exception, enum or typedef).
+    ///
+    /// The name may be prefixed by the name of an included document (e.g., `shared.SharedStruct`).
+    Named(String),
+}
+
+/// Constant value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[allow(missing_docs)]
+pub enum ConstValue {
+    Int(i64),
+    Double(f64),
+    String(String),
+
+    /// Reference to a constant or an enum value (e.g., `Operation.ADD`).
+    Identifier(String),
+    List(Vec<ConstValue>),
+    Map(Vec<(ConstValue, ConstValue)>),
+}
@@ -0,0 +1,12 @@
+//! Thrift [interface definition language][idl] (IDL).
+//!
+//! [idl]: https://thrift.apache.org/docs/idl
+pub use self::compat::{Change, ChangeKind, CompatibilityReport};
+pub use self::document::{
+    Const, ConstValue, Definition, Document, Enum, EnumValue, Field, FieldType, Function,
+    Namespace, Requiredness, Service, Struct, Typedef,
+};
+
+mod compat;
+mod document;
+mod parser;
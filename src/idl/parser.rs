@@ -0,0 +1,675 @@
+use crate::idl::{
+    Const, ConstValue, Definition, Document, Enum, EnumValue, Field, FieldType, Function,
+    Namespace, Requiredness, Service, Struct, Typedef,
+};
+use crate::{ErrorKind, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Int(i64),
+    Double(f64),
+    Literal(String),
+    Symbol(char),
+    Eos,
+}
+
+#[derive(Debug)]
+pub struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+    peeked: Option<(Token, usize, usize)>,
+}
+impl<'a> Parser<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Parser {
+            text,
+            offset: 0,
+            line: 1,
+            column: 1,
+            peeked: None,
+        }
+    }
+
+    pub fn parse_document(&mut self) -> Result<Document> {
+        let mut document = Document::default();
+        loop {
+            let position = self.position();
+            let keyword = match track!(self.next_token())? {
+                Token::Eos => break,
+                Token::Identifier(s) => s,
+                token => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "{}: expected a definition, found {:?}",
+                    position,
+                    token
+                ),
+            };
+            match keyword.as_str() {
+                "include" => document.includes.push(track!(self.expect_literal())?),
+                "cpp_include" => {
+                    track!(self.expect_literal())?;
+                }
+                "namespace" => {
+                    let scope = if track!(self.eat_symbol('*'))? {
+                        "*".to_owned()
+                    } else {
+                        track!(self.expect_identifier())?
+                    };
+                    let name = track!(self.expect_identifier())?;
+                    document.namespaces.push(Namespace { scope, name });
+                    track!(self.skip_annotations())?;
+                }
+                "const" => {
+                    let field_type = track!(self.parse_field_type())?;
+                    let name = track!(self.expect_identifier())?;
+                    track!(self.expect_symbol('='))?;
+                    let value = track!(self.parse_const_value())?;
+                    document.definitions.push(Definition::Const(Const {
+                        name,
+                        field_type,
+                        value,
+                    }));
+                }
+                "typedef" => {
+                    let field_type = track!(self.parse_field_type())?;
+                    let name = track!(self.expect_identifier())?;
+                    track!(self.skip_annotations())?;
+                    document
+                        .definitions
+                        .push(Definition::Typedef(Typedef { name, field_type }));
+                }
+                "enum" => {
+                    let e = track!(self.parse_enum())?;
+                    document.definitions.push(Definition::Enum(e));
+                }
+                "struct" => {
+                    let s = track!(self.parse_struct())?;
+                    document.definitions.push(Definition::Struct(s));
+                }
+                "union" => {
+                    let s = track!(self.parse_struct())?;
+                    document.definitions.push(Definition::Union(s));
+                }
+                "exception" => {
+                    let s = track!(self.parse_struct())?;
+                    document.definitions.push(Definition::Exception(s));
+                }
+                "service" => {
+                    let s = track!(self.parse_service())?;
+                    document.definitions.push(Definition::Service(s));
+                }
+                _ => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "{}: unknown keyword {:?}",
+                    position,
+                    keyword
+                ),
+            }
+            track!(self.skip_list_separator())?;
+        }
+        Ok(document)
+    }
+
+    fn parse_enum(&mut self) -> Result<Enum> {
+        let name = track!(self.expect_identifier())?;
+        track!(self.expect_symbol('{'))?;
+        let mut values = Vec::new();
+        let mut next_value = 0;
+        while !track!(self.eat_symbol('}'))? {
+            let name = track!(self.expect_identifier())?;
+            if track!(self.eat_symbol('='))? {
+                let position = self.position();
+                let value = track!(self.expect_int())?;
+                track_assert!(
+                    i64::from(value as i32) == value,
+                    ErrorKind::InvalidInput,
+                    "{}: enum value out of range: {}",
+                    position,
+                    value
+                );
+                next_value = value as i32;
+            }
+            values.push(EnumValue {
+                name,
+                value: next_value,
+            });
+            next_value = next_value.wrapping_add(1);
+            track!(self.skip_annotations())?;
+            track!(self.skip_list_separator())?;
+        }
+        track!(self.skip_annotations())?;
+        Ok(Enum { name, values })
+    }
+
+    fn parse_struct(&mut self) -> Result<Struct> {
+        let name = track!(self.expect_identifier())?;
+        if track!(self.peek())? == &Token::Identifier("xsd_all".to_owned()) {
+            self.next_token()?;
+        }
+        track!(self.expect_symbol('{'))?;
+        let fields = track!(self.parse_fields('}'))?;
+        track!(self.skip_annotations())?;
+        Ok(Struct { name, fields })
+    }
+
+    fn parse_service(&mut self) -> Result<Service> {
+        let name = track!(self.expect_identifier())?;
+        let extends = if track!(self.eat_keyword("extends"))? {
+            Some(track!(self.expect_identifier())?)
+        } else {
+            None
+        };
+        track!(self.expect_symbol('{'))?;
+        let mut functions = Vec::new();
+        while !track!(self.eat_symbol('}'))? {
+            let oneway = track!(self.eat_keyword("oneway"))?;
+            let return_type = if track!(self.eat_keyword("void"))? {
+                None
+            } else {
+                Some(track!(self.parse_field_type())?)
+            };
+            let name = track!(self.expect_identifier())?;
+            track!(self.expect_symbol('('))?;
+            let params = track!(self.parse_fields(')'))?;
+            let throws = if track!(self.eat_keyword("throws"))? {
+                track!(self.expect_symbol('('))?;
+                track!(self.parse_fields(')'))?
+            } else {
+                Vec::new()
+            };
+            track!(self.skip_annotations())?;
+            track!(self.skip_list_separator())?;
+            functions.push(Function {
+                name,
+                oneway,
+                return_type,
+                params,
+                throws,
+            });
+        }
+        track!(self.skip_annotations())?;
+        Ok(Service {
+            name,
+            extends,
+            functions,
+        })
+    }
+
+    fn parse_fields(&mut self, close: char) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        let mut next_implicit_id = -1;
+        while !track!(self.eat_symbol(close))? {
+            let id = if let Token::Int(_) = *track!(self.peek())? {
+                let position = self.position();
+                let id = track!(self.expect_int())?;
+                track_assert!(
+                    i64::from(id as i16) == id,
+                    ErrorKind::InvalidInput,
+                    "{}: field identifier out of range: {}",
+                    position,
+                    id
+                );
+                track!(self.expect_symbol(':'))?;
+                id as i16
+            } else {
+                next_implicit_id -= 1;
+                next_implicit_id + 1
+            };
+            let requiredness = if track!(self.eat_keyword("required"))? {
+                Requiredness::Required
+            } else if track!(self.eat_keyword("optional"))? {
+                Requiredness::Optional
+            } else {
+                Requiredness::Default
+            };
+            let field_type = track!(self.parse_field_type())?;
+            let name = track!(self.expect_identifier())?;
+            let default = if track!(self.eat_symbol('='))? {
+                Some(track!(self.parse_const_value())?)
+            } else {
+                None
+            };
+            track!(self.skip_annotations())?;
+            track!(self.skip_list_separator())?;
+            fields.push(Field {
+                id,
+                name,
+                field_type,
+                requiredness,
+                default,
+            });
+        }
+        Ok(fields)
+    }
+
+    fn parse_field_type(&mut self) -> Result<FieldType> {
+        let position = self.position();
+        let name = track!(self.expect_identifier())?;
+        let field_type = match name.as_str() {
+            "bool" => FieldType::Bool,
+            "byte" | "i8" => FieldType::I8,
+            "i16" => FieldType::I16,
+            "i32" => FieldType::I32,
+            "i64" => FieldType::I64,
+            "double" => FieldType::Double,
+            "string" => FieldType::String,
+            "binary" => FieldType::Binary,
+            "uuid" => FieldType::Uuid,
+            "list" => {
+                track!(self.expect_symbol('<'))?;
+                let elem = track!(self.parse_field_type())?;
+                track!(self.expect_symbol('>'))?;
+                track!(self.skip_cpp_type())?;
+                FieldType::List(Box::new(elem))
+            }
+            "set" => {
+                track!(self.skip_cpp_type())?;
+                track!(self.expect_symbol('<'))?;
+                let elem = track!(self.parse_field_type())?;
+                track!(self.expect_symbol('>'))?;
+                FieldType::Set(Box::new(elem))
+            }
+            "map" => {
+                track!(self.skip_cpp_type())?;
+                track!(self.expect_symbol('<'))?;
+                let key = track!(self.parse_field_type())?;
+                track!(self.expect_symbol(','))?;
+                let value = track!(self.parse_field_type())?;
+                track!(self.expect_symbol('>'))?;
+                FieldType::Map(Box::new(key), Box::new(value))
+            }
+            "void" | "oneway" | "required" | "optional" => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}: unexpected keyword {:?}",
+                position,
+                name
+            ),
+            _ => FieldType::Named(name),
+        };
+        track!(self.skip_annotations())?;
+        Ok(field_type)
+    }
+
+    fn parse_const_value(&mut self) -> Result<ConstValue> {
+        let position = self.position();
+        let value = match self.next_token()? {
+            Token::Int(n) => ConstValue::Int(n),
+            Token::Double(n) => ConstValue::Double(n),
+            Token::Literal(s) => ConstValue::String(s),
+            Token::Identifier(s) if s == "true" => ConstValue::Int(1),
+            Token::Identifier(s) if s == "false" => ConstValue::Int(0),
+            Token::Identifier(s) => ConstValue::Identifier(s),
+            Token::Symbol('[') => {
+                let mut values = Vec::new();
+                while !track!(self.eat_symbol(']'))? {
+                    values.push(track!(self.parse_const_value())?);
+                    track!(self.skip_list_separator())?;
+                }
+                ConstValue::List(values)
+            }
+            Token::Symbol('{') => {
+                let mut entries = Vec::new();
+                while !track!(self.eat_symbol('}'))? {
+                    let key = track!(self.parse_const_value())?;
+                    track!(self.expect_symbol(':'))?;
+                    let value = track!(self.parse_const_value())?;
+                    entries.push((key, value));
+                    track!(self.skip_list_separator())?;
+                }
+                ConstValue::Map(entries)
+            }
+            token => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}: expected a constant value, found {:?}",
+                position,
+                token
+            ),
+        };
+        Ok(value)
+    }
+
+    fn skip_cpp_type(&mut self) -> Result<()> {
+        if track!(self.eat_keyword("cpp_type"))? {
+            track!(self.expect_literal())?;
+        }
+        Ok(())
+    }
+
+    fn skip_annotations(&mut self) -> Result<()> {
+        if track!(self.eat_symbol('('))? {
+            while !track!(self.eat_symbol(')'))? {
+                track!(self.expect_identifier())?;
+                if track!(self.eat_symbol('='))? {
+                    track!(self.expect_literal())?;
+                }
+                track!(self.skip_list_separator())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_list_separator(&mut self) -> Result<()> {
+        if !track!(self.eat_symbol(','))? {
+            track!(self.eat_symbol(';'))?;
+        }
+        Ok(())
+    }
+
+    fn eat_symbol(&mut self, c: char) -> Result<bool> {
+        if *track!(self.peek())? == Token::Symbol(c) {
+            self.next_token()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> Result<bool> {
+        if matches!(track!(self.peek())?, Token::Identifier(s) if s == keyword) {
+            self.next_token()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect_symbol(&mut self, c: char) -> Result<()> {
+        let position = self.position();
+        let token = track!(self.next_token())?;
+        track_assert_eq!(
+            token,
+            Token::Symbol(c),
+            ErrorKind::InvalidInput,
+            "{}: expected {:?}",
+            position,
+            c
+        );
+        Ok(())
+    }
+
+    fn expect_identifier(&mut self) -> Result<String> {
+        let position = self.position();
+        match track!(self.next_token())? {
+            Token::Identifier(s) => Ok(s),
+            token => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}: expected an identifier, found {:?}",
+                position,
+                token
+            ),
+        }
+    }
+
+    fn expect_literal(&mut self) -> Result<String> {
+        let position = self.position();
+        match track!(self.next_token())? {
+            Token::Literal(s) => Ok(s),
+            token => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}: expected a string literal, found {:?}",
+                position,
+                token
+            ),
+        }
+    }
+
+    fn expect_int(&mut self) -> Result<i64> {
+        let position = self.position();
+        match track!(self.next_token())? {
+            Token::Int(n) => Ok(n),
+            token => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}: expected an integer, found {:?}",
+                position,
+                token
+            ),
+        }
+    }
+
+    fn position(&mut self) -> String {
+        if self.peeked.is_none() {
+            if let Ok(token) = self.read_token() {
+                self.peeked = Some(token);
+            }
+        }
+        match self.peeked {
+            Some((_, line, column)) => format!("{}:{}", line, column),
+            None => format!("{}:{}", self.line, self.column),
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = Some(track!(self.read_token())?);
+        }
+        Ok(&self.peeked.as_ref().expect("Never fails").0)
+    }
+
+    fn next_token(&mut self) -> Result<Token> {
+        if let Some((token, _, _)) = self.peeked.take() {
+            Ok(token)
+        } else {
+            track!(self.read_token()).map(|(token, _, _)| token)
+        }
+    }
+
+    fn read_token(&mut self) -> Result<(Token, usize, usize)> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let c = match self.peek_char() {
+            None => return Ok((Token::Eos, line, column)),
+            Some(c) => c,
+        };
+        let token = if c.is_ascii_alphabetic() || c == '_' {
+            let s = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            Token::Identifier(s.to_owned())
+        } else if c.is_ascii_digit() || c == '+' || c == '-' {
+            track!(self.read_number(), "{}:{}", line, column)?
+        } else if c == '"' || c == '\'' {
+            self.read_char();
+            let literal = self.take_while(|x| x != c).to_owned();
+            track_assert_eq!(
+                self.read_char(),
+                Some(c),
+                ErrorKind::InvalidInput,
+                "{}:{}: unterminated string literal",
+                line,
+                column
+            );
+            Token::Literal(literal)
+        } else if "{}()[]<>,;:=*".contains(c) {
+            self.read_char();
+            Token::Symbol(c)
+        } else {
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: unexpected character {:?}",
+                line,
+                column,
+                c
+            );
+        };
+        Ok((token, line, column))
+    }
+
+    fn read_number(&mut self) -> Result<Token> {
+        let s = self.take_while(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        let (sign, digits) = if let Some(digits) = s.strip_prefix('-') {
+            (-1, digits)
+        } else {
+            (1, s.strip_prefix('+').unwrap_or(s))
+        };
+        if let Some(hex) = digits.strip_prefix("0x") {
+            let n = track_assert_some!(
+                i64::from_str_radix(hex, 16).ok(),
+                ErrorKind::InvalidInput,
+                "invalid integer: {:?}",
+                s
+            );
+            Ok(Token::Int(sign * n))
+        } else if let Ok(n) = s.parse::<i64>() {
+            Ok(Token::Int(n))
+        } else {
+            let n = track_assert_some!(
+                s.parse::<f64>().ok(),
+                ErrorKind::InvalidInput,
+                "invalid number: {:?}",
+                s
+            );
+            Ok(Token::Double(n))
+        }
+    }
+
+    fn skip_whitespaces_and_comments(&mut self) -> Result<()> {
+        loop {
+            let rest = &self.text[self.offset..];
+            if rest.starts_with(|c: char| c.is_whitespace()) {
+                self.take_while(char::is_whitespace);
+            } else if rest.starts_with("//") || rest.starts_with('#') {
+                self.take_while(|c| c != '\n');
+            } else if rest.starts_with("/*") {
+                let (line, column) = (self.line, self.column);
+                match rest.find("*/") {
+                    None => track_panic!(
+                        ErrorKind::InvalidInput,
+                        "{}:{}: unterminated comment",
+                        line,
+                        column
+                    ),
+                    Some(end) => {
+                        for _ in rest[..end + 2].chars() {
+                            self.read_char();
+                        }
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.offset;
+        while self.peek_char().is_some_and(&f) {
+            self.read_char();
+        }
+        &self.text[start..self.offset]
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tutorial_works() {
+        let text = r#"
+            include "shared.thrift"
+
+            namespace rs tutorial
+            namespace * tutorial.generic
+
+            typedef i32 MyInteger
+            const i32 INT32CONSTANT = 9853
+            const map<string,string> MAPCONSTANT = {'hello':'world', 'goodnight':'moon'}
+
+            /** Operations */
+            enum Operation {
+              ADD = 1,
+              SUBTRACT = 2,
+              MULTIPLY,
+              DIVIDE = 0x10
+            }
+
+            struct Work {
+              1: i32 num1 = 0,
+              2: i32 num2,
+              3: Operation op,
+              4: optional string comment, # trailing comment
+            }
+
+            exception InvalidOperation {
+              1: i32 whatOp,
+              2: string why
+            } (rust.name = "Invalid")
+
+            service Calculator extends shared.SharedService {
+               void ping(),
+               i32 add(1:i32 num1, 2:i32 num2),
+               i32 calculate(1:i32 logid, 2:Work w) throws (1:InvalidOperation ouch),
+               oneway void zip()
+            }
+        "#;
+        let document = track_try_unwrap!(Parser::new(text).parse_document());
+        assert_eq!(document.includes, ["shared.thrift"]);
+        assert_eq!(document.namespace("rs"), Some("tutorial"));
+        assert_eq!(document.namespace("java"), Some("tutorial.generic"));
+        assert_eq!(document.definitions.len(), 7);
+
+        let Some(Definition::Enum(op)) = document.definition("Operation") else {
+            panic!()
+        };
+        let values = op.values.iter().map(|v| v.value).collect::<Vec<_>>();
+        assert_eq!(values, [1, 2, 3, 16]);
+
+        let Some(Definition::Struct(work)) = document.definition("Work") else {
+            panic!()
+        };
+        assert_eq!(work.fields[0].default, Some(ConstValue::Int(0)));
+        assert_eq!(
+            work.fields[2].field_type,
+            FieldType::Named("Operation".to_owned())
+        );
+        assert_eq!(work.fields[3].requiredness, Requiredness::Optional);
+
+        let Some(Definition::Service(calc)) = document.definition("Calculator") else {
+            panic!()
+        };
+        assert_eq!(calc.extends.as_deref(), Some("shared.SharedService"));
+        assert_eq!(calc.functions.len(), 4);
+        assert_eq!(calc.functions[0].return_type, None);
+        assert_eq!(calc.functions[2].throws[0].name, "ouch");
+        assert!(calc.functions[3].oneway);
+    }
+
+    #[test]
+    fn implicit_field_ids_work() {
+        let text = "struct Foo { i32 a; 5: i32 b; string c }";
+        let document = track_try_unwrap!(Parser::new(text).parse_document());
+        let Definition::Struct(foo) = &document.definitions[0] else {
+            panic!()
+        };
+        let ids = foo.fields.iter().map(|f| f.id).collect::<Vec<_>>();
+        assert_eq!(ids, [-1, 5, -2]);
+    }
+
+    #[test]
+    fn parse_error_has_position() {
+        let text = "struct Foo {\n  1: i32 a\n  2 i32 b\n}";
+        let e = Parser::new(text).parse_document().err().unwrap();
+        assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        assert!(e.to_string().contains("3:5"), "{}", e);
+    }
+}
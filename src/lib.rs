@@ -61,8 +61,16 @@ pub use decode::{BinaryDecode, CompactDecode};
 pub use encode::{BinaryEncode, CompactEncode};
 pub use error::{Error, ErrorKind};
 
+pub mod canonical;
+pub mod codegen;
 pub mod data;
+pub mod idl;
 pub mod message;
+pub mod rpc;
+pub mod text;
+
+#[doc(hidden)]
+pub mod macros;
 
 mod constants;
 mod decode;
@@ -0,0 +1,387 @@
+//! Helpers for the `thrift!` macro.
+use crate::data::{Data, DataKind, Elements, Field, Map, Struct};
+
+/// Constructs a `Struct` (or `Data`) from a literal-like notation.
+///
+/// `thrift! { id: value, ... }` makes a `Struct` and `thrift!(value)` makes a `Data`.
+/// Values are written as follows:
+///
+/// | Notation                        | Result                                          |
+/// |---------------------------------|-------------------------------------------------|
+/// | `{ 1: value, 3: value }`        | `Data::Struct` (`{}` is an empty struct)        |
+/// | `[value, ...]`                  | `Data::List`                                    |
+/// | `set[value, ...]`               | `Data::Set`                                     |
+/// | `{ key => value, ... }`         | `Data::Map`                                     |
+/// | `list<kind>[...]`, `set<kind>[...]`, `map<kind, kind>{...}` | The collections which have the explicit kinds |
+/// | `uuid("67e55044-10b1-426f-9247-bb680e5fe0c8")` | `Data::Uuid`                     |
+/// | Any other expression            | `Data::from(expression)`                        |
+///
+/// The kinds of the collections without explicit kinds are taken from the first elements,
+/// so the empty collections need the explicit kinds (e.g., `list<i32>[]`).
+/// The available kinds are `bool`, `i8` (or `byte`), `i16`, `i32`, `i64`, `double`,
+/// `binary` (or `string`), `struct`, `map`, `set`, `list` and `uuid`.
+///
+/// Literals of any size can be written as long as the values are single token trees
+/// (e.g., `1`, `"foo"`, `name` or bracketed collections).
+/// The other values (e.g., `-1` or `set[...]`) take a recursion level each,
+/// so a collection of more than about a hundred of them needs a higher `recursion_limit`.
+///
+/// # Panics
+///
+/// This macro panics if the elements of a collection have different kinds,
+/// a struct has duplicate field identifiers, a map has duplicate keys or
+/// a UUID literal is malformed.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{Data, List};
+/// use thrift_codec::thrift;
+///
+/// let s = thrift! {
+///     1: "svc",
+///     3: 42i64,
+///     7: [{ 1: true }, { 1: false }],
+///     9: { "k" => 1i32 },
+///     10: set<i16>[],
+///     -1: uuid("67e55044-10b1-426f-9247-bb680e5fe0c8"),
+/// };
+/// assert_eq!(s.get_str(1).unwrap(), Some("svc"));
+/// assert_eq!(s.get_i64(3).unwrap(), Some(42));
+/// assert_eq!(
+///     s.to_string(),
+///     concat!(
+///         r#"struct { 1: "svc", 3: i64:42, 7: list<struct>[struct { 1: true }, struct { 1: false }], "#,
+///         r#"9: map<binary, i32>{ "k": 1 }, 10: set<i16>[], -1: uuid:67e55044-10b1-426f-9247-bb680e5fe0c8 }"#
+///     )
+/// );
+///
+/// let data = thrift!([1, 2, 3]);
+/// assert_eq!(data, Data::List(List::from(vec![1, 2, 3])));
+/// ```
+#[macro_export]
+macro_rules! thrift {
+    () => {
+        $crate::data::Struct::new(::std::vec::Vec::new())
+    };
+    ($id:literal : $($rest:tt)*) => {
+        $crate::macros::fields($crate::thrift!(@fields [] $id : $($rest)*))
+    };
+
+    // Fields of a struct.
+    //
+    // The trailing values which are single token trees are matched by a repetition at once,
+    // and the other common values are matched one per expansion,
+    // so that the recursion depth does not grow with the number of tokens.
+    // Arbitrary expressions are split off token by token by `@take`.
+    (@fields [$($out:expr,)*] $($id:literal : $v:tt),* $(,)?) => {
+        ::std::vec![$($out,)* $($crate::data::Field::new($id, $crate::thrift!(@value $v)),)*]
+    };
+    (@fields [$($out:expr,)*] $id:literal : $v:tt , $($rest:tt)*) => {
+        $crate::thrift!(@fields [$($out,)* $crate::data::Field::new($id, $crate::thrift!(@value $v)),] $($rest)*)
+    };
+    (@fields [$($out:expr,)*] $id:literal : $a:tt $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(@fields [$($out,)* $crate::data::Field::new($id, $crate::thrift!(@value $a $v)),] $($($rest)*)?)
+    };
+    (@fields [$($out:expr,)*] $id:literal : $p:ident<$($k:ident),+> $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(
+            @fields [$($out,)* $crate::data::Field::new($id, $crate::thrift!(@value $p<$($k),+> $v)),]
+            $($($rest)*)?
+        )
+    };
+    (@fields [$($out:expr,)*] $id:literal : $($rest:tt)*) => {
+        $crate::thrift!(@take [@field [$($out,)*] $id] [] $($rest)*)
+    };
+    (@field [$($out:expr,)*] $id:literal [$($v:tt)+] $($rest:tt)*) => {
+        $crate::thrift!(@fields [$($out,)* $crate::data::Field::new($id, $crate::thrift!(@value $($v)+)),] $($rest)*)
+    };
+
+    // Elements of a list or a set.
+    (@elements $kind:expr; [$($out:expr,)*] $($v:tt),* $(,)?) => {
+        $crate::macros::elements($kind, ::std::vec![$($out,)* $($crate::thrift!(@value $v),)*])
+    };
+    (@elements $kind:expr; [$($out:expr,)*] $v:tt , $($rest:tt)*) => {
+        $crate::thrift!(@elements $kind; [$($out,)* $crate::thrift!(@value $v),] $($rest)*)
+    };
+    (@elements $kind:expr; [$($out:expr,)*] $a:tt $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(@elements $kind; [$($out,)* $crate::thrift!(@value $a $v),] $($($rest)*)?)
+    };
+    (@elements $kind:expr; [$($out:expr,)*] $p:ident<$($k:ident),+> $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(@elements $kind; [$($out,)* $crate::thrift!(@value $p<$($k),+> $v),] $($($rest)*)?)
+    };
+    (@elements $kind:expr; [$($out:expr,)*] $($rest:tt)+) => {
+        $crate::thrift!(@take [@element $kind; [$($out,)*]] [] $($rest)+)
+    };
+    (@element $kind:expr; [$($out:expr,)*] [$($v:tt)+] $($rest:tt)*) => {
+        $crate::thrift!(@elements $kind; [$($out,)* $crate::thrift!(@value $($v)+),] $($rest)*)
+    };
+
+    // Entries of a map.
+    (@entries $kinds:expr; [$($out:expr,)*] $($k:tt => $v:tt),* $(,)?) => {
+        $crate::macros::map(
+            $kinds,
+            ::std::vec![$($out,)* $(($crate::thrift!(@value $k), $crate::thrift!(@value $v)),)*],
+        )
+    };
+    (@entries $kinds:expr; [$($out:expr,)*] $k:tt => $v:tt , $($rest:tt)*) => {
+        $crate::thrift!(@entries $kinds; [$($out,)* ($crate::thrift!(@value $k), $crate::thrift!(@value $v)),] $($rest)*)
+    };
+    (@entries $kinds:expr; [$($out:expr,)*] $k:tt => $a:tt $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(
+            @entries $kinds;
+            [$($out,)* ($crate::thrift!(@value $k), $crate::thrift!(@value $a $v)),]
+            $($($rest)*)?
+        )
+    };
+    (@entries $kinds:expr; [$($out:expr,)*] $k:tt => $p:ident<$($x:ident),+> $v:tt $(, $($rest:tt)*)?) => {
+        $crate::thrift!(
+            @entries $kinds;
+            [$($out,)* ($crate::thrift!(@value $k), $crate::thrift!(@value $p<$($x),+> $v)),]
+            $($($rest)*)?
+        )
+    };
+    (@entries $kinds:expr; [$($out:expr,)*] $($rest:tt)+) => {
+        $crate::thrift!(@key $kinds; [$($out,)*] [] $($rest)+)
+    };
+    (@key $kinds:expr; [$($out:expr,)*] [$($k:tt)+] => $($rest:tt)*) => {
+        $crate::thrift!(@take [@entry $kinds; [$($out,)*] [$($k)+]] [] $($rest)*)
+    };
+    (@key $kinds:expr; [$($out:expr,)*] [$($k:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::thrift!(@key $kinds; [$($out,)*] [$($k)* $next] $($rest)*)
+    };
+    (@entry $kinds:expr; [$($out:expr,)*] [$($k:tt)+] [$($v:tt)+] $($rest:tt)*) => {
+        $crate::thrift!(
+            @entries $kinds;
+            [$($out,)* ($crate::thrift!(@value $($k)+), $crate::thrift!(@value $($v)+)),]
+            $($rest)*
+        )
+    };
+
+    // Splits the next value off the tokens and passes it (and the rest) to the continuation.
+    (@take [$($cont:tt)*] [$($v:tt)+] $(, $($rest:tt)*)?) => {
+        $crate::thrift!($($cont)* [$($v)+] $($($rest)*)?)
+    };
+    (@take [$($cont:tt)*] [$($v:tt)*] map<$k:ident, $w:ident> $($rest:tt)*) => {
+        $crate::thrift!(@take [$($cont)*] [$($v)* map<$k, $w>] $($rest)*)
+    };
+    (@take [$($cont:tt)*] [$($v:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::thrift!(@take [$($cont)*] [$($v)* $next] $($rest)*)
+    };
+
+    // Values.
+    (@value []) => {
+        ::std::compile_error!("the kind of an empty list must be specified (e.g., `list<i32>[]`)")
+    };
+    (@value [$($e:tt)+]) => {
+        $crate::data::Data::List($crate::data::List::new(
+            $crate::thrift!(@elements ::std::option::Option::None; [] $($e)+)
+        ))
+    };
+    (@value list<$kind:ident>[$($e:tt)*]) => {
+        $crate::data::Data::List($crate::data::List::new(
+            $crate::thrift!(@elements ::std::option::Option::Some($crate::thrift!(@kind $kind)); [] $($e)*)
+        ))
+    };
+    (@value set[]) => {
+        ::std::compile_error!("the kind of an empty set must be specified (e.g., `set<i32>[]`)")
+    };
+    (@value set[$($e:tt)+]) => {
+        $crate::data::Data::Set($crate::data::Set::new(
+            $crate::thrift!(@elements ::std::option::Option::None; [] $($e)+)
+        ))
+    };
+    (@value set<$kind:ident>[$($e:tt)*]) => {
+        $crate::data::Data::Set($crate::data::Set::new(
+            $crate::thrift!(@elements ::std::option::Option::Some($crate::thrift!(@kind $kind)); [] $($e)*)
+        ))
+    };
+    (@value map<$key:ident, $value:ident>{$($e:tt)*}) => {
+        $crate::data::Data::Map($crate::thrift!(
+            @entries ::std::option::Option::Some(($crate::thrift!(@kind $key), $crate::thrift!(@kind $value)));
+            [] $($e)*
+        ))
+    };
+    (@value uuid($s:expr)) => {
+        $crate::data::Data::Uuid($crate::macros::uuid($s))
+    };
+    (@value {}) => {
+        $crate::data::Data::Struct($crate::data::Struct::new(::std::vec::Vec::new()))
+    };
+    (@value {$id:literal : $($e:tt)*}) => {
+        $crate::data::Data::Struct($crate::thrift!($id : $($e)*))
+    };
+    (@value {$($e:tt)+}) => {
+        $crate::data::Data::Map($crate::thrift!(@entries ::std::option::Option::None; [] $($e)+))
+    };
+    (@value $e:expr) => {
+        $crate::data::Data::from($e)
+    };
+
+    // Kinds.
+    (@kind bool) => { $crate::data::DataKind::Bool };
+    (@kind i8) => { $crate::data::DataKind::I8 };
+    (@kind byte) => { $crate::data::DataKind::I8 };
+    (@kind i16) => { $crate::data::DataKind::I16 };
+    (@kind i32) => { $crate::data::DataKind::I32 };
+    (@kind i64) => { $crate::data::DataKind::I64 };
+    (@kind double) => { $crate::data::DataKind::Double };
+    (@kind binary) => { $crate::data::DataKind::Binary };
+    (@kind string) => { $crate::data::DataKind::Binary };
+    (@kind struct) => { $crate::data::DataKind::Struct };
+    (@kind map) => { $crate::data::DataKind::Map };
+    (@kind set) => { $crate::data::DataKind::Set };
+    (@kind list) => { $crate::data::DataKind::List };
+    (@kind uuid) => { $crate::data::DataKind::Uuid };
+
+    (@ $($invalid:tt)*) => {
+        ::std::compile_error!("invalid `thrift!` syntax")
+    };
+    ($($value:tt)+) => {
+        $crate::thrift!(@value $($value)+)
+    };
+}
+
+#[doc(hidden)]
+pub fn fields(fields: Vec<Field>) -> Struct {
+    let s = Struct::new(fields);
+    if let Some(id) = s.duplicate_ids().first() {
+        panic!("thrift!: duplicate field identifier: {}", id);
+    }
+    s
+}
+
+#[doc(hidden)]
+pub fn elements(kind: Option<DataKind>, items: Vec<Data>) -> Elements {
+    let kind = kind
+        .or_else(|| items.first().map(Data::kind))
+        .expect("Never fails");
+    let mut elements = Elements::new(kind);
+    for item in items {
+        if let Err(e) = elements.push(item) {
+            panic!("thrift!: {}", e);
+        }
+    }
+    elements
+}
+
+#[doc(hidden)]
+pub fn map(kinds: Option<(DataKind, DataKind)>, entries: Vec<(Data, Data)>) -> Map {
+    let (key_kind, value_kind) = kinds
+        .or_else(|| entries.first().map(|(k, v)| (k.kind(), v.kind())))
+        .expect("Never fails");
+    let mut keys = Elements::new(key_kind);
+    let mut values = Elements::new(value_kind);
+    for (k, v) in entries {
+        if let Err(e) = keys.push(k).and_then(|()| values.push(v)) {
+            panic!("thrift!: {}", e);
+        }
+    }
+    if let Some(i) = keys.dedup_flags(false).iter().position(|&keep| !keep) {
+        panic!(
+            "thrift!: duplicate map key: {}",
+            keys.get(i).expect("Never fails")
+        );
+    }
+    Map::from_keys_and_values(keys, values).expect("Never fails")
+}
+
+#[doc(hidden)]
+pub fn uuid(s: &str) -> crate::data::Uuid {
+    match s.parse() {
+        Ok(uuid) => uuid,
+        Err(e) => panic!("thrift!: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::{Data, DataKind, Field, List, Map, Set, Struct};
+
+    #[test]
+    fn thrift_macro_works() {
+        let name = String::from("foo");
+        let s = thrift! {
+            1: name.clone(),
+            2: -1i8,
+            -3: { 1: [[1, 2], list<i32>[]], 2: {} },
+            4: set["a", "b",],
+            5: map<i32, list>{ 1 => [true], -2 => list<bool>[] },
+        };
+
+        let mut map = Map::empty_of(DataKind::I32, DataKind::List);
+        track_try_unwrap!(map.insert(1, List::from(vec![true])));
+        track_try_unwrap!(map.insert(-2, List::from(Vec::<bool>::new())));
+        let expected = Struct::new(vec![
+            Field::new(1, "foo"),
+            Field::new(2, -1i8),
+            Field::new(
+                -3,
+                Struct::new(vec![
+                    Field::new(
+                        1,
+                        List::from(vec![List::from(vec![1, 2]), List::from(Vec::<i32>::new())]),
+                    ),
+                    Field::new(2, Struct::new(Vec::new())),
+                ]),
+            ),
+            Field::new(4, Set::from(vec![b"a".to_vec(), b"b".to_vec()])),
+            Field::new(5, map),
+        ]);
+        assert_eq!(s, expected);
+
+        assert_eq!(thrift!(), Struct::new(Vec::new()));
+        assert_eq!(thrift!(1.5), Data::Double(1.5));
+        assert_eq!(thrift!({ 1: 2 }), Data::Struct(thrift! { 1: 2 }));
+    }
+
+    #[test]
+    #[should_panic]
+    fn thrift_macro_rejects_mixed_kinds() {
+        let _ = thrift!([1, 2i64]);
+    }
+
+    #[test]
+    fn thrift_macro_accepts_many_fields_and_elements() {
+        #[rustfmt::skip]
+        let s = thrift! { 1: 1, 2: 2, 3: 3, 4: 4, 5: 5, 6: 6, 7: 7, 8: 8, 9: 9, 10: 10, 11: 11, 12: 12, 13: 13, 14: 14, 15: 15, 16: 16, 17: 17, 18: 18, 19: 19, 20: 20, 21: 21, 22: 22, 23: 23, 24: 24, 25: 25, 26: 26, 27: 27, 28: 28, 29: 29, 30: 30, 31: 31, 32: 32, 33: 33, 34: 34, 35: 35, 36: 36, 37: 37, 38: 38, 39: 39, 40: 40, 41: 41, 42: 42, 43: 43, 44: 44, 45: 45, 46: 46, 47: 47, 48: 48, 49: 49, 50: 50, 51: 51, 52: 52, 53: 53, 54: 54, 55: 55, 56: 56, 57: 57, 58: 58, 59: 59, 60: 60, 61: 61, 62: 62, 63: 63, 64: 64, 65: 65, 66: 66, 67: 67, 68: 68, 69: 69, 70: 70, 71: 71, 72: 72, 73: 73, 74: 74, 75: 75, 76: 76, 77: 77, 78: 78, 79: 79, 80: 80, 81: 81, 82: 82, 83: 83, 84: 84, 85: 85, 86: 86, 87: 87, 88: 88, 89: 89, 90: 90, 91: 91, 92: 92, 93: 93, 94: 94, 95: 95, 96: 96, 97: 97, 98: 98, 99: 99, 100: 100, 101: 101, 102: 102, 103: 103, 104: 104, 105: 105, 106: 106, 107: 107, 108: 108, 109: 109, 110: 110 };
+        assert_eq!(
+            s,
+            Struct::new((1..=110).map(|i| Field::new(i, i32::from(i))).collect())
+        );
+
+        #[rustfmt::skip]
+        let data = thrift!([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112, 113, 114, 115, 116, 117, 118, 119, 120]);
+        assert_eq!(
+            data,
+            Data::List(List::from((1..=120).collect::<Vec<i32>>()))
+        );
+
+        #[rustfmt::skip]
+        let s = thrift! { 1: 1, 2: 2, 3: 3, 4: 4, 5: 5, 6: 6, 7: 7, 8: 8, 9: 9, 10: -10, 11: 11, 12: 12, 13: 13, 14: 14, 15: 15, 16: 16, 17: 17, 18: 18, 19: 19, 20: -20, 21: 21, 22: 22, 23: 23, 24: 24, 25: 25, 26: 26, 27: 27, 28: 28, 29: 29, 30: -30, 31: 31, 32: 32, 33: 33, 34: 34, 35: 35, 36: 36, 37: 37, 38: 38, 39: 39, 40: -40, 41: 41, 42: 42, 43: 43, 44: 44, 45: 45, 46: 46, 47: 47, 48: 48, 49: 49, 50: -50, 51: 51, 52: 52, 53: 53, 54: 54, 55: 55, 56: 56, 57: 57, 58: 58, 59: 59, 60: -60, 61: 61, 62: 62, 63: 63, 64: 64, 65: 65, 66: 66, 67: 67, 68: 68, 69: 69, 70: -70, 71: 71, 72: 72, 73: 73, 74: 74, 75: 75, 76: 76, 77: 77, 78: 78, 79: 79, 80: -80, 81: 81, 82: 82, 83: 83, 84: 84, 85: 85, 86: 86, 87: 87, 88: 88, 89: 89, 90: -90, 91: 91, 92: 92, 93: 93, 94: 94, 95: 95, 96: 96, 97: 97, 98: 98, 99: 99, 100: -100, 101: 101, 102: 102, 103: 103, 104: 104, 105: 105, 106: 106, 107: 107, 108: 108, 109: 109, 110: -110 };
+        let expected = (1..=110)
+            .map(|i| {
+                Field::new(
+                    i,
+                    if i % 10 == 0 {
+                        -i32::from(i)
+                    } else {
+                        i32::from(i)
+                    },
+                )
+            })
+            .collect();
+        assert_eq!(s, Struct::new(expected));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate field identifier: 1")]
+    fn thrift_macro_rejects_duplicate_field_ids() {
+        let _ = thrift! { 1: 1, 2: 2, 1: 3 };
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate map key: \"a\"")]
+    fn thrift_macro_rejects_duplicate_map_keys() {
+        let _ = thrift!({ "a" => 1, "b" => 2, "a" => 3 });
+    }
+}
@@ -1,8 +1,12 @@
 //! RPC message.
-use crate::data::Struct;
+use crate::data::{Field, Struct};
+use crate::{Error, ErrorKind, Result};
+use std::convert::TryFrom;
+use std::fmt;
+use trackable::error::ErrorKindExt;
 
 /// RPC message.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Message {
     method_name: String,
@@ -36,6 +40,29 @@ impl Message {
         Self::new(method_name, MessageKind::Exception, sequence_id, body)
     }
 
+    /// Makes a new `Message` instance which has the kind `MessageKind::Exception` and
+    /// the body `exception`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use thrift_codec::message::{ApplicationException, ApplicationExceptionKind, Message};
+    ///
+    /// let exception = ApplicationException::new(ApplicationExceptionKind::UnknownMethod, "foo");
+    /// let message = Message::application_exception("foo", 1, exception.clone());
+    /// assert_eq!(message.to_application_exception(), Some(exception.clone()));
+    ///
+    /// let e = message.into_reply_body().unwrap_err();
+    /// assert_eq!(e.concrete_cause::<ApplicationException>(), Some(&exception));
+    /// ```
+    pub fn application_exception(
+        method_name: &str,
+        sequence_id: i32,
+        exception: ApplicationException,
+    ) -> Self {
+        Self::exception(method_name, sequence_id, exception.into())
+    }
+
     /// Makes a new `Message` instance which has the kind `MessageKind::Oneway`.
     pub fn oneway(method_name: &str, sequence_id: i32, body: Struct) -> Self {
         Self::new(method_name, MessageKind::Oneway, sequence_id, body)
@@ -60,6 +87,63 @@ impl Message {
     pub fn body(&self) -> &Struct {
         &self.body
     }
+
+    /// Returns the mutable reference to the body of this message.
+    pub fn body_mut(&mut self) -> &mut Struct {
+        &mut self.body
+    }
+
+    /// Takes the body of this message.
+    pub fn into_body(self) -> Struct {
+        self.body
+    }
+
+    /// Returns the application exception held by this message.
+    ///
+    /// If the kind of this message is not `MessageKind::Exception` or
+    /// the body is not a valid `TApplicationException`, this method returns `None`.
+    pub fn to_application_exception(&self) -> Option<ApplicationException> {
+        if self.kind != MessageKind::Exception {
+            return None;
+        }
+        ApplicationException::try_from(self.body.clone()).ok()
+    }
+
+    /// Takes ownership of the body of this reply message.
+    ///
+    /// # Errors
+    ///
+    /// If the kind of this message is `MessageKind::Exception`,
+    /// this method will return an error which kind is `ErrorKind::Other` and
+    /// which cause is the `ApplicationException` held by the message.
+    /// If the kind is neither `MessageKind::Reply` nor `MessageKind::Exception`, or
+    /// the exception could not be decoded,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn into_reply_body(self) -> Result<Struct> {
+        match self.kind {
+            MessageKind::Reply => Ok(self.body),
+            MessageKind::Exception => {
+                let Message {
+                    method_name,
+                    sequence_id,
+                    body,
+                    ..
+                } = self;
+                let exception = track!(ApplicationException::try_from(body))?;
+                Err(track!(
+                    Error::from(exception),
+                    "method={:?}, sequence_id={}",
+                    method_name,
+                    sequence_id
+                ))
+            }
+            kind => track_panic!(
+                ErrorKind::InvalidInput,
+                "Not a reply message: kind={:?}",
+                kind
+            ),
+        }
+    }
 }
 
 /// The kind of a message.
@@ -83,3 +167,170 @@ impl MessageKind {
         }
     }
 }
+
+/// `TApplicationException` (i.e., the body of a `MessageKind::Exception` message).
+///
+/// It is encoded as the struct `{1: string message, 2: i32 type}`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ApplicationException {
+    kind: ApplicationExceptionKind,
+    message: String,
+}
+impl ApplicationException {
+    /// Makes a new `ApplicationException` instance.
+    pub fn new(kind: ApplicationExceptionKind, message: impl Into<String>) -> Self {
+        ApplicationException {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the kind of this exception.
+    pub fn kind(&self) -> ApplicationExceptionKind {
+        self.kind
+    }
+
+    /// Returns the message of this exception.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+impl fmt::Display for ApplicationException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+impl std::error::Error for ApplicationException {}
+impl From<ApplicationException> for Struct {
+    fn from(f: ApplicationException) -> Self {
+        Struct::new(vec![Field::new(1, f.message), Field::new(2, f.kind.code())])
+    }
+}
+impl TryFrom<Struct> for ApplicationException {
+    type Error = Error;
+
+    /// Converts `f` to an `ApplicationException`.
+    ///
+    /// The missing message is regarded as empty, and
+    /// the missing type is regarded as `ApplicationExceptionKind::Unknown`.
+    /// The unknown types are kept as `ApplicationExceptionKind::Other`.
+    fn try_from(f: Struct) -> Result<Self> {
+        let message = track!(f.get_str(1))?.unwrap_or_default().to_owned();
+        let kind = track!(f.get_i32(2))?.map_or(
+            ApplicationExceptionKind::Unknown,
+            ApplicationExceptionKind::from_code,
+        );
+        Ok(ApplicationException { kind, message })
+    }
+}
+impl From<ApplicationException> for Error {
+    fn from(f: ApplicationException) -> Self {
+        ErrorKind::Other.cause(f).into()
+    }
+}
+
+/// The type of a `TApplicationException`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[allow(missing_docs)]
+pub enum ApplicationExceptionKind {
+    Unknown,
+    UnknownMethod,
+    InvalidMessageType,
+    WrongMethodName,
+    BadSequenceId,
+    MissingResult,
+    InternalError,
+    ProtocolError,
+    InvalidTransform,
+    InvalidProtocol,
+    UnsupportedClientType,
+    /// A type which has no named variant.
+    Other(i32),
+}
+impl ApplicationExceptionKind {
+    /// Makes the kind which has the type code `code`.
+    ///
+    /// The codes which have no named variants are kept as `ApplicationExceptionKind::Other`.
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => ApplicationExceptionKind::Unknown,
+            1 => ApplicationExceptionKind::UnknownMethod,
+            2 => ApplicationExceptionKind::InvalidMessageType,
+            3 => ApplicationExceptionKind::WrongMethodName,
+            4 => ApplicationExceptionKind::BadSequenceId,
+            5 => ApplicationExceptionKind::MissingResult,
+            6 => ApplicationExceptionKind::InternalError,
+            7 => ApplicationExceptionKind::ProtocolError,
+            8 => ApplicationExceptionKind::InvalidTransform,
+            9 => ApplicationExceptionKind::InvalidProtocol,
+            10 => ApplicationExceptionKind::UnsupportedClientType,
+            _ => ApplicationExceptionKind::Other(code),
+        }
+    }
+
+    /// Returns the type code of this kind.
+    pub fn code(self) -> i32 {
+        match self {
+            ApplicationExceptionKind::Unknown => 0,
+            ApplicationExceptionKind::UnknownMethod => 1,
+            ApplicationExceptionKind::InvalidMessageType => 2,
+            ApplicationExceptionKind::WrongMethodName => 3,
+            ApplicationExceptionKind::BadSequenceId => 4,
+            ApplicationExceptionKind::MissingResult => 5,
+            ApplicationExceptionKind::InternalError => 6,
+            ApplicationExceptionKind::ProtocolError => 7,
+            ApplicationExceptionKind::InvalidTransform => 8,
+            ApplicationExceptionKind::InvalidProtocol => 9,
+            ApplicationExceptionKind::UnsupportedClientType => 10,
+            ApplicationExceptionKind::Other(code) => code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BinaryDecode, BinaryEncode};
+
+    #[test]
+    fn application_exception_works() {
+        let exception =
+            ApplicationException::new(ApplicationExceptionKind::BadSequenceId, "unexpected");
+        let message = Message::application_exception("foo", 3, exception.clone());
+        let mut buf = Vec::new();
+        track_try_unwrap!(message.binary_encode(&mut buf));
+        let decoded = track_try_unwrap!(Message::binary_decode(&mut &buf[..]));
+        assert_eq!(decoded.to_application_exception(), Some(exception));
+        assert_eq!(
+            decoded.body().get_i32(2).ok(),
+            Some(Some(ApplicationExceptionKind::BadSequenceId.code()))
+        );
+
+        let e = track_try_unwrap!(ApplicationException::try_from(Struct::from(("oops", 100))));
+        assert_eq!(e.kind(), ApplicationExceptionKind::Other(100));
+        assert_eq!(e.to_string(), "Other(100): oops");
+        assert_eq!(Struct::from(e), Struct::from(("oops", 100)));
+
+        let e = track_try_unwrap!(ApplicationException::try_from(Struct::from(("oops",))));
+        assert_eq!(e.kind(), ApplicationExceptionKind::Unknown);
+        assert_eq!(e.to_string(), "Unknown: oops");
+
+        for code in -1..12 {
+            assert_eq!(ApplicationExceptionKind::from_code(code).code(), code);
+        }
+
+        let reply = Message::reply("foo", 1, Struct::success_result(1));
+        assert_eq!(reply.to_application_exception(), None);
+        assert_eq!(
+            track_try_unwrap!(reply.into_reply_body()),
+            Struct::success_result(1)
+        );
+        let call = Message::call("foo", 1, Struct::void_result());
+        assert_eq!(
+            *call.into_reply_body().unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,700 @@
+use crate::data::Struct;
+use crate::message::Message;
+use crate::rpc::scanner::MessageScanner;
+use crate::rpc::{Protocol, Transport, DEFAULT_MAX_MESSAGE_SIZE};
+use crate::{Error, ErrorKind, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use trackable::error::ErrorKindExt;
+
+/// The minimum byte size of the buffer given to a read.
+const MIN_READ_SIZE: usize = 4096;
+
+/// Builder for [`AsyncClient`].
+#[derive(Debug, Clone)]
+pub struct AsyncClientBuilder {
+    protocol: Protocol,
+    transport: Transport,
+    max_message_size: usize,
+    timeout: Option<Duration>,
+}
+impl AsyncClientBuilder {
+    /// Makes a new `AsyncClientBuilder` instance with the default settings
+    /// (the binary protocol, the unframed transport, `DEFAULT_MAX_MESSAGE_SIZE` and no timeout).
+    pub fn new() -> Self {
+        AsyncClientBuilder {
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            timeout: None,
+        }
+    }
+
+    /// Sets the protocol used to encode requests and decode replies.
+    pub fn protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the transport used to write requests and read replies.
+    pub fn transport(&mut self, transport: Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the maximum byte size of a reply (including the frame header if framed).
+    ///
+    /// If a larger reply is received, the connection fails.
+    pub fn max_message_size(&mut self, size: usize) -> &mut Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Sets the default timeout of calls.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Builds an `AsyncClient` instance which communicates over `stream`.
+    ///
+    /// This spawns the background tasks which read and write `stream`,
+    /// so it must be called within the context of a Tokio runtime.
+    pub fn build<S>(&self, stream: S) -> AsyncClient
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (reader, writer) = tokio::io::split(stream);
+        let shared = Arc::new(Shared::default());
+        let (requests, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_loop(writer, rx, Arc::clone(&shared)));
+        let reader = tokio::spawn(read_loop(
+            ReplyReader {
+                reader,
+                protocol: self.protocol,
+                transport: self.transport,
+                max_message_size: self.max_message_size,
+                buf: Vec::new(),
+                scanner: MessageScanner::new(self.protocol),
+            },
+            Arc::clone(&shared),
+        ));
+        AsyncClient {
+            inner: Arc::new(Inner {
+                protocol: self.protocol,
+                transport: self.transport,
+                timeout: self.timeout,
+                next_sequence_id: AtomicI32::new(1),
+                requests,
+                shared,
+                reader,
+            }),
+        }
+    }
+}
+impl Default for AsyncClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asynchronous RPC client which pipelines requests over a single connection.
+///
+/// Any number of calls can be outstanding at the same time;
+/// replies are matched with the callers by their sequence identifiers.
+/// The client is cheaply cloneable, and the clones share the same connection.
+///
+/// A call fails if its timeout expires, and the pending call is forgotten if its future is dropped.
+/// If the connection fails, all the pending and subsequent calls fail with the same error.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> thrift_codec::Result<()> {
+/// use std::time::Duration;
+/// use thrift_codec::data::Struct;
+/// use thrift_codec::rpc::{AsyncClientBuilder, Transport};
+/// use tokio::net::TcpStream;
+///
+/// let stream = TcpStream::connect("127.0.0.1:9090").await.unwrap();
+/// let client = AsyncClientBuilder::new()
+///     .transport(Transport::Framed)
+///     .timeout(Duration::from_secs(1))
+///     .build(stream);
+///
+/// let (a, b) = tokio::join!(
+///     client.call("add", Struct::from((1, 2))),
+///     client.call("add", Struct::from((3, 4))),
+/// );
+/// assert_eq!(a?.get_i32(0)?, Some(3));
+/// assert_eq!(b?.get_i32(0)?, Some(7));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AsyncClient {
+    inner: Arc<Inner>,
+}
+impl AsyncClient {
+    /// Makes a new `AsyncClient` instance with the default settings.
+    ///
+    /// See [`AsyncClientBuilder::build`] for more details.
+    pub fn new<S>(stream: S) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        AsyncClientBuilder::new().build(stream)
+    }
+
+    /// Calls the method `method_name` with the arguments `args` and returns the body of the reply.
+    ///
+    /// The default timeout set by [`AsyncClientBuilder::timeout`] is applied.
+    ///
+    /// # Errors
+    ///
+    /// If the reply is a `MessageKind::Exception` message,
+    /// this method will return an error which cause is the `ApplicationException` sent by the server
+    /// (see `Client::call` for the other errors on replies).
+    /// If the timeout expires or the connection fails,
+    /// this method will return an error which kind is `ErrorKind::Other`.
+    pub async fn call(&self, method_name: &str, args: Struct) -> Result<Struct> {
+        track!(self.call_inner(method_name, args, self.inner.timeout).await)
+    }
+
+    /// Calls the method `method_name` with the arguments `args` and the timeout `timeout`.
+    ///
+    /// See [`AsyncClient::call`] for more details.
+    pub async fn call_with_timeout(
+        &self,
+        method_name: &str,
+        args: Struct,
+        timeout: Duration,
+    ) -> Result<Struct> {
+        track!(self.call_inner(method_name, args, Some(timeout)).await)
+    }
+
+    /// Calls the oneway method `method_name` with the arguments `args`.
+    ///
+    /// This method returns as soon as the request is queued for sending.
+    pub async fn oneway(&self, method_name: &str, args: Struct) -> Result<()> {
+        let sequence_id = self.next_sequence_id();
+        let request = Message::oneway(method_name, sequence_id, args);
+        track!(self.send(&request))
+    }
+
+    async fn call_inner(
+        &self,
+        method_name: &str,
+        args: Struct,
+        timeout: Option<Duration>,
+    ) -> Result<Struct> {
+        let sequence_id = self.next_sequence_id();
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut state = self.inner.shared.lock();
+            if let Some(e) = &state.error {
+                return Err(track!(e.clone()));
+            }
+            state.pending.insert(sequence_id, tx);
+        }
+        let _guard = PendingGuard {
+            shared: &self.inner.shared,
+            sequence_id,
+        };
+
+        let request = Message::call(method_name, sequence_id, args);
+        track!(self.send(&request))?;
+        let reply = match timeout {
+            None => rx.await,
+            Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+                Ok(reply) => reply,
+                Err(_) => track_panic!(
+                    ErrorKind::Other,
+                    "Timed out: method={:?}, sequence_id={}",
+                    method_name,
+                    sequence_id
+                ),
+            },
+        };
+        let reply = match reply {
+            Ok(reply) => track!(reply)?,
+            Err(_) => return Err(track!(self.inner.shared.error())),
+        };
+        track_assert_eq!(
+            reply.method_name(),
+            method_name,
+            ErrorKind::InvalidInput,
+            "Unexpected method name: sequence_id={}",
+            sequence_id
+        );
+        track!(reply.into_reply_body())
+    }
+
+    fn send(&self, request: &Message) -> Result<()> {
+        let mut buf = Vec::new();
+        track!(self
+            .inner
+            .transport
+            .write_message(self.inner.protocol, request, &mut buf))?;
+        if self.inner.requests.send(buf).is_err() {
+            return Err(track!(self.inner.shared.error()));
+        }
+        Ok(())
+    }
+
+    fn next_sequence_id(&self) -> i32 {
+        self.inner.next_sequence_id.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    protocol: Protocol,
+    transport: Transport,
+    timeout: Option<Duration>,
+    next_sequence_id: AtomicI32,
+    requests: mpsc::UnboundedSender<Vec<u8>>,
+    shared: Arc<Shared>,
+    reader: JoinHandle<()>,
+}
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+}
+impl Shared {
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn error(&self) -> Error {
+        self.lock()
+            .error
+            .clone()
+            .unwrap_or_else(|| ErrorKind::Other.cause("Connection closed").into())
+    }
+
+    fn fail(&self, error: Error) {
+        let mut state = self.lock();
+        let error = state.error.get_or_insert(error).clone();
+        for (_, tx) in state.pending.drain() {
+            let _ = tx.send(Err(error.clone()));
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    pending: HashMap<i32, oneshot::Sender<Result<Message>>>,
+    error: Option<Error>,
+}
+
+/// Forgets the pending call when the call completes, times out or is cancelled.
+struct PendingGuard<'a> {
+    shared: &'a Shared,
+    sequence_id: i32,
+}
+impl<'a> Drop for PendingGuard<'a> {
+    fn drop(&mut self) {
+        self.shared.lock().pending.remove(&self.sequence_id);
+    }
+}
+
+async fn write_loop<W>(mut writer: W, mut rx: mpsc::UnboundedReceiver<Vec<u8>>, shared: Arc<Shared>)
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(buf) = rx.recv().await {
+        let result = async {
+            writer.write_all(&buf).await?;
+            writer.flush().await
+        };
+        if let Err(e) = result.await {
+            shared.fail(track!(Error::from(e)));
+            return;
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+async fn read_loop<R>(mut reader: ReplyReader<R>, shared: Arc<Shared>)
+where
+    R: AsyncRead + Unpin,
+{
+    loop {
+        match reader.read_reply().await {
+            Ok(Some(reply)) => {
+                // Replies to the calls which are no longer pending (e.g., timed out or cancelled) are discarded.
+                let tx = shared.lock().pending.remove(&reply.sequence_id());
+                if let Some(tx) = tx {
+                    let _ = tx.send(Ok(reply));
+                }
+            }
+            Ok(None) => {
+                shared.fail(ErrorKind::Other.cause("Connection closed").into());
+                return;
+            }
+            Err(e) => {
+                shared.fail(track!(e));
+                return;
+            }
+        }
+    }
+}
+
+/// Reads replies from a stream.
+#[derive(Debug)]
+struct ReplyReader<R> {
+    reader: R,
+    protocol: Protocol,
+    transport: Transport,
+    max_message_size: usize,
+
+    /// The bytes which have been read but not consumed yet.
+    buf: Vec<u8>,
+
+    /// The scanner of the unframed message at the head of `buf`.
+    scanner: MessageScanner,
+}
+impl<R: AsyncRead + Unpin> ReplyReader<R> {
+    /// Reads a reply.
+    ///
+    /// Returns `None` if the stream reaches EOF at a message boundary.
+    async fn read_reply(&mut self) -> Result<Option<Message>> {
+        let len = match self.transport {
+            Transport::Framed => track!(self.read_frame().await)?,
+            Transport::Unframed => track!(self.read_unframed().await)?,
+        };
+        let Some(len) = len else {
+            return Ok(None);
+        };
+        let reply = track!(self
+            .transport
+            .read_message(self.protocol, &mut &self.buf[..len]))?;
+        self.buf.drain(..len);
+        Ok(Some(reply))
+    }
+
+    /// Reads a frame into `buf` and returns its byte length (including the header).
+    async fn read_frame(&mut self) -> Result<Option<usize>> {
+        if !track!(self.fill(4).await)? {
+            return Ok(None);
+        }
+        let header = [self.buf[0], self.buf[1], self.buf[2], self.buf[3]];
+        let len = i32::from_be_bytes(header);
+        track_assert!(
+            len >= 0,
+            ErrorKind::InvalidInput,
+            "Negative frame size: {}",
+            len
+        );
+        let len = len as usize + 4;
+        track_assert!(
+            len <= self.max_message_size,
+            ErrorKind::Other,
+            "Too large reply: {} bytes",
+            len
+        );
+        track_assert!(
+            track!(self.fill(len).await)?,
+            ErrorKind::Other,
+            "Connection closed in the middle of a message"
+        );
+        Ok(Some(len))
+    }
+
+    /// Reads bytes into `buf` until it holds an unframed message, and returns its byte length.
+    async fn read_unframed(&mut self) -> Result<Option<usize>> {
+        loop {
+            let len = track!(self.scanner.scan(&self.buf))?;
+            track_assert!(
+                len.unwrap_or(self.buf.len()) <= self.max_message_size,
+                ErrorKind::Other,
+                "Too large reply: more than {} bytes",
+                self.max_message_size
+            );
+            if len.is_some() {
+                return Ok(len);
+            }
+            if !track!(self.fill(self.buf.len() + 1).await)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Reads bytes until `buf` holds at least `len` bytes.
+    ///
+    /// Returns `false` if the stream reaches EOF while `buf` is empty.
+    async fn fill(&mut self, len: usize) -> Result<bool> {
+        while self.buf.len() < len {
+            self.buf.reserve((len - self.buf.len()).max(MIN_READ_SIZE));
+            let n = track!(self
+                .reader
+                .read_buf(&mut self.buf)
+                .await
+                .map_err(Error::from))?;
+            if n == 0 {
+                track_assert!(
+                    self.buf.is_empty(),
+                    ErrorKind::Other,
+                    "Connection closed in the middle of a message"
+                );
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::List;
+    use crate::message::{ApplicationException, ApplicationExceptionKind};
+    use crate::rpc::{Processor, Router};
+    use std::io::BufReader;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn spawn_server(protocol: Protocol, transport: Transport) -> std::net::SocketAddr {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        thread::spawn(move || {
+            let (stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+            let mut reader = BufReader::new(track_try_unwrap!(track_io!(stream.try_clone())));
+            let mut writer = stream;
+            let mut router = Router::new();
+            router.method("sleep", |args: Struct| -> Result<Struct> {
+                let millis = args.get_i64(1)?.unwrap_or(0);
+                thread::sleep(Duration::from_millis(millis as u64));
+                Ok(Struct::success_result(millis))
+            });
+            let mut processor = Processor::new(router);
+            processor.protocol(protocol).transport(transport);
+            let _ = processor.serve(&mut reader, &mut writer);
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn async_client_works() {
+        for (protocol, transport) in [
+            (Protocol::Binary, Transport::Unframed),
+            (Protocol::Compact, Transport::Framed),
+        ] {
+            let addr = spawn_server(protocol, transport);
+            let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+            let client = AsyncClientBuilder::new()
+                .protocol(protocol)
+                .transport(transport)
+                .build(stream);
+
+            let calls = (0..10)
+                .map(|i| {
+                    let client = client.clone();
+                    tokio::spawn(async move {
+                        client.call("sleep", Struct::from((i64::from(i),))).await
+                    })
+                })
+                .collect::<Vec<_>>();
+            for (i, call) in calls.into_iter().enumerate() {
+                let result = track_try_unwrap!(call.await.unwrap());
+                assert_eq!(result.get_i64(0).ok(), Some(Some(i as i64)));
+            }
+
+            let e = client
+                .call("unknown", Struct::void_result())
+                .await
+                .unwrap_err();
+            let cause = e.concrete_cause::<ApplicationException>().unwrap();
+            assert_eq!(cause.kind(), ApplicationExceptionKind::UnknownMethod);
+
+            let e = client
+                .call_with_timeout("sleep", Struct::from((200i64,)), Duration::from_millis(10))
+                .await
+                .unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::Other);
+            assert!(client.inner.shared.lock().pending.is_empty());
+            let result = track_try_unwrap!(client.call("sleep", Struct::from((1i64,))).await);
+            assert_eq!(result.get_i64(0).ok(), Some(Some(1)));
+            track_try_unwrap!(client.oneway("sleep", Struct::void_result()).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn async_client_matches_out_of_order_replies() {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let server = thread::spawn(move || {
+            let (mut stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+            let transport = Transport::Framed;
+            let mut requests = (0..3)
+                .map(|_| track_try_unwrap!(transport.read_message(Protocol::Binary, &mut stream)))
+                .collect::<Vec<_>>();
+            requests.reverse();
+            for request in requests {
+                let reply = Message::reply(
+                    request.method_name(),
+                    request.sequence_id(),
+                    Struct::success_result(request.body().clone()),
+                );
+                track_try_unwrap!(transport.write_message(Protocol::Binary, &reply, &mut stream));
+            }
+        });
+
+        let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+        let client = AsyncClientBuilder::new()
+            .transport(Transport::Framed)
+            .build(stream);
+        let (a, b, c) = tokio::join!(
+            client.call("foo", Struct::from((1,))),
+            client.call("foo", Struct::from((2,))),
+            client.call("foo", Struct::from((3,))),
+        );
+        for (result, i) in [(a, 1), (b, 2), (c, 3)] {
+            let result = track_try_unwrap!(result);
+            assert_eq!(result.result_success(), Some(&Struct::from((i,)).into()));
+        }
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn async_client_propagates_connection_failures() {
+        let listener = track_try_unwrap!(track_io!(
+            tokio::net::TcpListener::bind("127.0.0.1:0").await
+        ));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1];
+            let _ = stream.read(&mut buf).await;
+        });
+        let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+        let client = AsyncClient::new(stream);
+
+        let calls = (0..3)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.call("foo", Struct::void_result()).await })
+            })
+            .collect::<Vec<_>>();
+        for call in calls {
+            let e = call.await.unwrap().unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::Other);
+        }
+        let e = client.call("foo", Struct::void_result()).await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Other);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn async_client_discards_replies_to_cancelled_calls() {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let (resume, resumed) = std::sync::mpsc::channel();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+            let transport = Transport::Framed;
+            for _ in 0..2 {
+                let request =
+                    track_try_unwrap!(transport.read_message(Protocol::Binary, &mut stream));
+                if request.sequence_id() == 1 {
+                    resumed.recv().unwrap();
+                }
+                let reply = Message::reply(
+                    request.method_name(),
+                    request.sequence_id(),
+                    Struct::success_result(request.sequence_id()),
+                );
+                track_try_unwrap!(transport.write_message(Protocol::Binary, &reply, &mut stream));
+            }
+        });
+
+        let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+        let client = AsyncClientBuilder::new()
+            .transport(Transport::Framed)
+            .build(stream);
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(50),
+            client.call("foo", Struct::void_result()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert!(client.inner.shared.lock().pending.is_empty());
+
+        // The late reply to the cancelled call is sent before the reply to the next call.
+        resume.send(()).unwrap();
+        let result = track_try_unwrap!(client.call("foo", Struct::void_result()).await);
+        assert_eq!(result.get_i32(0).ok(), Some(Some(2)));
+        assert!(client.inner.shared.lock().pending.is_empty());
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn async_client_rejects_too_large_replies() {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let (finish, finished) = std::sync::mpsc::channel::<()>();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+            let _ =
+                track_try_unwrap!(Transport::Framed.read_message(Protocol::Binary, &mut stream));
+            let mut frame = vec![0x7f, 0xff, 0xff, 0xff];
+            frame.extend_from_slice(&[0; 100]);
+            track_try_unwrap!(track_io!(std::io::Write::write_all(&mut stream, &frame)));
+            let _ = finished.recv();
+        });
+
+        let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+        let client = AsyncClientBuilder::new()
+            .transport(Transport::Framed)
+            .max_message_size(64)
+            .build(stream);
+        let e = client.call("foo", Struct::void_result()).await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Other);
+        let e = client.call("foo", Struct::void_result()).await.unwrap_err();
+        assert_eq!(*e.kind(), ErrorKind::Other);
+        drop(finish);
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn async_client_reads_split_unframed_replies() {
+        for protocol in [Protocol::Binary, Protocol::Compact] {
+            let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+            let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+            let server = thread::spawn(move || {
+                let (mut stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+                let transport = Transport::Unframed;
+                let request = track_try_unwrap!(transport.read_message(protocol, &mut stream));
+                let body = Struct::success_result(List::from(vec!["foo".to_owned(); 100]));
+                let reply = Message::reply(request.method_name(), request.sequence_id(), body);
+                let mut buf = Vec::new();
+                track_try_unwrap!(transport.write_message(protocol, &reply, &mut buf));
+                for chunk in buf.chunks(7) {
+                    track_try_unwrap!(track_io!(std::io::Write::write_all(&mut stream, chunk)));
+                    thread::sleep(Duration::from_micros(100));
+                }
+            });
+
+            let stream = track_try_unwrap!(track_io!(tokio::net::TcpStream::connect(addr).await));
+            let client = AsyncClientBuilder::new().protocol(protocol).build(stream);
+            let result = track_try_unwrap!(client.call("foo", Struct::void_result()).await);
+            assert_eq!(
+                result,
+                Struct::success_result(List::from(vec!["foo".to_owned(); 100]))
+            );
+            server.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use crate::data::Struct;
+use crate::message::Message;
+use crate::rpc::{Protocol, Transport};
+use crate::{ErrorKind, Result};
+use std::io::{BufReader, Read, Write};
+
+/// Blocking RPC client.
+///
+/// A client sends a request over a stream (e.g., `TcpStream`) and waits for the reply, one at a time.
+/// The sequence identifiers of the requests are allocated in increasing order, and
+/// the method name and the sequence identifier of each reply are checked against the request.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::net::TcpStream;
+/// use thrift_codec::data::Struct;
+/// use thrift_codec::rpc::{Client, Protocol, Transport};
+///
+/// let stream = TcpStream::connect("127.0.0.1:9090").unwrap();
+/// let mut client = Client::new(stream);
+/// client.protocol(Protocol::Compact).transport(Transport::Framed);
+///
+/// let result = client.call("add", Struct::from((1, 2))).unwrap();
+/// assert_eq!(result.get_i32(0).unwrap(), Some(3));
+/// client.oneway("log", Struct::from(("hello",))).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Client<T> {
+    stream: BufReader<T>,
+    protocol: Protocol,
+    transport: Transport,
+    next_sequence_id: i32,
+}
+impl<T: Read + Write> Client<T> {
+    /// Makes a new `Client` instance which uses the binary protocol and the unframed transport.
+    pub fn new(stream: T) -> Self {
+        Client {
+            stream: BufReader::new(stream),
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+            next_sequence_id: 1,
+        }
+    }
+
+    /// Sets the protocol used to encode requests and decode replies.
+    pub fn protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the transport used to write requests and read replies.
+    pub fn transport(&mut self, transport: Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Returns a reference to the underlying stream.
+    pub fn stream(&self) -> &T {
+        self.stream.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying stream.
+    ///
+    /// Note that reading from the stream directly may corrupt the replies.
+    pub fn stream_mut(&mut self) -> &mut T {
+        self.stream.get_mut()
+    }
+
+    /// Takes ownership of the underlying stream.
+    ///
+    /// The data buffered by this client but not consumed yet is discarded.
+    pub fn into_stream(self) -> T {
+        self.stream.into_inner()
+    }
+
+    /// Calls the method `method_name` with the arguments `args` and returns the body of the reply.
+    ///
+    /// The returned struct is the `result` struct of the method
+    /// (see `Struct::result_success` and `Struct::result_exception`).
+    ///
+    /// # Errors
+    ///
+    /// If the reply is a `MessageKind::Exception` message,
+    /// this method will return an error which kind is `ErrorKind::Other` and
+    /// which cause is the `ApplicationException` sent by the server.
+    /// If the method name or the sequence identifier of the reply does not match the request,
+    /// this method will return an error which kind is `ErrorKind::InvalidInput`.
+    pub fn call(&mut self, method_name: &str, args: Struct) -> Result<Struct> {
+        let sequence_id = self.next_sequence_id();
+        let request = Message::call(method_name, sequence_id, args);
+        track!(self.send(&request))?;
+
+        let reply = track!(self.transport.read_message(self.protocol, &mut self.stream))?;
+        track_assert_eq!(
+            reply.sequence_id(),
+            sequence_id,
+            ErrorKind::InvalidInput,
+            "Unexpected sequence id: method={:?}",
+            method_name
+        );
+        track_assert_eq!(
+            reply.method_name(),
+            method_name,
+            ErrorKind::InvalidInput,
+            "Unexpected method name: sequence_id={}",
+            sequence_id
+        );
+        track!(reply.into_reply_body())
+    }
+
+    /// Calls the oneway method `method_name` with the arguments `args`.
+    ///
+    /// This method returns as soon as the request is written to the stream.
+    pub fn oneway(&mut self, method_name: &str, args: Struct) -> Result<()> {
+        let sequence_id = self.next_sequence_id();
+        let request = Message::oneway(method_name, sequence_id, args);
+        track!(self.send(&request))
+    }
+
+    fn send(&mut self, request: &Message) -> Result<()> {
+        let stream = self.stream.get_mut();
+        track!(self.transport.write_message(self.protocol, request, stream))?;
+        track_io!(stream.flush())?;
+        Ok(())
+    }
+
+    fn next_sequence_id(&mut self) -> i32 {
+        let sequence_id = self.next_sequence_id;
+        self.next_sequence_id = self.next_sequence_id.wrapping_add(1);
+        sequence_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::{ApplicationException, ApplicationExceptionKind};
+    use crate::rpc::{Processor, Router};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router
+            .method("add", |args: Struct| -> Result<Struct> {
+                let a = args.get_i32(1)?.unwrap_or(0);
+                let b = args.get_i32(2)?.unwrap_or(0);
+                Ok(Struct::success_result(a + b))
+            })
+            .method("log", |_| -> Result<Struct> { Ok(Struct::void_result()) });
+        router
+    }
+
+    #[test]
+    fn client_works() {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let settings = [
+            (Protocol::Binary, Transport::Unframed),
+            (Protocol::Binary, Transport::Framed),
+            (Protocol::Compact, Transport::Unframed),
+            (Protocol::Compact, Transport::Framed),
+        ];
+        let server = thread::spawn(move || {
+            for &(protocol, transport) in &settings {
+                let (stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+                let mut reader =
+                    std::io::BufReader::new(track_try_unwrap!(track_io!(stream.try_clone())));
+                let mut writer = stream;
+                let mut processor = Processor::new(router());
+                processor.protocol(protocol).transport(transport);
+                track_try_unwrap!(processor.serve(&mut reader, &mut writer));
+            }
+        });
+
+        for &(protocol, transport) in &settings {
+            let stream = track_try_unwrap!(track_io!(TcpStream::connect(addr)));
+            let mut client = Client::new(stream);
+            client.protocol(protocol).transport(transport);
+
+            let result = track_try_unwrap!(client.call("add", Struct::from((1, 2))));
+            assert_eq!(result.get_i32(0).ok(), Some(Some(3)));
+            track_try_unwrap!(client.oneway("log", Struct::void_result()));
+            let result = track_try_unwrap!(client.call("add", Struct::from((3, 4))));
+            assert_eq!(result.get_i32(0).ok(), Some(Some(7)));
+
+            let e = client.call("sub", Struct::void_result()).unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::Other);
+            let cause = e.concrete_cause::<ApplicationException>().unwrap();
+            assert_eq!(cause.kind(), ApplicationExceptionKind::UnknownMethod);
+            assert_eq!(client.next_sequence_id, 5);
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn client_rejects_unexpected_replies() {
+        let listener = track_try_unwrap!(track_io!(TcpListener::bind("127.0.0.1:0")));
+        let addr = track_try_unwrap!(track_io!(listener.local_addr()));
+        let server = thread::spawn(move || {
+            let (mut stream, _) = track_try_unwrap!(track_io!(listener.accept()));
+            let transport = Transport::Unframed;
+            for reply in [
+                Message::reply("add", 100, Struct::void_result()),
+                Message::reply("sub", 2, Struct::void_result()),
+            ] {
+                let _ = track_try_unwrap!(transport.read_message(Protocol::Binary, &mut stream));
+                track_try_unwrap!(transport.write_message(Protocol::Binary, &reply, &mut stream));
+            }
+        });
+
+        let stream = track_try_unwrap!(track_io!(TcpStream::connect(addr)));
+        let mut client = Client::new(stream);
+        for _ in 0..2 {
+            let e = client.call("add", Struct::void_result()).unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+        }
+        server.join().unwrap();
+    }
+}
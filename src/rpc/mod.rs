@@ -0,0 +1,168 @@
+//! RPC framework built on top of [`Message`].
+//!
+//! A [`Service`] maps method names to handlers which take the arguments struct of a call and
+//! return the `result` struct (see `Struct::success_result` and `Struct::exception_result`)
+//! or an [`ApplicationException`].
+//! [`Processor`] reads messages from a stream, dispatches them to a service and writes the replies.
+//! [`Client`] is the other side: it sends calls over a stream and waits for the replies.
+//! [`Server`] runs a service on a TCP port, handling each connection by a dedicated thread.
+//! With the `tokio` feature, `AsyncClient` is also available, which pipelines many calls over a single connection.
+//!
+//! Messages are encoded by a [`Protocol`] and written to streams as specified by a [`Transport`].
+//!
+//! # Examples
+//!
+//! ```
+//! use thrift_codec::data::Struct;
+//! use thrift_codec::message::Message;
+//! use thrift_codec::rpc::{Processor, Protocol, Router};
+//!
+//! let mut router = Router::new();
+//! router.method("add", |args: Struct| -> thrift_codec::Result<Struct> {
+//!     let a = args.get_i32(1)?.unwrap_or(0);
+//!     let b = args.get_i32(2)?.unwrap_or(0);
+//!     Ok(Struct::success_result(a + b))
+//! });
+//! let mut processor = Processor::new(router);
+//!
+//! let mut input = Vec::new();
+//! Protocol::Binary
+//!     .encode_message(&Message::call("add", 7, Struct::from((1, 2))), &mut input)
+//!     .unwrap();
+//!
+//! let mut output = Vec::new();
+//! assert!(processor.process(&mut &input[..], &mut output).unwrap());
+//! assert!(!processor.process(&mut &input[input.len()..], &mut output).unwrap());
+//!
+//! let reply = Protocol::Binary.decode_message(&mut &output[..]).unwrap();
+//! assert_eq!(reply, Message::reply("add", 7, Struct::success_result(3)));
+//! ```
+//!
+//! [`Message`]: crate::message::Message
+//! [`ApplicationException`]: crate::message::ApplicationException
+#[cfg(feature = "tokio")]
+pub use self::async_client::{AsyncClient, AsyncClientBuilder};
+pub use self::client::Client;
+pub use self::processor::Processor;
+pub use self::server::{Server, ServerBuilder, DEFAULT_MAX_MESSAGE_SIZE};
+pub use self::service::{HandlerResult, Router, Service};
+
+use crate::message::Message;
+use crate::{BinaryDecode, BinaryEncode, CompactDecode, CompactEncode, ErrorKind, Result};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+#[cfg(feature = "tokio")]
+mod async_client;
+mod client;
+mod processor;
+#[cfg(feature = "tokio")]
+mod scanner;
+mod server;
+mod service;
+
+/// The protocol used to encode messages.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Protocol {
+    /// Binary protocol.
+    #[default]
+    Binary,
+
+    /// Compact protocol.
+    Compact,
+}
+impl Protocol {
+    /// Encodes `message` to `writer` by using this protocol.
+    pub fn encode_message<W: Write>(self, message: &Message, writer: &mut W) -> Result<()> {
+        match self {
+            Protocol::Binary => track!(message.binary_encode(writer)),
+            Protocol::Compact => track!(message.compact_encode(writer)),
+        }
+    }
+
+    /// Decodes a message from `reader` by using this protocol.
+    pub fn decode_message<R: Read>(self, reader: &mut R) -> Result<Message> {
+        match self {
+            Protocol::Binary => track!(Message::binary_decode(reader)),
+            Protocol::Compact => track!(Message::compact_decode(reader)),
+        }
+    }
+}
+
+/// The way to write encoded messages to a stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Transport {
+    /// Messages are written as is (i.e., `TBufferedTransport`).
+    #[default]
+    Unframed,
+
+    /// Each message is prefixed by its byte length as a 4-byte big-endian integer
+    /// (i.e., `TFramedTransport`).
+    Framed,
+}
+impl Transport {
+    /// Encodes `message` by `protocol` and writes it to `writer`.
+    ///
+    /// The message is encoded into an internal buffer first, so it is written to `writer` at once.
+    pub fn write_message<W: Write>(
+        self,
+        protocol: Protocol,
+        message: &Message,
+        writer: &mut W,
+    ) -> Result<()> {
+        let mut buf = Vec::new();
+        if self == Transport::Framed {
+            buf.extend_from_slice(&[0; 4]);
+        }
+        track!(protocol.encode_message(message, &mut buf))?;
+        if self == Transport::Framed {
+            let len = buf.len() - 4;
+            track_assert!(
+                len <= i32::MAX as usize,
+                ErrorKind::InvalidInput,
+                "Too large frame: {} bytes",
+                len
+            );
+            (&mut buf[..4])
+                .write_u32::<BigEndian>(len as u32)
+                .expect("Never fails");
+        }
+        track_io!(writer.write_all(&buf))?;
+        Ok(())
+    }
+
+    /// Reads a message from `reader` and decodes it by `protocol`.
+    pub fn read_message<R: Read>(self, protocol: Protocol, reader: &mut R) -> Result<Message> {
+        match self {
+            Transport::Unframed => track!(protocol.decode_message(reader)),
+            Transport::Framed => {
+                let len = track_io!(reader.read_i32::<BigEndian>())?;
+                track_assert!(
+                    len >= 0,
+                    ErrorKind::InvalidInput,
+                    "Negative frame size: {}",
+                    len
+                );
+                let mut frame = Vec::new();
+                track_io!(reader.take(len as u64).read_to_end(&mut frame))?;
+                track_assert_eq!(
+                    frame.len(),
+                    len as usize,
+                    ErrorKind::InvalidInput,
+                    "Truncated frame"
+                );
+                let mut bytes = &frame[..];
+                let message = track!(protocol.decode_message(&mut bytes))?;
+                track_assert!(
+                    bytes.is_empty(),
+                    ErrorKind::InvalidInput,
+                    "{} trailing bytes in the frame",
+                    bytes.len()
+                );
+                Ok(message)
+            }
+        }
+    }
+}
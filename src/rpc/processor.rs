@@ -0,0 +1,217 @@
+use crate::message::{ApplicationException, ApplicationExceptionKind, Message, MessageKind};
+use crate::rpc::{Protocol, Service, Transport};
+use crate::Result;
+use std::io::{BufRead, Write};
+
+/// Processor which dispatches incoming messages to a [`Service`].
+///
+/// See the [module level documentation](super) for an example.
+#[derive(Debug)]
+pub struct Processor<S> {
+    service: S,
+    protocol: Protocol,
+    transport: Transport,
+}
+impl<S: Service> Processor<S> {
+    /// Makes a new `Processor` instance which uses the binary protocol and the unframed transport.
+    pub fn new(service: S) -> Self {
+        Processor {
+            service,
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+        }
+    }
+
+    /// Sets the protocol used to decode requests and encode replies.
+    pub fn protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the transport used to read requests and write replies.
+    pub fn transport(&mut self, transport: Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Returns a reference to the service of this processor.
+    pub fn service(&self) -> &S {
+        &self.service
+    }
+
+    /// Returns a mutable reference to the service of this processor.
+    pub fn service_mut(&mut self) -> &mut S {
+        &mut self.service
+    }
+
+    /// Takes ownership of the service of this processor.
+    pub fn into_service(self) -> S {
+        self.service
+    }
+
+    /// Handles `message` and returns the reply message to be sent back.
+    ///
+    /// The reply has the same method name and sequence identifier as `message`:
+    ///
+    /// - For a `MessageKind::Call`, the reply is the result returned by the service, or
+    ///   an `ApplicationException` message if the method is unknown or the handler failed.
+    /// - For a `MessageKind::Oneway`, the service is called but nothing is replied.
+    /// - For the other kinds, an `ApplicationException` message of the kind
+    ///   `ApplicationExceptionKind::InvalidMessageType` is replied.
+    pub fn handle_message(&mut self, message: Message) -> Option<Message> {
+        let method_name = message.method_name().to_owned();
+        let sequence_id = message.sequence_id();
+        let exception = |kind, reason: String| {
+            let exception = ApplicationException::new(kind, reason);
+            Some(Message::application_exception(
+                &method_name,
+                sequence_id,
+                exception,
+            ))
+        };
+        match message.kind() {
+            MessageKind::Call => match self.service.call(&method_name, message.into_body()) {
+                Some(Ok(body)) => Some(Message::reply(&method_name, sequence_id, body)),
+                Some(Err(e)) => Some(Message::application_exception(&method_name, sequence_id, e)),
+                None => exception(
+                    ApplicationExceptionKind::UnknownMethod,
+                    format!("Unknown method: {:?}", method_name),
+                ),
+            },
+            MessageKind::Oneway => {
+                let _ = self.service.call(&method_name, message.into_body());
+                None
+            }
+            kind => exception(
+                ApplicationExceptionKind::InvalidMessageType,
+                format!("Unexpected message kind: {:?}", kind),
+            ),
+        }
+    }
+
+    /// Reads a message from `reader`, handles it and writes the reply to `writer`.
+    ///
+    /// Returns `false` if `reader` has reached EOF before reading a message, otherwise `true`.
+    ///
+    /// # Errors
+    ///
+    /// If the message could not be decoded, or an I/O error occurred,
+    /// this method will return an error.
+    /// Since the stream can not be recovered from such errors, the connection should be closed.
+    pub fn process<R: BufRead, W: Write>(
+        &mut self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<bool> {
+        if track_io!(reader.fill_buf())?.is_empty() {
+            return Ok(false);
+        }
+        let message = track!(self.transport.read_message(self.protocol, reader))?;
+        if let Some(reply) = self.handle_message(message) {
+            track!(self.transport.write_message(self.protocol, &reply, writer))?;
+            track_io!(writer.flush())?;
+        }
+        Ok(true)
+    }
+
+    /// Processes messages from `reader` until it reaches EOF.
+    ///
+    /// See [`Processor::process`] for more details.
+    pub fn serve<R: BufRead, W: Write>(&mut self, reader: &mut R, writer: &mut W) -> Result<()> {
+        while track!(self.process(reader, writer))? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Field, Struct};
+    use crate::rpc::Router;
+    use crate::ErrorKind;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn processor_works() {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let logs_clone = Arc::clone(&logs);
+        let mut router = Router::new();
+        router
+            .method("echo", |args: Struct| -> Result<Struct> {
+                let s = args.get_str(1)?.unwrap_or_default();
+                Ok(Struct::success_result(s))
+            })
+            .method("fail", |_| -> Result<Struct> {
+                track_panic!(ErrorKind::InvalidInput, "oops")
+            })
+            .method("log", move |args: Struct| -> Result<Struct> {
+                logs_clone.lock().unwrap().push(args);
+                Ok(Struct::void_result())
+            })
+            .method("reject", |_| {
+                Err(ApplicationException::new(
+                    ApplicationExceptionKind::MissingResult,
+                    "rejected",
+                ))
+            });
+
+        for protocol in [Protocol::Binary, Protocol::Compact] {
+            let requests = [
+                Message::call("echo", 1, Struct::from(("foo",))),
+                Message::oneway("log", 2, Struct::from((10,))),
+                Message::call("unknown", 3, Struct::void_result()),
+                Message::call("fail", 4, Struct::void_result()),
+                Message::reply("echo", 5, Struct::void_result()),
+                Message::call("reject", 6, Struct::void_result()),
+            ];
+            let mut input = Vec::new();
+            for m in &requests {
+                track_try_unwrap!(protocol.encode_message(m, &mut input));
+            }
+
+            let mut processor = Processor::new(&mut router);
+            processor.protocol(protocol);
+            let mut output = Vec::new();
+            track_try_unwrap!(processor.serve(&mut &input[..], &mut output));
+
+            let mut reader = &output[..];
+            let mut replies = Vec::new();
+            while !reader.is_empty() {
+                replies.push(track_try_unwrap!(protocol.decode_message(&mut reader)));
+            }
+            assert_eq!(replies.len(), 5);
+            assert_eq!(
+                replies[0],
+                Message::reply("echo", 1, Struct::success_result("foo"))
+            );
+            let kinds = replies[1..]
+                .iter()
+                .map(|m| {
+                    (
+                        m.sequence_id(),
+                        m.to_application_exception().unwrap().kind(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                kinds,
+                [
+                    (3, ApplicationExceptionKind::UnknownMethod),
+                    (4, ApplicationExceptionKind::InternalError),
+                    (5, ApplicationExceptionKind::InvalidMessageType),
+                    (6, ApplicationExceptionKind::MissingResult),
+                ]
+            );
+            let internal = replies[2].to_application_exception().unwrap();
+            assert_eq!(internal.message(), "InvalidInput (cause; oops)");
+        }
+        assert_eq!(
+            *logs.lock().unwrap(),
+            vec![Struct::new(vec![Field::new(1, 10)]); 2]
+        );
+
+        let mut processor = Processor::new(Router::new());
+        let e = processor.process(&mut &[0xff][..], &mut Vec::new());
+        assert!(e.is_err());
+    }
+}
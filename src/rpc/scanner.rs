@@ -0,0 +1,305 @@
+use crate::constants;
+use crate::data::DataKind;
+use crate::decode::compact_element_kind;
+use crate::rpc::Protocol;
+use crate::{ErrorKind, Result};
+
+/// Incremental scanner which finds the end of an unframed message in a growing buffer.
+///
+/// The scanner keeps its progress between calls,
+/// so each byte is examined only once no matter how the message is split into reads.
+/// It only follows the structure of the message; the contents are validated by the decoder.
+#[derive(Debug)]
+pub(crate) struct MessageScanner {
+    protocol: Protocol,
+    position: usize,
+    stack: Vec<Item>,
+}
+impl MessageScanner {
+    pub(crate) fn new(protocol: Protocol) -> Self {
+        MessageScanner {
+            protocol,
+            position: 0,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Scans `buf` which starts with a message.
+    ///
+    /// `buf` must be the same as the one given to the previous call except that it may be extended.
+    /// Returns the byte length of the message if `buf` holds the whole message.
+    /// Then the scanner is reset for the next message.
+    pub(crate) fn scan(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        if self.position == 0 && self.stack.is_empty() {
+            self.stack.push(Item::Message);
+        }
+        while let Some(item) = self.stack.pop() {
+            let start = self.position;
+            if !track!(self.step(item, buf))? {
+                self.position = start;
+                self.stack.push(item);
+                return Ok(None);
+            }
+        }
+        let len = self.position;
+        self.position = 0;
+        Ok(Some(len))
+    }
+
+    /// Consumes `item` and pushes the items which follow it.
+    ///
+    /// Returns `false` if `buf` does not have enough bytes.
+    fn step(&mut self, item: Item, buf: &[u8]) -> Result<bool> {
+        match item {
+            Item::Message => {
+                // The binary protocol starts with the 4-byte version and kind,
+                // and the compact protocol starts with the protocol id and the kind and version.
+                let (size, rest) = match self.protocol {
+                    Protocol::Binary => (
+                        4,
+                        [Item::Fields, Item::Bytes(4), Item::Value(DataKind::Binary)],
+                    ),
+                    Protocol::Compact => (
+                        2,
+                        [Item::Fields, Item::Value(DataKind::Binary), Item::Varint],
+                    ),
+                };
+                if self.take(buf, size).is_none() {
+                    return Ok(false);
+                }
+                self.stack.extend_from_slice(&rest);
+            }
+            Item::Bytes(n) => {
+                let available = (buf.len() - self.position) as u64;
+                if available == 0 && n > 0 {
+                    return Ok(false);
+                }
+                let skipped = available.min(n);
+                self.position += skipped as usize;
+                if skipped < n {
+                    self.stack.push(Item::Bytes(n - skipped));
+                }
+            }
+            Item::Varint => {
+                if track!(self.varint(buf))?.is_none() {
+                    return Ok(false);
+                }
+            }
+            Item::Value(kind) => return track!(self.value(kind, buf)),
+            Item::Fields => return track!(self.field(buf)),
+            Item::Elements(_, 0) | Item::Entries(_, _, 0) => {}
+            Item::Elements(kind, n) => {
+                self.stack.push(Item::Elements(kind, n - 1));
+                self.stack.push(Item::Value(kind));
+            }
+            Item::Entries(key_kind, value_kind, n) => {
+                self.stack.push(Item::Entries(key_kind, value_kind, n - 1));
+                self.stack.push(Item::Value(value_kind));
+                self.stack.push(Item::Value(key_kind));
+            }
+        }
+        Ok(true)
+    }
+
+    fn field(&mut self, buf: &[u8]) -> Result<bool> {
+        let Some(&[header]) = self.take(buf, 1) else {
+            return Ok(false);
+        };
+        if header == 0 {
+            return Ok(true);
+        }
+        let kind = match self.protocol {
+            Protocol::Binary => {
+                let kind = track_assert_some!(
+                    DataKind::from_u8(header),
+                    ErrorKind::InvalidInput,
+                    "kind={}",
+                    header
+                );
+                if self.take(buf, 2).is_none() {
+                    return Ok(false);
+                }
+                Some(kind)
+            }
+            Protocol::Compact => {
+                if header >> 4 == 0 && track!(self.varint(buf))?.is_none() {
+                    return Ok(false);
+                }
+                match header & 0b1111 {
+                    // The values of boolean fields are embedded in the field headers.
+                    constants::COMPACT_FIELD_BOOLEAN_TRUE
+                    | constants::COMPACT_FIELD_BOOLEAN_FALSE => None,
+                    kind => Some(track!(compact_element_kind(kind))?),
+                }
+            }
+        };
+        self.stack.push(Item::Fields);
+        self.stack.extend(kind.map(Item::Value));
+        Ok(true)
+    }
+
+    fn value(&mut self, kind: DataKind, buf: &[u8]) -> Result<bool> {
+        let next = match (self.protocol, kind) {
+            (_, DataKind::Bool | DataKind::I8) => Item::Bytes(1),
+            (_, DataKind::Double) => Item::Bytes(8),
+            (_, DataKind::Uuid) => Item::Bytes(16),
+            (_, DataKind::Struct) => Item::Fields,
+            (Protocol::Binary, DataKind::I16) => Item::Bytes(2),
+            (Protocol::Binary, DataKind::I32) => Item::Bytes(4),
+            (Protocol::Binary, DataKind::I64) => Item::Bytes(8),
+            (Protocol::Binary, DataKind::Binary) => {
+                let Some(size) = self.take(buf, 4) else {
+                    return Ok(false);
+                };
+                Item::Bytes(track!(binary_size(size))?)
+            }
+            (Protocol::Binary, DataKind::Map) => {
+                let Some(header) = self.take(buf, 6) else {
+                    return Ok(false);
+                };
+                let key_kind = track!(binary_kind(header[0]))?;
+                let value_kind = track!(binary_kind(header[1]))?;
+                Item::Entries(key_kind, value_kind, track!(binary_size(&header[2..]))?)
+            }
+            (Protocol::Binary, DataKind::Set | DataKind::List) => {
+                let Some(header) = self.take(buf, 5) else {
+                    return Ok(false);
+                };
+                let kind = track!(binary_kind(header[0]))?;
+                Item::Elements(kind, track!(binary_size(&header[1..]))?)
+            }
+            (Protocol::Compact, DataKind::I16 | DataKind::I32 | DataKind::I64) => Item::Varint,
+            (Protocol::Compact, DataKind::Binary) => match track!(self.varint(buf))? {
+                None => return Ok(false),
+                Some(size) => Item::Bytes(size),
+            },
+            (Protocol::Compact, DataKind::Map) => {
+                let Some(size) = track!(self.varint(buf))? else {
+                    return Ok(false);
+                };
+                if size == 0 {
+                    return Ok(true);
+                }
+                let Some(&[kinds]) = self.take(buf, 1) else {
+                    return Ok(false);
+                };
+                let key_kind = track!(compact_element_kind(kinds >> 4))?;
+                let value_kind = track!(compact_element_kind(kinds & 0b1111))?;
+                Item::Entries(key_kind, value_kind, size)
+            }
+            (Protocol::Compact, DataKind::Set | DataKind::List) => {
+                let Some(&[size_and_kind]) = self.take(buf, 1) else {
+                    return Ok(false);
+                };
+                let kind = track!(compact_element_kind(size_and_kind & 0b1111))?;
+                let mut size = u64::from(size_and_kind >> 4);
+                if size == 0b1111 {
+                    match track!(self.varint(buf))? {
+                        None => return Ok(false),
+                        Some(n) => size = n,
+                    }
+                }
+                Item::Elements(kind, size)
+            }
+        };
+        self.stack.push(next);
+        Ok(true)
+    }
+
+    fn take<'a>(&mut self, buf: &'a [u8], n: usize) -> Option<&'a [u8]> {
+        let bytes = buf.get(self.position..self.position + n)?;
+        self.position += n;
+        Some(bytes)
+    }
+
+    fn varint(&mut self, buf: &[u8]) -> Result<Option<u64>> {
+        let mut n = 0;
+        for i in 0..10 {
+            let Some(&[b]) = self.take(buf, 1) else {
+                return Ok(None);
+            };
+            n |= u64::from(b & 0b0111_1111) << (i * 7);
+            if b & 0b1000_0000 == 0 {
+                return Ok(Some(n));
+            }
+        }
+        track_panic!(ErrorKind::InvalidInput, "Too long varint")
+    }
+}
+
+/// A part of a message which is not scanned yet.
+#[derive(Debug, Clone, Copy)]
+enum Item {
+    Message,
+    Bytes(u64),
+    Varint,
+    Value(DataKind),
+    Fields,
+    Elements(DataKind, u64),
+    Entries(DataKind, DataKind, u64),
+}
+
+fn binary_kind(kind: u8) -> Result<DataKind> {
+    let kind = track_assert_some!(
+        DataKind::from_u8(kind),
+        ErrorKind::InvalidInput,
+        "kind={}",
+        kind
+    );
+    Ok(kind)
+}
+
+fn binary_size(bytes: &[u8]) -> Result<u64> {
+    let size = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    track_assert!(size >= 0, ErrorKind::InvalidInput, "size={}", size);
+    Ok(size as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Data, Field, List, Map, Set, Struct, Uuid};
+    use crate::message::Message;
+
+    #[test]
+    fn scanner_finds_message_boundaries() {
+        let mut map = Map::empty_of(DataKind::Binary, DataKind::List);
+        track_try_unwrap!(map.insert("foo", List::from(vec![true, false])));
+        track_try_unwrap!(map.insert("bar", List::from(vec![Struct::from((1,))])));
+        let body = Struct::new(vec![
+            Field::new(1, true),
+            Field::new(2, false),
+            Field::new(3, 7i8),
+            Field::new(4, -300i16),
+            Field::new(5, 1 << 20),
+            Field::new(6, -(1i64 << 40)),
+            Field::new(7, 1.5),
+            Field::new(8, "baz"),
+            Field::new(100, Struct::from((map, Set::from(vec![1i64; 20])))),
+            Field::new(200, Map::empty_of(DataKind::I8, DataKind::I8)),
+            Field::new(-1, Data::Uuid(Uuid::new([1; 16]))),
+        ]);
+        let message = Message::call("foo", 12345, body);
+
+        for protocol in [Protocol::Binary, Protocol::Compact] {
+            let mut buf = Vec::new();
+            track_try_unwrap!(protocol.encode_message(&message, &mut buf));
+            let len = buf.len();
+            buf.extend_from_slice(&buf.clone());
+
+            let mut scanner = MessageScanner::new(protocol);
+            for i in 0..len {
+                assert_eq!(track_try_unwrap!(scanner.scan(&buf[..i])), None);
+            }
+            assert_eq!(track_try_unwrap!(scanner.scan(&buf)), Some(len));
+            assert_eq!(track_try_unwrap!(scanner.scan(&buf[len..])), Some(len));
+        }
+    }
+
+    #[test]
+    fn scanner_rejects_invalid_kinds() {
+        let mut scanner = MessageScanner::new(Protocol::Binary);
+        let buf = [0x80, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 99];
+        assert!(scanner.scan(&buf).is_err());
+    }
+}
@@ -0,0 +1,486 @@
+use crate::rpc::{Processor, Protocol, Service, Transport};
+use crate::{Error, Result};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The interval to check the shutdown flag while reading or writing a connection.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The default value of [`ServerBuilder::max_message_size`] (and `AsyncClientBuilder::max_message_size`).
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Builder for [`Server`].
+#[derive(Debug, Clone)]
+pub struct ServerBuilder {
+    protocol: Protocol,
+    transport: Transport,
+    max_message_size: usize,
+    idle_timeout: Option<Duration>,
+}
+impl ServerBuilder {
+    /// Makes a new `ServerBuilder` instance with the default settings
+    /// (the binary protocol, the unframed transport, `DEFAULT_MAX_MESSAGE_SIZE` and no idle timeout).
+    pub fn new() -> Self {
+        ServerBuilder {
+            protocol: Protocol::default(),
+            transport: Transport::default(),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            idle_timeout: None,
+        }
+    }
+
+    /// Sets the protocol used to decode requests and encode replies.
+    pub fn protocol(&mut self, protocol: Protocol) -> &mut Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the transport used to read requests and write replies.
+    pub fn transport(&mut self, transport: Transport) -> &mut Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the maximum byte size of a request (including the frame header if framed).
+    ///
+    /// The connection which sends a larger request is closed.
+    pub fn max_message_size(&mut self, size: usize) -> &mut Self {
+        self.max_message_size = size;
+        self
+    }
+
+    /// Sets the idle timeout of connections.
+    ///
+    /// The connection which sends no request for `timeout` is closed.
+    /// The timeout is also applied to the time to read a request and to write a reply.
+    pub fn idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Starts a server listening on `addr`.
+    ///
+    /// Each connection is handled by a dedicated thread, which processes the requests one by one
+    /// with the service made by `make_service`.
+    pub fn bind<A, F, S>(&self, addr: A, make_service: F) -> Result<Server>
+    where
+        A: ToSocketAddrs,
+        F: Fn() -> S + Send + 'static,
+        S: Service + Send + 'static,
+    {
+        let listener = track_io!(TcpListener::bind(addr))?;
+        let local_addr = track_io!(listener.local_addr())?;
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let config = self.clone();
+        let flag = Arc::clone(&shutdown);
+        let acceptor = thread::spawn(move || accept_loop(listener, config, make_service, flag));
+        Ok(Server {
+            local_addr,
+            shutdown,
+            acceptor: Some(acceptor),
+        })
+    }
+}
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thread-per-connection TCP server for RPC services.
+///
+/// The server stops when [`Server::shutdown`] is called or the `Server` instance is dropped.
+/// On shutdown, the server stops accepting connections,
+/// finishes the requests in progress and closes all the connections
+/// (including the ones in the middle of sending requests).
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::Struct;
+/// use thrift_codec::rpc::{Client, Router, ServerBuilder, Transport};
+/// use std::net::TcpStream;
+///
+/// let server = ServerBuilder::new()
+///     .transport(Transport::Framed)
+///     .bind("127.0.0.1:0", || {
+///         let mut router = Router::new();
+///         router.method("add", |args: Struct| -> thrift_codec::Result<Struct> {
+///             let a = args.get_i32(1)?.unwrap_or(0);
+///             let b = args.get_i32(2)?.unwrap_or(0);
+///             Ok(Struct::success_result(a + b))
+///         });
+///         router
+///     })
+///     .unwrap();
+///
+/// let mut client = Client::new(TcpStream::connect(server.local_addr()).unwrap());
+/// client.transport(Transport::Framed);
+/// let result = client.call("add", Struct::from((1, 2))).unwrap();
+/// assert_eq!(result.get_i32(0).unwrap(), Some(3));
+///
+/// server.shutdown();
+/// server.join();
+/// ```
+#[derive(Debug)]
+pub struct Server {
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    acceptor: Option<JoinHandle<()>>,
+}
+impl Server {
+    /// Returns the address on which this server is listening.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Requests this server to shut down.
+    ///
+    /// This method returns immediately. Use [`Server::join`] to wait for the completion.
+    pub fn shutdown(&self) {
+        if !self.shutdown.swap(true, Ordering::SeqCst) {
+            // Wakes up the acceptor blocked in `accept`.
+            let _ = TcpStream::connect_timeout(&wake_up_addr(self.local_addr), POLL_INTERVAL);
+        }
+    }
+
+    /// Blocks until this server shuts down and all the connections are closed.
+    pub fn join(mut self) {
+        self.join_acceptor();
+    }
+
+    fn join_acceptor(&mut self) {
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.shutdown();
+        self.join_acceptor();
+    }
+}
+
+fn accept_loop<F, S>(
+    listener: TcpListener,
+    config: ServerBuilder,
+    make_service: F,
+    shutdown: Arc<AtomicBool>,
+) where
+    F: Fn() -> S,
+    S: Service + Send + 'static,
+{
+    let mut connections: Vec<JoinHandle<()>> = Vec::new();
+    loop {
+        let result = listener.accept();
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+        match result {
+            Ok((stream, _)) => {
+                let service = make_service();
+                let config = config.clone();
+                let shutdown = Arc::clone(&shutdown);
+                connections.retain(|c| !c.is_finished());
+                connections.push(thread::spawn(move || {
+                    let _ = handle_connection(stream, service, &config, &shutdown);
+                }));
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::Interrupted | io::ErrorKind::ConnectionAborted
+                ) => {}
+            // Backs off from persistent errors (e.g., too many open files).
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    }
+    for connection in connections {
+        let _ = connection.join();
+    }
+}
+
+fn handle_connection<S: Service>(
+    stream: TcpStream,
+    service: S,
+    config: &ServerBuilder,
+    shutdown: &AtomicBool,
+) -> Result<()> {
+    let mut writer = track_io!(stream.try_clone())?;
+    let mut reader = BufReader::new(stream);
+    let mut processor = Processor::new(service);
+    processor
+        .protocol(config.protocol)
+        .transport(config.transport);
+
+    // Reads and writes time out at every `POLL_INTERVAL` so that the shutdown flag is checked
+    // even while a request is being read or a reply is being written.
+    track_io!(reader.get_ref().set_read_timeout(Some(POLL_INTERVAL)))?;
+    track_io!(writer.set_write_timeout(Some(POLL_INTERVAL)))?;
+    let mut last_activity = Instant::now();
+    loop {
+        // Waits for the next request while checking the shutdown flag and the idle timeout.
+        match reader.fill_buf() {
+            Ok([]) => return Ok(()),
+            Ok(_) => {}
+            Err(e) if is_timeout(&e) => {
+                let idle = config
+                    .idle_timeout
+                    .is_some_and(|t| last_activity.elapsed() >= t);
+                if shutdown.load(Ordering::SeqCst) || idle {
+                    return Ok(());
+                }
+                continue;
+            }
+            Err(e) => return Err(track!(Error::from(e))),
+        }
+
+        let mut reader = Polling::new(&mut reader, shutdown, config.idle_timeout);
+        let mut writer = Polling::new(&mut writer, shutdown, config.idle_timeout);
+        let mut limited = (&mut reader).take(config.max_message_size as u64);
+        track!(processor.process(&mut limited, &mut writer))?;
+        last_activity = Instant::now();
+    }
+}
+
+/// Stream which retries timed out reads and writes until the shutdown flag is set or `timeout` expires.
+///
+/// `timeout` is measured from the first time an operation blocks.
+struct Polling<'a, T> {
+    inner: T,
+    shutdown: &'a AtomicBool,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+impl<'a, T> Polling<'a, T> {
+    fn new(inner: T, shutdown: &'a AtomicBool, timeout: Option<Duration>) -> Self {
+        Polling {
+            inner,
+            shutdown,
+            timeout,
+            deadline: None,
+        }
+    }
+
+    fn retry<U, F>(&mut self, mut f: F) -> io::Result<U>
+    where
+        F: FnMut(&mut T) -> io::Result<U>,
+    {
+        loop {
+            match f(&mut self.inner) {
+                Err(e) if is_timeout(&e) => {
+                    let now = Instant::now();
+                    let expired = self
+                        .timeout
+                        .is_some_and(|t| now >= *self.deadline.get_or_insert(now + t));
+                    if self.shutdown.load(Ordering::SeqCst) || expired {
+                        return Err(e);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+impl<'a, T: Read> Read for Polling<'a, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.retry(|inner| inner.read(buf))
+    }
+}
+impl<'a, T: BufRead> BufRead for Polling<'a, T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.retry(|inner| inner.fill_buf().map(|buf| buf.len()))?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+impl<'a, T: Write> Write for Polling<'a, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.retry(|inner| inner.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.retry(|inner| inner.flush())
+    }
+}
+
+/// Returns the address to connect to wake up the listener bound to `addr`.
+fn wake_up_addr(addr: SocketAddr) -> SocketAddr {
+    match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, addr.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, addr.port()).into(),
+        _ => addr,
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Struct;
+    use crate::message::Message;
+    use crate::rpc::{Client, Router};
+    use crate::ErrorKind;
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.method("echo", |args: Struct| -> Result<Struct> {
+            Ok(Struct::success_result(args))
+        });
+        router
+    }
+
+    #[test]
+    fn server_works() {
+        for (protocol, transport) in [
+            (Protocol::Binary, Transport::Framed),
+            (Protocol::Compact, Transport::Unframed),
+        ] {
+            let server = track_try_unwrap!(ServerBuilder::new()
+                .protocol(protocol)
+                .transport(transport)
+                .max_message_size(64)
+                .bind("127.0.0.1:0", router));
+
+            let clients = (0..4)
+                .map(|i| {
+                    let addr = server.local_addr();
+                    thread::spawn(move || {
+                        let stream = track_try_unwrap!(track_io!(TcpStream::connect(addr)));
+                        let mut client = Client::new(stream);
+                        client.protocol(protocol).transport(transport);
+                        for j in 0..3 {
+                            let args = Struct::from((i, j));
+                            let result = track_try_unwrap!(client.call("echo", args.clone()));
+                            assert_eq!(result, Struct::success_result(args));
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for client in clients {
+                client.join().unwrap();
+            }
+
+            let stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+            let mut client = Client::new(stream);
+            client.protocol(protocol).transport(transport);
+            let e = client
+                .call("echo", Struct::from((vec![0u8; 100],)))
+                .unwrap_err();
+            assert_eq!(*e.kind(), ErrorKind::Other);
+
+            server.shutdown();
+            server.join();
+        }
+    }
+
+    #[test]
+    fn server_closes_idle_connections() {
+        let server = track_try_unwrap!(ServerBuilder::new()
+            .idle_timeout(Duration::from_millis(100))
+            .bind("127.0.0.1:0", router));
+        let stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+        let mut client = Client::new(stream);
+        track_try_unwrap!(client.call("echo", Struct::void_result()));
+
+        thread::sleep(Duration::from_millis(300));
+        assert!(client.call("echo", Struct::void_result()).is_err());
+
+        let stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+        let mut client = Client::new(stream);
+        track_try_unwrap!(client.call("echo", Struct::void_result()));
+        drop(server);
+        assert!(client.call("echo", Struct::void_result()).is_err());
+    }
+
+    #[test]
+    fn server_shuts_down_with_stalled_requests() {
+        let server = track_try_unwrap!(ServerBuilder::new().bind("127.0.0.1:0", router));
+        let mut stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+        track_try_unwrap!(track_io!(io::Write::write_all(&mut stream, &[0x80, 0x01])));
+        thread::sleep(Duration::from_millis(200));
+
+        let start = Instant::now();
+        server.shutdown();
+        server.join();
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn server_rejects_requests_declaring_huge_binaries() {
+        let server = track_try_unwrap!(ServerBuilder::new()
+            .transport(Transport::Framed)
+            .max_message_size(64)
+            .bind("127.0.0.1:0", router));
+
+        // A 10-byte frame which contains a method name of `i32::MAX` bytes.
+        let mut stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+        let frame = [
+            0, 0, 0, 10, 0x80, 0x01, 0, 1, 0x7F, 0xFF, 0xFF, 0xFF, b'a', b'b',
+        ];
+        track_try_unwrap!(track_io!(io::Write::write_all(&mut stream, &frame)));
+        let mut buf = Vec::new();
+        let _ = stream.read_to_end(&mut buf);
+        assert!(buf.is_empty());
+
+        let stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+        let mut client = Client::new(stream);
+        client.transport(Transport::Framed);
+        track_try_unwrap!(client.call("echo", Struct::void_result()));
+    }
+
+    #[test]
+    fn server_shuts_down_with_stalled_replies() {
+        let server = track_try_unwrap!(ServerBuilder::new().bind("127.0.0.1:0", router));
+        let stream = track_try_unwrap!(track_io!(TcpStream::connect(server.local_addr())));
+
+        // Sends requests but never reads the replies.
+        let mut writer = track_try_unwrap!(track_io!(stream.try_clone()));
+        thread::spawn(move || {
+            let request = Message::call("echo", 0, Struct::from((vec![0u8; 1024 * 1024],)));
+            for _ in 0..64 {
+                let result =
+                    Transport::Unframed.write_message(Protocol::Binary, &request, &mut writer);
+                if result.is_err() {
+                    break;
+                }
+            }
+        });
+        thread::sleep(Duration::from_millis(500));
+
+        let start = Instant::now();
+        server.shutdown();
+        server.join();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        drop(stream);
+    }
+
+    #[test]
+    fn server_accepts_connections_without_polling_delay() {
+        let server = track_try_unwrap!(ServerBuilder::new().bind("0.0.0.0:0", router));
+        let addr = wake_up_addr(server.local_addr());
+        let start = Instant::now();
+        for _ in 0..10 {
+            let stream = track_try_unwrap!(track_io!(TcpStream::connect(addr)));
+            let mut client = Client::new(stream);
+            track_try_unwrap!(client.call("echo", Struct::void_result()));
+        }
+        assert!(start.elapsed() < Duration::from_millis(250));
+        drop(server);
+    }
+}
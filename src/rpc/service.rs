@@ -0,0 +1,85 @@
+use crate::data::Struct;
+use crate::message::{ApplicationException, ApplicationExceptionKind};
+use crate::Error;
+use std::collections::HashMap;
+
+/// The result of a method handler.
+///
+/// The `Ok` value is the `result` struct of the call (i.e., the body of the reply message).
+/// Declared exceptions are also returned as `Ok` (see `Struct::exception_result`).
+pub type HandlerResult = std::result::Result<Struct, ApplicationException>;
+
+/// RPC service.
+pub trait Service {
+    /// Handles the call of the method `method_name` with the arguments `args`.
+    ///
+    /// If this service does not provide the method, this returns `None`.
+    fn call(&mut self, method_name: &str, args: Struct) -> Option<HandlerResult>;
+}
+impl<S: Service + ?Sized> Service for &mut S {
+    fn call(&mut self, method_name: &str, args: Struct) -> Option<HandlerResult> {
+        (**self).call(method_name, args)
+    }
+}
+impl<S: Service + ?Sized> Service for Box<S> {
+    fn call(&mut self, method_name: &str, args: Struct) -> Option<HandlerResult> {
+        (**self).call(method_name, args)
+    }
+}
+
+type Handler = Box<dyn FnMut(Struct) -> HandlerResult + Send>;
+
+/// A [`Service`] which dispatches calls to the handlers registered by the method names.
+///
+/// The errors returned by handlers are converted to `ApplicationException`s.
+/// If the cause of an error is an `ApplicationException`, it is used as is;
+/// otherwise an exception of the kind `ApplicationExceptionKind::InternalError` is made.
+#[derive(Default)]
+pub struct Router {
+    handlers: HashMap<String, Handler>,
+}
+impl Router {
+    /// Makes a new empty `Router` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for the method `method_name`.
+    ///
+    /// If a handler is already registered for the method, it is replaced.
+    pub fn method<F, E>(&mut self, method_name: &str, mut handler: F) -> &mut Self
+    where
+        F: FnMut(Struct) -> std::result::Result<Struct, E> + Send + 'static,
+        E: Into<Error>,
+    {
+        let handler = move |args| handler(args).map_err(|e| to_exception(e.into()));
+        self.handlers
+            .insert(method_name.to_owned(), Box::new(handler));
+        self
+    }
+
+    /// Returns `true` if this router has the handler for the method `method_name`.
+    pub fn contains(&self, method_name: &str) -> bool {
+        self.handlers.contains_key(method_name)
+    }
+}
+impl Service for Router {
+    fn call(&mut self, method_name: &str, args: Struct) -> Option<HandlerResult> {
+        let handler = self.handlers.get_mut(method_name)?;
+        Some(handler(args))
+    }
+}
+impl std::fmt::Debug for Router {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut methods = self.handlers.keys().collect::<Vec<_>>();
+        methods.sort();
+        f.debug_struct("Router").field("methods", &methods).finish()
+    }
+}
+
+fn to_exception(error: Error) -> ApplicationException {
+    match error.concrete_cause::<ApplicationException>() {
+        Some(e) => e.clone(),
+        None => ApplicationException::new(ApplicationExceptionKind::InternalError, error.reason()),
+    }
+}
@@ -0,0 +1,120 @@
+//! Human readable text representation of data and messages.
+//!
+//! The representation is similar to the output of Thrift's `TDebugProtocol`,
+//! but every value is annotated with enough kind information to restore the original data:
+//!
+//! | Kind       | Example                                        |
+//! |------------|------------------------------------------------|
+//! | `bool`     | `true`                                         |
+//! | `i8`       | `i8:1`                                         |
+//! | `i16`      | `i16:-2`                                       |
+//! | `i32`      | `3`                                            |
+//! | `i64`      | `i64:4`                                        |
+//! | `double`   | `5.0`, `NaN`, `-inf`                           |
+//! | `binary`   | `"foo"` (valid UTF-8), `0x00ff` (otherwise)    |
+//! | `uuid`     | `uuid:67e55044-10b1-426f-9247-bb680e5fe0c8`    |
+//! | `struct`   | `struct { 1: "foo", 3: i64:4 }`                |
+//! | `map`      | `map<binary, i32>{ "foo": 1, "bar": 2 }`       |
+//! | `set`      | `set<i8>[i8:1, i8:2]`                          |
+//! | `list`     | `list<struct>[struct { 1: true }]`             |
+//!
+//! Quoted strings are parsed as `Data::String`, and hexadecimal literals as `Data::Binary`.
+//!
+//! A message is rendered as its kind, method name, sequence identifier and body
+//! (e.g., `call emitBatch #1 { 1: struct { 1: "svc" } }`).
+//!
+//! The `Display` implementations of [`Data`], [`Struct`], [`Message`] and so on use this representation.
+//! The alternate flag (`{:#}`) enables the indented multi-line layout.
+//! The representation can be parsed by [`parse_data`] and [`parse_message`] (or `str::parse`).
+//!
+//! [`Data`]: crate::data::Data
+//! [`Struct`]: crate::data::Struct
+//! [`Message`]: crate::message::Message
+pub use self::printer::Printer;
+
+use self::parser::Parser;
+use crate::data::Data;
+use crate::message::Message;
+use crate::{Error, Result};
+use std::str::FromStr;
+
+mod parser;
+mod printer;
+
+/// Parses the text representation of a datum.
+///
+/// The parser accepts the output of [`Printer`] (unless the elements are truncated) and
+/// restores the original data exactly.
+/// In addition, the following notations are accepted for writing data by hand:
+///
+/// - `i32:` prefix for `i32` values,
+/// - `string` and `byte` as aliases of `binary` and `i8` kinds,
+/// - trailing commas,
+/// - line comments starting with `//`.
+///
+/// # Errors
+///
+/// If `text` is malformed, this function will return an error which kind is `ErrorKind::InvalidInput`.
+/// The error message contains the position (`line:column`) where the problem is found.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{Data, Field, List, Struct};
+/// use thrift_codec::text;
+///
+/// let data = text::parse_data(r#"struct { 1: "foo", 2: list<i16>[i16:1, i16:2] }"#).unwrap();
+/// assert_eq!(
+///     data,
+///     Data::Struct(Struct::new(vec![
+///         Field::new(1, "foo"),
+///         Field::new(2, List::from(vec![1i16, 2])),
+///     ]))
+/// );
+/// assert_eq!(text::parse_data(&data.to_string()).unwrap(), data);
+///
+/// let e = text::parse_data("struct {\n  1: i8:1000\n}").unwrap_err();
+/// assert!(e.to_string().contains("2:9"));
+/// ```
+pub fn parse_data(text: &str) -> Result<Data> {
+    track!(Parser::new(text).parse_data())
+}
+
+/// Parses the text representation of a message.
+///
+/// See [`parse_data`] for the details of the syntax.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{Field, Struct};
+/// use thrift_codec::message::Message;
+/// use thrift_codec::text;
+///
+/// let message = text::parse_message(r#"call emitBatch #1 { 1: struct { 1: "svc" } }"#).unwrap();
+/// assert_eq!(
+///     message,
+///     Message::call("emitBatch", 1, Struct::new(vec![
+///         Field::new(1, Struct::new(vec![Field::new(1, "svc")])),
+///     ]))
+/// );
+/// ```
+pub fn parse_message(text: &str) -> Result<Message> {
+    track!(Parser::new(text).parse_message())
+}
+
+impl FromStr for Data {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        track!(parse_data(s))
+    }
+}
+
+impl FromStr for Message {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        track!(parse_message(s))
+    }
+}
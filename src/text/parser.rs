@@ -0,0 +1,541 @@
+use crate::data::{Data, DataKind, Elements, Field, List, Map, Set, Struct, Uuid};
+use crate::message::{Message, MessageKind};
+use crate::text::printer::kind_name;
+use crate::{ErrorKind, Result};
+
+#[derive(Debug)]
+pub struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+impl<'a> Parser<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Parser {
+            text,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    pub fn parse_data(&mut self) -> Result<Data> {
+        let data = track!(self.data())?;
+        track!(self.expect_eos())?;
+        Ok(data)
+    }
+
+    pub fn parse_message(&mut self) -> Result<Message> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let kind = match self.identifier() {
+            "call" => MessageKind::Call,
+            "reply" => MessageKind::Reply,
+            "exception" => MessageKind::Exception,
+            "oneway" => MessageKind::Oneway,
+            s => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: expected a message kind, found {:?}",
+                line,
+                column,
+                s
+            ),
+        };
+
+        track!(self.skip_whitespaces_and_comments())?;
+        let method_name = if self.peek_char() == Some('"') {
+            track!(self.string())?
+        } else {
+            let (line, column) = (self.line, self.column);
+            let name = self.take_while(|c| c.is_ascii_alphanumeric() || "_.:".contains(c));
+            track_assert!(
+                !name.is_empty(),
+                ErrorKind::InvalidInput,
+                "{}:{}: expected a method name",
+                line,
+                column
+            );
+            name.to_owned()
+        };
+
+        track!(self.expect_symbol('#'))?;
+        let sequence_id = track!(self.int::<i32>())?;
+        track!(self.expect_symbol('{'))?;
+        let body = track!(self.fields())?;
+        track!(self.expect_eos())?;
+        Ok(Message::new(&method_name, kind, sequence_id, body))
+    }
+
+    fn data(&mut self) -> Result<Data> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let c = match self.peek_char() {
+            None => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: unexpected end of input",
+                line,
+                column
+            ),
+            Some(c) => c,
+        };
+        if c == '"' {
+            return track!(self.string()).map(Data::String);
+        }
+        if self.rest().starts_with("0x") {
+            return track!(self.hex()).map(Data::Binary);
+        }
+        if c.is_ascii_digit() || c == '-' || c == '+' {
+            return track!(self.number());
+        }
+
+        let data = match self.identifier() {
+            "true" => Data::Bool(true),
+            "false" => Data::Bool(false),
+            "NaN" => Data::Double(f64::NAN),
+            "inf" => Data::Double(f64::INFINITY),
+            "i8" => {
+                track!(self.expect_char(':'))?;
+                Data::I8(track!(self.int())?)
+            }
+            "i16" => {
+                track!(self.expect_char(':'))?;
+                Data::I16(track!(self.int())?)
+            }
+            "i32" => {
+                track!(self.expect_char(':'))?;
+                Data::I32(track!(self.int())?)
+            }
+            "i64" => {
+                track!(self.expect_char(':'))?;
+                Data::I64(track!(self.int())?)
+            }
+            "uuid" => {
+                track!(self.expect_char(':'))?;
+                let (line, column) = (self.line, self.column);
+                let s = self.take_while(|c| c.is_ascii_hexdigit() || c == '-');
+                let uuid = track!(s.parse::<Uuid>(), "{}:{}", line, column)?;
+                Data::Uuid(uuid)
+            }
+            "struct" => {
+                track!(self.expect_symbol('{'))?;
+                Data::Struct(track!(self.fields())?)
+            }
+            "map" => {
+                track!(self.expect_symbol('<'))?;
+                let key_kind = track!(self.kind())?;
+                track!(self.expect_symbol(','))?;
+                let value_kind = track!(self.kind())?;
+                track!(self.expect_symbol('>'))?;
+                track!(self.expect_symbol('{'))?;
+                let mut keys = Elements::new(key_kind);
+                let mut values = Elements::new(value_kind);
+                while !track!(self.eat_symbol('}'))? {
+                    track!(self.element(&mut keys))?;
+                    track!(self.expect_symbol(':'))?;
+                    track!(self.element(&mut values))?;
+                    track!(self.expect_separator('}'))?;
+                }
+                let map = track!(Map::from_keys_and_values(keys, values))?;
+                Data::Map(map)
+            }
+            "set" => Data::Set(Set::new(track!(self.elements())?)),
+            "list" => Data::List(List::new(track!(self.elements())?)),
+            s => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: expected a value, found {:?}",
+                line,
+                column,
+                s
+            ),
+        };
+        Ok(data)
+    }
+
+    fn fields(&mut self) -> Result<Struct> {
+        let mut fields: Vec<Field> = Vec::new();
+        while !track!(self.eat_symbol('}'))? {
+            track!(self.skip_whitespaces_and_comments())?;
+            let (line, column) = (self.line, self.column);
+            let id = track!(self.int::<i16>())?;
+            track_assert!(
+                fields.iter().all(|f| f.id() != id),
+                ErrorKind::InvalidInput,
+                "{}:{}: duplicate field id {}",
+                line,
+                column,
+                id
+            );
+            track!(self.expect_symbol(':'))?;
+            let data = track!(self.data())?;
+            fields.push(Field::new(id, data));
+            track!(self.expect_separator('}'))?;
+        }
+        Ok(Struct::new(fields))
+    }
+
+    fn elements(&mut self) -> Result<Elements> {
+        track!(self.expect_symbol('<'))?;
+        let kind = track!(self.kind())?;
+        track!(self.expect_symbol('>'))?;
+        track!(self.expect_symbol('['))?;
+        let mut elements = Elements::new(kind);
+        while !track!(self.eat_symbol(']'))? {
+            track!(self.element(&mut elements))?;
+            track!(self.expect_separator(']'))?;
+        }
+        Ok(elements)
+    }
+
+    fn element(&mut self, elements: &mut Elements) -> Result<()> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let data = track!(self.data())?;
+        track_assert_eq!(
+            data.kind(),
+            elements.kind(),
+            ErrorKind::InvalidInput,
+            "{}:{}: expected {} element, found {}",
+            line,
+            column,
+            kind_name(elements.kind()),
+            kind_name(data.kind())
+        );
+        track!(elements.push(data))
+    }
+
+    fn kind(&mut self) -> Result<DataKind> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let kind = match self.identifier() {
+            "bool" => DataKind::Bool,
+            "i8" | "byte" => DataKind::I8,
+            "i16" => DataKind::I16,
+            "i32" => DataKind::I32,
+            "i64" => DataKind::I64,
+            "double" => DataKind::Double,
+            "binary" | "string" => DataKind::Binary,
+            "struct" => DataKind::Struct,
+            "map" => DataKind::Map,
+            "set" => DataKind::Set,
+            "list" => DataKind::List,
+            "uuid" => DataKind::Uuid,
+            s => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: expected a kind, found {:?}",
+                line,
+                column,
+                s
+            ),
+        };
+        Ok(kind)
+    }
+
+    fn number(&mut self) -> Result<Data> {
+        let (line, column) = (self.line, self.column);
+        let s = self.take_while(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        if !s.contains(['.', 'e', 'E', 'i', 'N']) {
+            let n = track_assert_some!(
+                s.parse::<i32>().ok(),
+                ErrorKind::InvalidInput,
+                "{}:{}: invalid i32 value {:?}",
+                line,
+                column,
+                s
+            );
+            Ok(Data::I32(n))
+        } else {
+            let n = track_assert_some!(
+                s.parse::<f64>().ok(),
+                ErrorKind::InvalidInput,
+                "{}:{}: invalid double value {:?}",
+                line,
+                column,
+                s
+            );
+            Ok(Data::Double(n))
+        }
+    }
+
+    fn int<T: std::str::FromStr>(&mut self) -> Result<T> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        let s = self.take_while(|c| c.is_ascii_digit() || c == '-' || c == '+');
+        let n = track_assert_some!(
+            s.parse::<T>().ok(),
+            ErrorKind::InvalidInput,
+            "{}:{}: invalid {} value {:?}",
+            line,
+            column,
+            std::any::type_name::<T>(),
+            s
+        );
+        Ok(n)
+    }
+
+    fn hex(&mut self) -> Result<Vec<u8>> {
+        let (line, column) = (self.line, self.column);
+        self.read_char();
+        self.read_char();
+        let s = self.take_while(|c| c.is_ascii_alphanumeric());
+        track_assert!(
+            s.len().is_multiple_of(2) && s.chars().all(|c| c.is_ascii_hexdigit()),
+            ErrorKind::InvalidInput,
+            "{}:{}: invalid hex binary 0x{}",
+            line,
+            column,
+            s
+        );
+        let bytes = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("Never fails"))
+            .collect();
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let (line, column) = (self.line, self.column);
+        self.read_char();
+        let mut s = String::new();
+        loop {
+            let c = match self.read_char() {
+                None => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "{}:{}: unterminated string literal",
+                    line,
+                    column
+                ),
+                Some('"') => break,
+                Some('\\') => track!(self.escape())?,
+                Some(c) => c,
+            };
+            s.push(c);
+        }
+        Ok(s)
+    }
+
+    fn escape(&mut self) -> Result<char> {
+        let (line, column) = (self.line, self.column);
+        let c = match self.read_char() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some('u') if self.peek_char() == Some('{') => {
+                self.read_char();
+                let hex = self.take_while(|c| c.is_ascii_hexdigit());
+                let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+                if self.read_char() == Some('}') {
+                    if let Some(c) = c {
+                        return Ok(c);
+                    }
+                }
+                track_panic!(
+                    ErrorKind::InvalidInput,
+                    "{}:{}: invalid unicode escape",
+                    line,
+                    column
+                );
+            }
+            c => track_panic!(
+                ErrorKind::InvalidInput,
+                "{}:{}: unknown escape sequence {:?}",
+                line,
+                column,
+                c
+            ),
+        };
+        Ok(c)
+    }
+
+    fn identifier(&mut self) -> &'a str {
+        self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn eat_symbol(&mut self, c: char) -> Result<bool> {
+        track!(self.skip_whitespaces_and_comments())?;
+        if self.peek_char() == Some(c) {
+            self.read_char();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect_symbol(&mut self, c: char) -> Result<()> {
+        track!(self.skip_whitespaces_and_comments())?;
+        track!(self.expect_char(c))
+    }
+
+    fn expect_char(&mut self, c: char) -> Result<()> {
+        let (line, column) = (self.line, self.column);
+        let actual = self.peek_char();
+        track_assert_eq!(
+            actual,
+            Some(c),
+            ErrorKind::InvalidInput,
+            "{}:{}: expected {:?}",
+            line,
+            column,
+            c
+        );
+        self.read_char();
+        Ok(())
+    }
+
+    /// Consumes a `,` or checks that the next character is `close`.
+    fn expect_separator(&mut self, close: char) -> Result<()> {
+        if !track!(self.eat_symbol(','))? {
+            let (line, column) = (self.line, self.column);
+            track_assert_eq!(
+                self.peek_char(),
+                Some(close),
+                ErrorKind::InvalidInput,
+                "{}:{}: expected ',' or {:?}",
+                line,
+                column,
+                close
+            );
+        }
+        Ok(())
+    }
+
+    fn expect_eos(&mut self) -> Result<()> {
+        track!(self.skip_whitespaces_and_comments())?;
+        let (line, column) = (self.line, self.column);
+        track_assert!(
+            self.peek_char().is_none(),
+            ErrorKind::InvalidInput,
+            "{}:{}: unexpected trailing characters",
+            line,
+            column
+        );
+        Ok(())
+    }
+
+    fn skip_whitespaces_and_comments(&mut self) -> Result<()> {
+        loop {
+            let rest = self.rest();
+            if rest.starts_with(|c: char| c.is_whitespace()) {
+                self.take_while(char::is_whitespace);
+            } else if rest.starts_with("//") {
+                self.take_while(|c| c != '\n');
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn take_while<F>(&mut self, f: F) -> &'a str
+    where
+        F: Fn(char) -> bool,
+    {
+        let start = self.offset;
+        while self.peek_char().is_some_and(&f) {
+            self.read_char();
+        }
+        &self.text[start..self.offset]
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn read_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::Printer;
+
+    #[test]
+    fn round_trip_works() {
+        let mut map = Map::empty_of(DataKind::Binary, DataKind::Set);
+        track_try_unwrap!(map.insert("a", Set::from(vec![1i8, 2])));
+        track_try_unwrap!(map.insert(vec![0xffu8, 0], Set::from(Vec::<i8>::new())));
+        let body = Struct::new(vec![
+            Field::new(1, true),
+            Field::new(2, -3i16),
+            Field::new(3, i64::MIN),
+            Field::new(4, -0.0),
+            Field::new(5, f64::NEG_INFINITY),
+            Field::new(6, 1e-300),
+            Field::new(7, "a\"b\n\u{1b}\\ あ"),
+            Field::new(8, Uuid::new([0xab; 16])),
+            Field::new(9, map),
+            Field::new(10, List::from(vec![Struct::new(Vec::new())])),
+            Field::new(
+                11,
+                List::from(vec![List::from(vec![1, 2]), List::from(Vec::<i64>::new())]),
+            ),
+            Field::new(-1, i32::MIN),
+        ]);
+        let message = Message::reply("Svc:do.it", i32::MAX, body);
+
+        let mut printer = Printer::new();
+        for pretty in [false, true] {
+            let text = printer.pretty(pretty).width(40).message_to_string(&message);
+            let parsed = track_try_unwrap!(Parser::new(&text).parse_message(), "text={}", text);
+            assert_eq!(parsed, message);
+
+            let data = Data::Struct(message.body().clone());
+            let text = printer.data_to_string(data.as_ref());
+            let parsed = track_try_unwrap!(Parser::new(&text).parse_data(), "text={}", text);
+            assert_eq!(parsed, data);
+        }
+
+        let text = r#"
+            // A hand-written fixture.
+            call "foo bar" #1 {
+              1: list<string>["x", "y",],
+              2: i32:3,
+            }
+        "#;
+        let parsed = track_try_unwrap!(Parser::new(text).parse_message());
+        assert_eq!(
+            parsed,
+            Message::call(
+                "foo bar",
+                1,
+                Struct::new(vec![
+                    Field::new(1, List::from(vec![b"x".to_vec(), b"y".to_vec()])),
+                    Field::new(2, 3)
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn parse_error_has_position() {
+        let cases = [
+            ("struct {\n  1: 2,\n  3: list<i32>[1, i64:2]\n}", "3:19"),
+            ("struct {\n  1: 2\n  3: 4\n}", "3:3"),
+            ("struct { 1: \"foo }", "1:13"),
+            ("map<binary, i32>{ \"a\" 1 }", "1:23"),
+            ("struct { 1: 2, 1: 3 }", "1:16"),
+            ("list<i32>[]]", "1:12"),
+            ("i8:300", "1:4"),
+        ];
+        for (text, position) in cases {
+            let e = Parser::new(text).parse_data().err().unwrap();
+            assert_eq!(*e.kind(), ErrorKind::InvalidInput);
+            assert!(e.to_string().contains(position), "{}", e);
+        }
+    }
+}
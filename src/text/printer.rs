@@ -0,0 +1,416 @@
+use crate::data::{Data, DataKind, DataRef, Elements, List, Map, Set, Struct};
+use crate::message::{Message, MessageKind};
+use std::fmt::{self, Write};
+
+/// Printer which renders data and messages in the text representation.
+///
+/// See [the module documentation](crate::text) for the details of the representation.
+///
+/// # Examples
+///
+/// ```
+/// use thrift_codec::data::{Field, List, Struct};
+/// use thrift_codec::message::Message;
+/// use thrift_codec::text::Printer;
+///
+/// let body = Struct::new(vec![
+///     Field::new(1, "svc"),
+///     Field::new(2, List::from(vec![1, 2, 3, 4])),
+/// ]);
+/// let message = Message::call("emitBatch", 1, body);
+/// assert_eq!(
+///     message.to_string(),
+///     r#"call emitBatch #1 { 1: "svc", 2: list<i32>[1, 2, 3, 4] }"#
+/// );
+///
+/// let mut printer = Printer::new();
+/// printer.pretty(true).width(30).max_elements(2);
+/// assert_eq!(
+///     printer.message_to_string(&message),
+///     r#"call emitBatch #1 {
+///   1: "svc",
+///   2: list<i32>[1, 2, ...]
+/// }"#
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Printer {
+    pretty: bool,
+    indent: usize,
+    width: usize,
+    max_elements: Option<usize>,
+}
+impl Printer {
+    /// The default value of the `indent` parameter.
+    pub const DEFAULT_INDENT: usize = 2;
+
+    /// The default value of the `width` parameter.
+    pub const DEFAULT_WIDTH: usize = 80;
+
+    /// Makes a new `Printer` instance with the default settings.
+    ///
+    /// The default printer renders everything in a single line.
+    pub fn new() -> Self {
+        Printer {
+            pretty: false,
+            indent: Self::DEFAULT_INDENT,
+            width: Self::DEFAULT_WIDTH,
+            max_elements: None,
+        }
+    }
+
+    /// Sets whether to use the indented multi-line layout.
+    ///
+    /// In this layout, the structs, maps, sets and lists which do not fit in the line `width`
+    /// are split into multiple lines.
+    ///
+    /// The default value is `false`.
+    pub fn pretty(&mut self, pretty: bool) -> &mut Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Sets the number of spaces used for a level of indentation.
+    ///
+    /// The default value is `DEFAULT_INDENT`.
+    pub fn indent(&mut self, indent: usize) -> &mut Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets the preferred maximum width of a line in the multi-line layout.
+    ///
+    /// The default value is `DEFAULT_WIDTH`.
+    pub fn width(&mut self, width: usize) -> &mut Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets the maximum number of elements (or entries) rendered for a map, set or list.
+    ///
+    /// The remaining elements are omitted and denoted by `...`.
+    /// Note that the truncated representation cannot be restored to the original data.
+    ///
+    /// By default, all elements are rendered.
+    pub fn max_elements(&mut self, n: usize) -> &mut Self {
+        self.max_elements = Some(n);
+        self
+    }
+
+    /// Writes the text representation of `data` to `writer`.
+    pub fn write_data<W: Write>(&self, writer: &mut W, data: DataRef<'_>) -> fmt::Result {
+        self.data(writer, data, 0, 0)
+    }
+
+    /// Writes the text representation of `message` to `writer`.
+    pub fn write_message<W: Write>(&self, writer: &mut W, message: &Message) -> fmt::Result {
+        let mut header = String::new();
+        let kind = match message.kind() {
+            MessageKind::Call => "call",
+            MessageKind::Reply => "reply",
+            MessageKind::Exception => "exception",
+            MessageKind::Oneway => "oneway",
+        };
+        write!(header, "{} ", kind)?;
+        if is_identifier(message.method_name()) {
+            write!(header, "{}", message.method_name())?;
+        } else {
+            write!(header, "{:?}", message.method_name())?;
+        }
+        write!(header, " #{} ", message.sequence_id())?;
+        writer.write_str(&header)?;
+
+        let body = message.body();
+        let multiline = self.pretty && !self.fits(DataRef::Struct(body), header.len());
+        let items = body
+            .fields()
+            .iter()
+            .map(|f| Item::Field(f.id(), f.data().as_ref()))
+            .collect();
+        self.composite(writer, "{", "}", true, items, 0, 0, multiline)
+    }
+
+    /// Returns the text representation of `data`.
+    pub fn data_to_string(&self, data: DataRef<'_>) -> String {
+        let mut s = String::new();
+        self.write_data(&mut s, data).expect("Never fails");
+        s
+    }
+
+    /// Returns the text representation of `message`.
+    pub fn message_to_string(&self, message: &Message) -> String {
+        let mut s = String::new();
+        self.write_message(&mut s, message).expect("Never fails");
+        s
+    }
+
+    fn from_formatter(f: &fmt::Formatter) -> Self {
+        let mut printer = Printer::new();
+        printer.pretty(f.alternate());
+        printer
+    }
+
+    fn data(
+        &self,
+        w: &mut dyn Write,
+        data: DataRef<'_>,
+        level: usize,
+        column: usize,
+    ) -> fmt::Result {
+        let multiline = self.pretty && !self.fits(data, column);
+        match data {
+            DataRef::Bool(v) => write!(w, "{}", v),
+            DataRef::I8(v) => write!(w, "i8:{}", v),
+            DataRef::I16(v) => write!(w, "i16:{}", v),
+            DataRef::I32(v) => write!(w, "{}", v),
+            DataRef::I64(v) => write!(w, "i64:{}", v),
+            DataRef::Double(v) => write!(w, "{:?}", v),
+            DataRef::Binary(v) => match std::str::from_utf8(v) {
+                Ok(s) => write!(w, "{:?}", s),
+                Err(_) => {
+                    write!(w, "0x")?;
+                    for b in v {
+                        write!(w, "{:02x}", b)?;
+                    }
+                    Ok(())
+                }
+            },
+            DataRef::String(v) => write!(w, "{:?}", v),
+            DataRef::Uuid(v) => write!(w, "uuid:{}", v),
+            DataRef::Struct(v) => {
+                let items = v
+                    .fields()
+                    .iter()
+                    .map(|f| Item::Field(f.id(), f.data().as_ref()))
+                    .collect();
+                self.composite(w, "struct {", "}", true, items, 0, level, multiline)
+            }
+            DataRef::Map(v) => {
+                let open = format!(
+                    "map<{}, {}>{{",
+                    kind_name(v.key_kind()),
+                    kind_name(v.value_kind())
+                );
+                let n = self.max_elements.unwrap_or(usize::MAX);
+                let items = v.iter().take(n).map(|(k, v)| Item::Entry(k, v)).collect();
+                let omitted = v.len().saturating_sub(n);
+                self.composite(w, &open, "}", true, items, omitted, level, multiline)
+            }
+            DataRef::Set(v) => {
+                let open = format!("set<{}>[", kind_name(v.kind()));
+                self.elements(w, &open, v, level, multiline)
+            }
+            DataRef::List(v) => {
+                let open = format!("list<{}>[", kind_name(v.kind()));
+                self.elements(w, &open, v, level, multiline)
+            }
+        }
+    }
+
+    fn elements(
+        &self,
+        w: &mut dyn Write,
+        open: &str,
+        elements: &Elements,
+        level: usize,
+        multiline: bool,
+    ) -> fmt::Result {
+        let n = self.max_elements.unwrap_or(usize::MAX);
+        let items = elements.iter().take(n).map(Item::Element).collect();
+        let omitted = elements.len().saturating_sub(n);
+        self.composite(w, open, "]", false, items, omitted, level, multiline)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn composite(
+        &self,
+        w: &mut dyn Write,
+        open: &str,
+        close: &str,
+        spaced: bool,
+        items: Vec<Item<'_>>,
+        omitted: usize,
+        level: usize,
+        multiline: bool,
+    ) -> fmt::Result {
+        w.write_str(open)?;
+        if items.is_empty() && omitted == 0 {
+            return w.write_str(close);
+        }
+
+        let inner = self.indent * (level + 1);
+        let count = items.len() + usize::from(omitted > 0);
+        for (i, item) in items.into_iter().enumerate() {
+            if multiline {
+                write!(w, "\n{:1$}", "", inner)?;
+            } else if i > 0 || spaced {
+                w.write_str(" ")?;
+            }
+            match item {
+                Item::Field(id, data) => {
+                    let prefix = format!("{}: ", id);
+                    w.write_str(&prefix)?;
+                    self.data(w, data, level + 1, inner + prefix.len())?;
+                }
+                Item::Entry(key, value) => {
+                    let prefix = format!("{}: ", Printer::new().data_to_string(key));
+                    w.write_str(&prefix)?;
+                    self.data(w, value, level + 1, inner + prefix.len())?;
+                }
+                Item::Element(data) => {
+                    self.data(w, data, level + 1, inner)?;
+                }
+            }
+            if i + 1 < count {
+                w.write_str(",")?;
+            }
+        }
+        if omitted > 0 {
+            if multiline {
+                write!(w, "\n{:1$}", "", inner)?;
+            } else if count > 1 || spaced {
+                w.write_str(" ")?;
+            }
+            w.write_str("...")?;
+        }
+
+        if multiline {
+            write!(w, "\n{:1$}", "", self.indent * level)?;
+        } else if spaced {
+            w.write_str(" ")?;
+        }
+        w.write_str(close)
+    }
+
+    /// Returns `true` if the single line representation of `data` fits in the line width.
+    fn fits(&self, data: DataRef<'_>, column: usize) -> bool {
+        let mut compact = self.clone();
+        compact.pretty = false;
+        let mut w = LimitedWriter {
+            remaining: self.width.saturating_sub(column),
+        };
+        compact.data(&mut w, data, 0, 0).is_ok()
+    }
+}
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+enum Item<'a> {
+    Field(i16, DataRef<'a>),
+    Entry(DataRef<'a>, DataRef<'a>),
+    Element(DataRef<'a>),
+}
+
+/// A writer which fails when the written text exceeds the limit.
+struct LimitedWriter {
+    remaining: usize,
+}
+impl Write for LimitedWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.remaining = self.remaining.checked_sub(s.len()).ok_or(fmt::Error)?;
+        Ok(())
+    }
+}
+
+pub(crate) fn kind_name(kind: DataKind) -> &'static str {
+    match kind {
+        DataKind::Bool => "bool",
+        DataKind::I8 => "i8",
+        DataKind::I16 => "i16",
+        DataKind::I32 => "i32",
+        DataKind::I64 => "i64",
+        DataKind::Double => "double",
+        DataKind::Binary => "binary",
+        DataKind::Struct => "struct",
+        DataKind::Map => "map",
+        DataKind::Set => "set",
+        DataKind::List => "list",
+        DataKind::Uuid => "uuid",
+    }
+}
+
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':'))
+}
+
+macro_rules! impl_display {
+    ($($ty:ty => |$x:ident| $to_ref:expr),*) => {
+        $(
+            impl fmt::Display for $ty {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    let $x = self;
+                    Printer::from_formatter(f).write_data(f, $to_ref)
+                }
+            }
+        )*
+    };
+}
+impl_display!(
+    Data => |x| x.as_ref(),
+    DataRef<'_> => |x| *x,
+    Struct => |x| DataRef::Struct(x),
+    Map => |x| DataRef::Map(x),
+    Set => |x| DataRef::Set(x),
+    List => |x| DataRef::List(x)
+);
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer::from_formatter(f).write_message(f, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Field, Uuid};
+
+    #[test]
+    fn display_works() {
+        let mut map = Map::empty_of(DataKind::Binary, DataKind::Set);
+        track_try_unwrap!(map.insert("a", Set::from(vec![1i8, 2])));
+        track_try_unwrap!(map.insert(vec![0xffu8, 0], Set::from(Vec::<i8>::new())));
+        let s = Struct::new(vec![
+            Field::new(1, true),
+            Field::new(2, -3i16),
+            Field::new(3, i64::MAX),
+            Field::new(4, -0.5),
+            Field::new(5, "a\"b\n"),
+            Field::new(6, Uuid::new([0xab; 16])),
+            Field::new(7, map),
+            Field::new(8, List::from(vec![Struct::new(Vec::new())])),
+        ]);
+        assert_eq!(
+            s.to_string(),
+            concat!(
+                r#"struct { 1: true, 2: i16:-3, 3: i64:9223372036854775807, 4: -0.5, 5: "a\"b\n", "#,
+                "6: uuid:abababab-abab-abab-abab-abababababab, ",
+                r#"7: map<binary, set>{ "a": set<i8>[i8:1, i8:2], 0xff00: set<i8>[] }, "#,
+                "8: list<struct>[struct {}] }"
+            )
+        );
+        assert_eq!(
+            format!("{:#}", s),
+            r#"struct {
+  1: true,
+  2: i16:-3,
+  3: i64:9223372036854775807,
+  4: -0.5,
+  5: "a\"b\n",
+  6: uuid:abababab-abab-abab-abab-abababababab,
+  7: map<binary, set>{ "a": set<i8>[i8:1, i8:2], 0xff00: set<i8>[] },
+  8: list<struct>[struct {}]
+}"#
+        );
+
+        let message = Message::oneway("foo bar", -1, Struct::new(Vec::new()));
+        assert_eq!(message.to_string(), r#"oneway "foo bar" #-1 {}"#);
+    }
+}
@@ -0,0 +1,8 @@
+use thrift_codec::codegen::Builder;
+
+fn main() {
+    Builder::new()
+        .file("idl/tutorial.thrift")
+        .write_to_out_dir("thrift.rs")
+        .unwrap();
+}
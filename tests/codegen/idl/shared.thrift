@@ -0,0 +1,12 @@
+namespace rs shared
+
+enum Color {
+  RED = 1,
+  GREEN,
+  BLUE = 10,
+}
+
+struct Node {
+  1: required string label,
+  2: optional list<Node> children,
+}
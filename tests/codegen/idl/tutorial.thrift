@@ -0,0 +1,58 @@
+include "shared.thrift"
+
+typedef i32 MyInteger
+
+const i32 INT32CONSTANT = 9853
+
+// Constants whose names are Rust keywords.
+const i32 type = 1
+const string self = "x"
+const i32 TYPE_ALIAS = type
+
+enum Operation {
+  ADD = 1,
+  SUBTRACT = 2,
+}
+
+struct Work {
+  1: required MyInteger num1 = 0,
+  2: required i32 num2,
+  3: required Operation op,
+  4: optional string comment,
+  5: optional shared.Color color = shared.Color.GREEN,
+  6: optional set<string> tags,
+  7: optional map<string, shared.Node> nodes,
+  8: optional Work next,
+  9: required Work2 w2,
+}
+
+// `Work` and `Work2` refer to each other.
+struct Work2 {
+  1: optional Work back,
+  2: optional binary payload,
+}
+
+// `Left`, `Right` and `Choice` form a cycle through a union.
+struct Left {
+  1: optional Right right,
+}
+
+struct Right {
+  1: optional Choice choice,
+}
+
+union Choice {
+  1: Left left,
+  2: i64 number,
+}
+
+exception InvalidOperation {
+  1: i32 what_op,
+  2: string why,
+}
+
+service Calculator {
+  void ping(),
+  i32 calculate(1: i32 logid, 2: Work w) throws (1: InvalidOperation ouch),
+  oneway void zip(),
+}
@@ -0,0 +1,81 @@
+//! Compiles the code generated from `idl/*.thrift` and checks that it works.
+include!(concat!(env!("OUT_DIR"), "/thrift.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::shared::{Color, Node};
+    use super::tutorial::{Choice, Left, Operation, Right, Work, Work2};
+    use std::fmt::Debug;
+    use thrift_codec::{BinaryDecode, BinaryEncode, CompactDecode, CompactEncode};
+
+    fn round_trip<T>(value: &T)
+    where
+        T: BinaryEncode + BinaryDecode + CompactEncode + CompactDecode + PartialEq + Debug,
+    {
+        let mut buf = Vec::new();
+        value.binary_encode(&mut buf).unwrap();
+        assert_eq!(T::binary_decode(&mut &buf[..]).unwrap(), *value);
+
+        let mut buf = Vec::new();
+        value.compact_encode(&mut buf).unwrap();
+        assert_eq!(T::compact_decode(&mut &buf[..]).unwrap(), *value);
+    }
+
+    #[test]
+    fn keyword_constants_work() {
+        assert_eq!(super::tutorial::r#type, 1);
+        assert_eq!(super::tutorial::self_, "x");
+        assert_eq!(super::tutorial::TYPE_ALIAS, 1);
+    }
+
+    #[test]
+    fn defaults_work() {
+        let work = Work::default();
+        assert_eq!(work.num1, 0);
+        assert_eq!(work.color, Some(Color::Green));
+        assert_eq!(work.next, None);
+        assert_eq!(work.w2, Work2::default());
+        assert_eq!(Choice::default(), Choice::Left(Box::default()));
+    }
+
+    #[test]
+    fn recursive_structs_round_trip() {
+        let leaf = Work {
+            num2: 2,
+            op: Operation::Subtract,
+            comment: Some("leaf".to_owned()),
+            ..Default::default()
+        };
+        let work = Work {
+            num1: 1,
+            num2: 3,
+            op: Operation::Add,
+            tags: Some(["a".to_owned(), "b".to_owned()].into_iter().collect()),
+            nodes: Some(
+                [(
+                    "root".to_owned(),
+                    Node {
+                        label: "root".to_owned(),
+                        children: Some(vec![Node::default()]),
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            next: Some(Box::new(leaf.clone())),
+            w2: Work2 {
+                back: Some(Box::new(leaf)),
+                payload: Some(vec![0, 1, 2]),
+            },
+            ..Default::default()
+        };
+        round_trip(&work);
+
+        let choice = Choice::Left(Box::new(Left {
+            right: Some(Right {
+                choice: Some(Choice::Number(10)),
+            }),
+        }));
+        round_trip(&choice);
+    }
+}